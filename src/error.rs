@@ -0,0 +1,224 @@
+use std::fmt;
+
+/// The fields missing from a patch to be converted into its original type.
+///
+/// Nested fields are joined by a dot (e.g., `b.c`), while an unmodified
+/// optional enum is missing as a whole (i.e., has no fields).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MissingFields(Vec<String>);
+
+impl MissingFields {
+    /// No missing fields (yet).
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// The names of the missing fields.
+    pub fn fields(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Adds a missing field.
+    pub fn push(&mut self, field: impl Into<String>) {
+        self.0.push(field.into())
+    }
+
+    /// Adds the missing fields of the nested `field`.
+    pub fn nest(&mut self, field: &str, nested: Self) {
+        if nested.0.is_empty() {
+            self.push(field)
+        } else {
+            for nested in nested.0 {
+                self.push(format!("{field}.{nested}"))
+            }
+        }
+    }
+}
+
+impl fmt::Display for MissingFields {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("missing value");
+        }
+        write!(f, "missing fields: {}", self.0.join(", "))
+    }
+}
+
+impl std::error::Error for MissingFields {}
+
+/// The field an error was raised for, if any, and why.
+///
+/// Nested fields are joined by a dot (e.g., `b.c`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Context {
+    field: Option<String>,
+    reason: String,
+}
+
+impl Context {
+    fn new(reason: impl fmt::Display) -> Self {
+        Self {
+            field: None,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// The context as that of the nested `field`.
+    fn nest(mut self, field: &str) -> Self {
+        self.field = Some(match self.field {
+            Some(nested) => format!("{field}.{nested}"),
+            None => field.to_owned(),
+        });
+        self
+    }
+
+    /// Writes the reason, after the field (or `value` if none) described as `kind` and located at
+    /// `location` (e.g., "invalid field `b` at offset 2: ..." or "invalid bytes at offset 2: ...").
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+        kind: &str,
+        value: &str,
+        location: fmt::Arguments,
+    ) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "{kind} field `{field}`{location}: {}", self.reason),
+            None => write!(f, "{kind} {value}{location}: {}", self.reason),
+        }
+    }
+}
+
+/// The reason a patch was rejected by `try_patch`, and the field it was rejected for.
+///
+/// Nested fields are joined by a dot (e.g., `b.c`), while the struct-level
+/// validation (`#[wopt(validate = ...)]` on the type) rejects no field in particular.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchError(Context);
+
+impl PatchError {
+    /// A rejection for `reason`, of no field in particular (yet).
+    pub fn new(reason: impl fmt::Display) -> Self {
+        Self(Context::new(reason))
+    }
+
+    /// The name of the rejected field, if any.
+    pub fn field(&self) -> Option<&str> {
+        self.0.field.as_deref()
+    }
+
+    /// Why the field (or value) was rejected.
+    pub fn reason(&self) -> &str {
+        &self.0.reason
+    }
+
+    /// The rejection as that of the nested `field`.
+    pub fn nest(self, field: &str) -> Self {
+        Self(self.0.nest(field))
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f, "invalid", "value", format_args!(""))
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// The reason bytes couldn't be decoded, the offset they were rejected at, and the field they
+/// were rejected for.
+///
+/// Nested fields are joined by a dot (e.g., `b.c`), while the framing (e.g., the identity or the
+/// trailing bytes) belongs to no field in particular.
+#[cfg(feature = "bytemuck")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    offset: usize,
+    context: Context,
+}
+
+#[cfg(feature = "bytemuck")]
+impl DecodeError {
+    /// A rejection for `reason`, at `offset`, of no field in particular (yet).
+    pub fn new(offset: usize, reason: impl fmt::Display) -> Self {
+        Self {
+            offset,
+            context: Context::new(reason),
+        }
+    }
+
+    /// The offset of the rejected bytes.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The name of the rejected field, if any.
+    pub fn field(&self) -> Option<&str> {
+        self.context.field.as_deref()
+    }
+
+    /// Why the bytes were rejected.
+    pub fn reason(&self) -> &str {
+        &self.context.reason
+    }
+
+    /// The rejection as that of the nested `field`.
+    pub fn nest(self, field: &str) -> Self {
+        Self {
+            context: self.context.nest(field),
+            ..self
+        }
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let offset = format_args!(" at offset {}", self.offset);
+        self.context.fmt(f, "invalid", "bytes", offset)
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl std::error::Error for DecodeError {}
+
+/// The reason a value couldn't be encoded (e.g., a length overflowing its prefix), and the field
+/// it was rejected for.
+///
+/// Nested fields are joined by a dot (e.g., `b.c`).
+#[cfg(feature = "bytemuck")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodeError(Context);
+
+#[cfg(feature = "bytemuck")]
+impl EncodeError {
+    /// A rejection for `reason`, of no field in particular (yet).
+    pub fn new(reason: impl fmt::Display) -> Self {
+        Self(Context::new(reason))
+    }
+
+    /// The name of the rejected field, if any.
+    pub fn field(&self) -> Option<&str> {
+        self.0.field.as_deref()
+    }
+
+    /// Why the value was rejected.
+    pub fn reason(&self) -> &str {
+        &self.0.reason
+    }
+
+    /// The rejection as that of the nested `field`.
+    pub fn nest(self, field: &str) -> Self {
+        Self(self.0.nest(field))
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f, "unencodable", "value", format_args!(""))
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl std::error::Error for EncodeError {}
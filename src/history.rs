@@ -0,0 +1,183 @@
+use crate::{Patch, WithOpt};
+use std::collections::VecDeque;
+
+/// Undo/redo history of a value, recording the patches restoring what each patch replaced.
+///
+/// Only the patched fields are recorded, so memory grows with the modifications rather than
+/// with the size of the value:
+/// ```
+/// use wopt::*;
+///
+/// #[derive(Debug, PartialEq, WithOpt)]
+/// #[wopt(derive(Default))]
+/// struct Doc {
+///     title: u8,
+///     body: u8,
+/// }
+///
+/// let mut history = History::new(Doc { title: 1, body: 2 });
+///
+/// history.apply(&mut DocOpt { title: Some(3), ..Default::default() });
+/// assert_eq!(history.get(), &Doc { title: 3, body: 2 });
+///
+/// assert!(history.undo());
+/// assert_eq!(history.get(), &Doc { title: 1, body: 2 });
+///
+/// assert!(history.redo());
+/// assert_eq!(history.get(), &Doc { title: 3, body: 2 });
+/// ```
+pub struct History<T: WithOpt> {
+    value: T,
+    /// Steps to undo (oldest first).
+    undo: VecDeque<T::Opt>,
+    /// Steps to redo (latest undone last).
+    redo: Vec<T::Opt>,
+    /// Maximum number of steps to undo.
+    depth: usize,
+    /// The patches of the open group, merged into a single step.
+    group: Option<T::Opt>,
+    /// How many groups are open (nested groups merge into the outermost).
+    groups: usize,
+}
+
+impl<T: WithOpt> History<T> {
+    /// A history of `value`, without any depth limit.
+    pub fn new(value: T) -> Self {
+        Self::with_depth(value, usize::MAX)
+    }
+
+    /// A history of `value`, keeping up to `depth` steps to undo.
+    pub fn with_depth(value: T, depth: usize) -> Self {
+        Self {
+            value,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            depth,
+            group: None,
+            groups: 0,
+        }
+    }
+
+    /// The current value.
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Consumes the history, returning the current value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Maximum number of steps to undo.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Sets the maximum number of steps to undo, dropping the oldest ones.
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        self.truncate();
+    }
+
+    /// Patches the current value (taking `rhs`), recording the step to undo it.
+    ///
+    /// Any undone steps can't be redone anymore.
+    pub fn apply(&mut self, rhs: &mut T::Opt) {
+        if !rhs.is_modified() {
+            return;
+        }
+        let mut undo = self.value.patch_with_undo(rhs);
+        if !undo.is_modified() {
+            return;
+        }
+        self.redo.clear();
+        if self.groups == 0 {
+            self.push_undo(undo);
+            return;
+        }
+        // the latest patch is undone first
+        if let Some(group) = self.group.take() {
+            undo.merge(group);
+        }
+        self.group = Some(undo);
+    }
+
+    /// Starts recording the following patches as a single step, until [`Self::end_group`].
+    ///
+    /// Groups can be nested, in which case they're merged into the outermost one.
+    pub fn begin_group(&mut self) {
+        self.groups += 1;
+    }
+
+    /// Stops recording the current group, if any.
+    pub fn end_group(&mut self) {
+        match self.groups {
+            0 => {}
+            1 => {
+                self.groups = 0;
+                if let Some(group) = self.group.take() {
+                    self.push_undo(group);
+                }
+            }
+            _ => self.groups -= 1,
+        }
+    }
+
+    /// Whether there's a step to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.group.is_some()
+    }
+
+    /// Whether there's a step to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Undoes the latest step (ending any open groups), returning whether there was one.
+    pub fn undo(&mut self) -> bool {
+        self.end_groups();
+        let Some(mut step) = self.undo.pop_back() else {
+            return false;
+        };
+        let step = self.value.patch_with_undo(&mut step);
+        self.redo.push(step);
+        true
+    }
+
+    /// Redoes the latest undone step (ending any open groups), returning whether there was one.
+    pub fn redo(&mut self) -> bool {
+        self.end_groups();
+        let Some(mut step) = self.redo.pop() else {
+            return false;
+        };
+        let step = self.value.patch_with_undo(&mut step);
+        self.push_undo(step);
+        true
+    }
+
+    /// Forgets every step to undo or redo (ending any open groups).
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group = None;
+        self.groups = 0;
+    }
+
+    fn end_groups(&mut self) {
+        if self.groups > 0 {
+            self.groups = 1;
+            self.end_group();
+        }
+    }
+
+    fn push_undo(&mut self, step: T::Opt) {
+        self.undo.push_back(step);
+        self.truncate();
+    }
+
+    fn truncate(&mut self) {
+        while self.undo.len() > self.depth {
+            self.undo.pop_front();
+        }
+    }
+}
@@ -1,1049 +1,486 @@
-#![allow(unused_variables)]
-
-use proc_macro::{Span, TokenStream};
-use quote::quote;
-use syn::{
-    DeriveInput, Field, Fields, Ident, Index, LitStr, Meta, Path, PathSegment, Type, TypePath,
-    parse_macro_input, punctuated::Iter,
-};
-
-#[cfg(any(feature = "bf", feature = "bytemuck"))]
-use syn::{Expr, Lit};
+//! Runtime companion of the [`WithOpt`](derive@WithOpt) derive macro.
+//!
+//! Every derivation implements the traits below, so generic code can be
+//! written over any type with an optional counterpart:
+//! ```
+//! use wopt::*;
+//!
+//! fn apply<T: WithOpt>(t: &mut T, p: &mut T::Opt) {
+//!     if p.is_modified() {
+//!         t.patch(p)
+//!     }
+//! }
+//! ```
+
+mod error;
+mod history;
+mod list;
+mod map;
+mod nested;
+mod nullable;
+
+pub use error::{MissingFields, PatchError};
+pub use history::History;
+pub use list::{ListOp, ListPatch};
+pub use map::{Map, MapOp, MapPatch, Set, SetOp, SetPatch};
+pub use nested::{OptionChanges, OptionPatch};
+pub use nullable::Nullable;
+pub use wopt_derive::WithOpt;
 
 #[cfg(feature = "bytemuck")]
-use std::{
-    fs::File,
-    io::{Read, Seek, SeekFrom, Write},
-};
-
-#[cfg(all(not(feature = "bytemuck"), feature = "unchecked"))]
-compile_error!("Feature `unchecked` requires feature `bytemuck`.");
+pub use error::{DecodeError, EncodeError};
 
 #[cfg(feature = "bytemuck")]
-fn setup_counter_file() -> File {
-    let path = std::path::Path::new("target")
-        .join("tmp")
-        .join("wopt")
-        .join("counter");
+pub use wopt_derive::registry;
+
+/// A type with an optional counterpart (see [`WithOpt`](derive@WithOpt)).
+///
+/// Also implemented for `Box<T>`, `Rc<T>` and `Arc<T>` (patched in place, copy-on-write if
+/// shared), `Option<T>` (see [`OptionPatch`]), arrays and tuples, so `optional` fields may wrap
+/// their nested type.
+pub trait WithOpt: Sized {
+    /// The optional counterpart of this type.
+    type Opt: Patch;
+
+    /// Patches `self` with the modifications of `rhs`, taking them.
+    ///
+    /// The patch of an enum switching to another variant without all of its fields can't be
+    /// applied, and is left in `rhs`.
+    fn patch(&mut self, rhs: &mut Self::Opt);
+
+    /// Patches `self` like [`WithOpt::patch`], returning the patch restoring the replaced values.
+    fn patch_with_undo(&mut self, rhs: &mut Self::Opt) -> Self::Opt;
+
+    /// Converts `self` into its optional counterpart, with every field set.
+    fn into_opt(self) -> Self::Opt;
+}
 
-    // ensure directory exists
-    std::fs::create_dir_all(unsafe {
-        // SAFETY - impossible to be root/prefix and isn't empty.
-        path.parent().unwrap_unchecked()
-    })
-    .unwrap_or_else(|_| panic!("Failed to create {:?} directory.", path.parent()));
+/// The optional counterpart of a type (i.e., a patch).
+pub trait Patch: Sized {
+    /// Whether any field has been modified.
+    fn is_modified(&self) -> bool;
 
-    #[allow(clippy::suspicious_open_options)]
-    let mut f = File::options()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(&path)
-        .unwrap_or_else(|_| panic!("Failed to open {path:?}."));
+    /// Takes the modifications, leaving `self` unmodified.
+    fn take(&mut self) -> Self;
 
-    let mut s = String::new();
-    f.read_to_string(&mut s).unwrap();
-    if s.trim().is_empty() {
-        f.write_all(b"0")
-            .unwrap_or_else(|_| panic!("Failed to write to {path:?} file."));
-        f.flush().unwrap_or_default();
-    }
-    f
+    /// Merges a later patch into `self`, whose modifications take precedence.
+    ///
+    /// Patching with the merged patch is the same as patching with `self`, then `rhs`
+    /// (barring incomplete enum variants, which may be completed by the merge).
+    fn merge(&mut self, rhs: Self);
 }
 
-#[cfg(feature = "bytemuck")]
-fn next_id(f: &mut File) -> u8 {
-    f.seek(SeekFrom::Start(0)).unwrap();
+/// A type whose differences can be expressed as a patch.
+///
+/// Implemented whenever every patched field is [`PartialEq`] and [`Clone`]
+/// (or, with `optional`, is itself [`Diff`]).
+pub trait Diff: WithOpt {
+    /// The smallest patch turning `self` into `other`, i.e., with only the fields that differ.
+    fn diff(&self, other: &Self) -> Self::Opt;
+}
 
-    let mut s = String::new();
-    f.read_to_string(&mut s).unwrap();
+/// A type whose patches report the fields they changed.
+///
+/// Implemented whenever every patched field is [`PartialEq`]
+/// (or, with `optional`, is itself [`Track`]).
+pub trait Track: WithOpt {
+    /// The fields changed by a patch (and the changes of nested fields).
+    type Changes;
 
-    let current = s.trim().parse::<u8>().unwrap();
+    /// Patches `self` like [`WithOpt::patch`], returning the fields whose values changed
+    /// (and running the `on_change` callbacks).
+    fn patch_tracked(&mut self, rhs: &mut Self::Opt) -> Self::Changes;
 
-    let next: u8 = current.wrapping_add(1);
+    /// The changes of a patch, given its undo patch (i.e., the replaced values).
+    #[doc(hidden)]
+    fn changes(&self, undo: &Self::Opt) -> Self::Changes;
 
-    // overwrite with new value
-    f.set_len(0).unwrap();
-    f.seek(SeekFrom::Start(0)).unwrap();
-    f.write_all(next.to_string().as_bytes()).unwrap();
-    f.flush().unwrap();
+    /// Whether nothing changed.
+    fn is_unchanged(changes: &Self::Changes) -> bool;
+}
 
-    current
+/// Binary (de)serialization through `bytemuck`.
+#[cfg(feature = "bytemuck")]
+pub trait WireEncode: Sized {
+    /// Identity of the type, leading the serialized bytes (see [`check_ids`]).
+    ///
+    /// The inherent `ID` is typed after [`Self::ID_WIDTH`] (i.e., `u8`, `u16` or `u32` for
+    /// varints), so code building bytes from it (e.g., `[Foo::ID, ..]`) breaks whenever the width
+    /// changes, including through `WOPT_ID_WIDTH`. This one is always a `u32`.
+    const ID: u32;
+
+    /// How [`Self::ID`] is serialized (see `#[wopt(id_width = ...)]`).
+    const ID_WIDTH: IdWidth;
+
+    /// Size of the serialized fields (without padding).
+    const UNPADDED_SIZE: usize;
+
+    /// Serializes `self`, starting with [`Self::ID`], panicking if a length overflows its prefix
+    /// (see [`LenWidth`]).
+    fn serialize(&self) -> Vec<u8>;
+
+    /// Serializes `self` like [`Self::serialize`], rejecting any length overflowing its prefix.
+    fn try_serialize(&self) -> Result<Vec<u8>, EncodeError>;
+
+    /// Deserializes the body of the bytes (i.e., following [`Self::ID`], unlike
+    /// [`Self::try_deserialize`]), panicking if it's invalid. The inherent `deserialize` takes the
+    /// same bytes.
+    fn deserialize_body(bytes: &[u8]) -> Self;
+
+    /// Deserializes bytes (including [`Self::ID`], as serialized), rejecting them if they're truncated, followed by
+    /// trailing bytes, or otherwise invalid (e.g., received from an untrusted peer).
+    fn try_deserialize(bytes: &[u8]) -> Result<Self, DecodeError>;
 }
 
-fn get_opt_type(original: &Type) -> Type {
-    if let Type::Path(TypePath { path, .. }) = original
-        && let Some(last_segment) = path.segments.last()
-    {
-        let orig_ident = &last_segment.ident;
-        let new_ident = Ident::new(
-            format!("{orig_ident}Opt").as_str(),
-            Span::call_site().into(),
-        );
+/// How the identity of a type is serialized, leading its bytes (see [`WireEncode::ID`]).
+#[cfg(feature = "bytemuck")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IdWidth {
+    /// A single byte (up to 256 identities, the default for explicit ones).
+    U8,
+    /// Two bytes, little-endian.
+    U16,
+    /// LEB128 (i.e., 7 bits per byte, the high bit set on every byte but the last), the default
+    /// for hashed identities (up to 5 bytes).
+    Varint,
+}
 
-        // Construct a new path with the modified ident and same arguments
-        let new_segment = PathSegment {
-            ident: new_ident,
-            arguments: last_segment.arguments.clone(),
+#[cfg(feature = "bytemuck")]
+impl IdWidth {
+    /// Decodes the leading identity of `bytes`, along with its length.
+    pub fn decode(self, bytes: &[u8]) -> Result<(u32, usize), DecodeError> {
+        let mut tail = 0;
+        let id = match self {
+            Self::U8 => __private::read_pod::<u8>(bytes, &mut tail)? as u32,
+            Self::U16 => u16::from_le(__private::read_pod(bytes, &mut tail)?) as u32,
+            Self::Varint => __private::read_varint(bytes, &mut tail)?,
         };
-
-        let mut new_path = path.clone();
-        new_path.segments.pop();
-        new_path.segments.push(new_segment);
-
-        return Type::Path(TypePath {
-            qself: None,
-            path: new_path,
-        });
+        Ok((id, tail))
     }
-    panic!("Unexpected syn::Type variant.")
-}
 
-struct FieldAttr<'a> {
-    field_name_opt: Option<&'a Option<Ident>>,
-    field_type: &'a Type,
-    field_type_opt: Type,
-    is_vec: bool,
-    is_optional: bool,
-    is_required: bool,
-    is_skipped: bool,
-    _is_serde: bool,
-    _serde_fn: Option<[Path; 2]>,
+    /// The length of `id` once serialized.
+    pub const fn len(self, id: u32) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16 => 2,
+            Self::Varint => (32 - (id | 1).leading_zeros() as usize).div_ceil(7),
+        }
+    }
 }
 
-struct FieldAttrs<'a> {
-    attrs: Vec<FieldAttr<'a>>,
-    is_const: bool,
+/// How the lengths of variable-size fields (i.e., `Vec`s with ser/de and `list_ops`/`map_ops`
+/// patches, along with their indices) are serialized (see `#[wopt(len = ...)]`).
+#[cfg(feature = "bytemuck")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LenWidth {
+    /// A single byte (up to 255).
+    U8,
+    /// Two bytes, little-endian (up to 65,535, the default).
+    #[default]
+    U16,
+    /// Four bytes, little-endian.
+    U32,
+    /// LEB128, like [`IdWidth::Varint`] (up to `u32::MAX`).
+    Varint,
 }
 
-fn get_field_kvs(fields: Iter<Field>, is_named: bool) -> FieldAttrs {
-    let mut is_const = true;
-
-    let attrs = fields
-        .map(|field: &Field| {
-            let (mut is_vec, mut is_optional, mut is_required, mut is_skipped, mut _is_serde) =
-                Default::default();
-            let (mut ser, mut de) = Default::default();
-
-            // check for non-constant field (e.g., 'Vec')
-            if let Type::Path(path) = &field.ty
-                && path.path.segments.last().unwrap().ident == "Vec"
-            {
-                is_vec = true;
-                is_const = false;
-            }
-
-            if let Some(attr) = field.attrs.first()
-                && attr.path().is_ident("wopt")
-            {
-                attr.parse_nested_meta(|a| {
-                    if let Some(ident) = a.path.get_ident() {
-                        match ident.to_string().as_str() {
-                            "non_const" => is_const = false,
-                            "optional" => is_optional = true,
-                            "required" => is_required = true,
-                            "skip" => is_skipped = true,
-                            "serde" => _is_serde = true,
-                            "ser" => {
-                                let value = a.value()?;
-                                let s: LitStr = value.parse()?;
-                                let p = syn::parse_str::<Path>(s.value().as_str())?;
-                                ser = Some(p)
-                            }
-                            "de" => {
-                                let value = a.value()?;
-                                let s: LitStr = value.parse()?;
-                                let p = syn::parse_str::<Path>(s.value().as_str())?;
-                                de = Some(p)
-                            }
-                            attr => panic!("Unsupported attribute ({attr})."),
-                        }
-                    }
-                    Ok(())
-                })
-                .unwrap();
-
-                if is_required && is_skipped {
-                    panic!("`required` and `skip` can't be specified together.")
-                }
-            }
-
-            // determine if optional struct provided
-            let field_type = &field.ty;
-            let field_type_opt = if is_optional {
-                get_opt_type(field_type)
-            } else {
-                field_type.clone()
-            };
-
-            // override if any user-provided definitions
-            let _serde_fn = match (ser, de) {
-                (None, None) => None,
-                (Some(ser), Some(de)) => Some([ser, de]),
-                _ => panic!("Both ser/de need to be implemented."),
-            };
-
-            FieldAttr {
-                field_name_opt: is_named.then_some(&field.ident),
-                field_type,
-                field_type_opt,
-                is_vec,
-                is_optional,
-                is_required,
-                is_skipped,
-                _is_serde,
-                _serde_fn,
-            }
-        })
-        .collect::<Vec<_>>();
-    FieldAttrs { attrs, is_const }
+/// Checks at compile time that the given [`WireEncode`] types have distinct identities, e.g., those
+/// derived in different crates (deriving the same one twice in a crate failing to compile anyway).
+///
+/// ```
+/// use wopt::*;
+///
+/// #[derive(WithOpt)]
+/// #[wopt(id = 1)]
+/// struct Foo(u8);
+///
+/// #[derive(WithOpt)]
+/// #[wopt(id = 2)]
+/// struct Bar(u16);
+///
+/// check_ids!(Foo, FooOpt, Bar, BarOpt);
+/// ```
+#[cfg(feature = "bytemuck")]
+#[macro_export]
+macro_rules! check_ids {
+    ($($ty:ty),* $(,)?) => {
+        const _: () = $crate::__private::check_ids(
+            &[$(<$ty as $crate::WireEncode>::ID),*],
+            &[$(stringify!($ty)),*],
+        );
+    };
 }
 
-#[proc_macro_derive(WithOpt, attributes(id, wopt))]
-pub fn wopt_derive(input: TokenStream) -> TokenStream {
-    // parse the input tokens into a syntax tree
-    let input = parse_macro_input!(input as DeriveInput);
-
-    // get the struct name
-    let name = &input.ident;
+/// Dependencies of the generated code.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::nested::Nested;
+    pub use bitflags;
 
-    // identity of this optional struct
     #[cfg(feature = "bytemuck")]
-    let mut id = None;
+    pub use crate::nested::NestedWire;
 
-    #[allow(unused_mut)]
-    let mut is_unit = false;
+    #[cfg(feature = "bytemuck")]
+    pub use bytemuck;
 
-    // the type of struct
-    let mut is_named = false;
+    #[cfg(feature = "bytemuck")]
+    use crate::{DecodeError, EncodeError, LenWidth};
 
-    // match on the fields of the struct
-    let info = if let syn::Data::Struct(ref data) = input.data {
-        match &data.fields {
-            Fields::Named(fields) => {
-                is_named = true;
-                get_field_kvs(fields.named.iter(), true)
-            }
-            Fields::Unnamed(fields) => get_field_kvs(fields.unnamed.iter(), false),
-            _ => {
-                #[cfg(not(feature = "bytemuck"))]
-                panic!("Unit structs are only supported with the `bytemuck` feature.");
+    #[cfg(feature = "bytemuck")]
+    pub use crate::list::{de_list, de_list_patch, ser_list, ser_list_patch};
 
-                #[cfg(feature = "bytemuck")]
-                {
-                    is_unit = true;
-                    FieldAttrs {
-                        attrs: Vec::new(),
-                        is_const: true,
-                    }
-                }
-            }
-        }
-    } else {
-        panic!("Only structs are supported");
+    #[cfg(feature = "bytemuck")]
+    pub use crate::map::{
+        de_map, de_map_patch, de_set, de_set_patch, ser_map, ser_map_patch, ser_set, ser_set_patch,
     };
 
-    if info.attrs.is_empty() && !is_unit {
-        panic!("Must have at least 1 field.")
-    }
-
-    let mut derives = Vec::new();
-    let mut _no_serde = false;
-
-    // process any `#[wopt(...)]` attributes
-    for attr in &input.attrs {
-        if attr.path().is_ident("wopt") {
-            let meta = attr.parse_args::<Meta>().unwrap();
-
-            match &meta {
-                Meta::Path(path) => {
-                    if !path.is_ident("no_serde") {
-                        panic!("Only 'no_serde' path meta is supported.")
-                    }
-                    _no_serde = true
-                }
-
-                Meta::List(list) => {
-                    if !list.path.is_ident("derive") {
-                        panic!("Only 'derive' list meta is supported.")
-                    }
-
-                    list.parse_nested_meta(|a| {
-                        if let Some(ident) = a.path.get_ident() {
-                            derives.push(quote! { #ident });
-                        }
-                        Ok(())
-                    })
-                    .unwrap();
-                }
-                Meta::NameValue(nv) => {
-                    if nv.path.is_ident("id") {
-                        #[cfg(not(feature = "bytemuck"))]
-                        panic!("Enable the `bytemuck` feature to use the `id` attribute.");
-
-                        #[cfg(feature = "bytemuck")]
-                        {
-                            id = Some(match &nv.value {
-                                Expr::Lit(expr) => match &expr.lit {
-                                    Lit::Int(v) => {
-                                        let value = v
-                                            .base10_parse::<u8>()
-                                            .expect("Only `u8` is supported.");
-                                        if value > 127 {
-                                            panic!("Value too large (max: 127)")
-                                        }
-                                        value
-                                    }
-                                    _ => panic!("Expected integer literal."),
-                                },
-                                _ => panic!("Expected literal expression."),
-                            });
-                            continue;
+    /// Panics naming the first type whose identity equals that of a previous one, along with the
+    /// latter (see `check_ids!`).
+    #[cfg(feature = "bytemuck")]
+    pub const fn check_ids(ids: &[u32], names: &[&str]) {
+        let mut i = 0;
+        while i < ids.len() {
+            let mut j = 0;
+            while j < i {
+                if ids[i] == ids[j] {
+                    let parts = [
+                        "`",
+                        names[i],
+                        "` has the same ID as `",
+                        names[j],
+                        "` (see `#[wopt(id = ...)]`).",
+                    ];
+                    // messages of const panics can't be formatted, only concatenated
+                    let (mut message, mut len) = ([0; 512], 0);
+                    let mut p = 0;
+                    while p < parts.len() {
+                        let bytes = parts[p].as_bytes();
+                        let mut b = 0;
+                        while b < bytes.len() && len < message.len() {
+                            message[len] = bytes[b];
+                            (len, b) = (len + 1, b + 1);
                         }
+                        p += 1;
                     }
-                    if nv.path.is_ident("bf") {
-                        #[cfg(not(feature = "bf"))]
-                        panic!("Enable the `bf` feature to use brainfuck.");
-
-                        #[cfg(feature = "bf")]
-                        {
-                            let code = match &nv.value {
-                                Expr::Lit(expr) => match &expr.lit {
-                                    Lit::Str(s) => s.value(),
-                                    _ => panic!("Expected string literal."),
-                                },
-                                _ => panic!("Expected literal expression."),
-                            };
-
-                            let s = bf2s::bf_to_str(&code);
-                            derives.extend(s.split_whitespace().map(|p| {
-                                let p = Ident::new(p, Span::call_site().into());
-                                quote! { #p }
-                            }));
-                            continue;
-                        }
+                    match core::str::from_utf8(message.split_at(len).0) {
+                        Ok(message) => panic!("{}", message),
+                        Err(_) => panic!("Two types have the same ID (see `#[wopt(id = ...)]`)."),
                     }
-                    panic!("Unsupported attribute.")
                 }
+                j += 1;
             }
+            i += 1;
         }
     }
+
+    /// Panics with the message of the first identity width different from the first one (see
+    /// `registry!`).
     #[cfg(feature = "bytemuck")]
-    if !is_unit {
-        derives.extend([quote! { ::enum_unit::EnumUnit }]);
+    pub const fn check_id_widths(widths: &[crate::IdWidth], messages: &[&str]) {
+        let mut i = 1;
+        while i < widths.len() {
+            if widths[i] as u8 != widths[0] as u8 {
+                panic!("{}", messages[i])
+            }
+            i += 1;
+        }
     }
 
-    let opt_name = if is_unit {
-        name.clone()
-    } else {
-        Ident::new(&format!("{name}Opt"), name.span())
-    };
-
-    #[cfg(feature = "bytemuck")]
-    let unit = Ident::new(&format!("{opt_name}Unit"), Span::call_site().into());
+    /// The type of the value of an `Option` (for `#[wopt(nullable)]` fields).
+    pub trait OptionType {
+        type Inner;
+    }
 
-    let mut field_struct_new = Vec::new();
+    impl<T> OptionType for Option<T> {
+        type Inner = T;
+    }
 
+    /// Serializes an `Option` as its tag, followed by its value (zeroed if `None`).
     #[cfg(feature = "bytemuck")]
-    let mut field_serialization = Vec::new();
+    pub fn ser_nullable<T: bytemuck::Pod>(value: &Option<T>) -> Vec<u8> {
+        let mut data = Vec::with_capacity(1 + core::mem::size_of::<T>());
+        match value {
+            Some(value) => {
+                data.push(1);
+                data.extend_from_slice(bytemuck::bytes_of(value))
+            }
+            None => data.resize(1 + core::mem::size_of::<T>(), 0),
+        }
+        data
+    }
 
+    /// Deserializes an `Option` serialized by [`ser_nullable`], starting at `tail` (moved past it).
     #[cfg(feature = "bytemuck")]
-    let mut field_deserialization = Vec::new();
+    pub fn de_nullable<T: bytemuck::Pod>(
+        bytes: &[u8],
+        tail: &mut usize,
+    ) -> Result<Option<T>, DecodeError> {
+        let offset = *tail;
+        let tag = read_pod::<u8>(bytes, tail)?;
+        let value = read_pod::<T>(bytes, tail)?;
+        match tag {
+            0 => Ok(None),
+            1 => Ok(Some(value)),
+            tag => Err(DecodeError::new(offset, format!("unknown tag `{tag}`"))),
+        }
+    }
 
+    /// Serializes `value` without its identity (e.g., nested within another value).
     #[cfg(feature = "bytemuck")]
-    let mut field_serialization_opt = Vec::new();
+    pub fn ser_fields<T: crate::WireEncode>(value: &T) -> Result<Vec<u8>, EncodeError> {
+        let mut data = value.try_serialize()?;
+        data.drain(..T::ID_WIDTH.len(T::ID));
+        Ok(data)
+    }
 
+    /// Serializes a length (or an index) as `width`, rejecting it if it overflows.
     #[cfg(feature = "bytemuck")]
-    let mut field_deserialization_opt = Vec::new();
-
-    let mut fields = Vec::new();
-    let mut upts = Vec::new();
-    let mut mods = Vec::new();
-    let mut take = Vec::new();
-    let mut into = Vec::new();
-
-    let mut size = Vec::new();
-    let mut size_opt = Vec::new();
-
-    #[cfg(all(feature = "bytemuck", not(feature = "unchecked")))]
-    let unwrap = Ident::new("unwrap", Span::call_site().into());
-
-    #[cfg(all(feature = "bytemuck", feature = "unchecked"))]
-    let unwrap = Ident::new("unwrap_unchecked", Span::call_site().into());
-
-    let is_const = info.is_const;
-    let mut has_optional = false;
-
-    for (
-        i,
-        FieldAttr {
-            field_name_opt,
-            field_type,
-            field_type_opt,
-            is_vec,
-            is_optional,
-            is_required,
-            is_skipped,
-            _is_serde,
-            ref _serde_fn,
-        },
-    ) in info.attrs.into_iter().enumerate()
-    {
-        let (size_of, size_of_opt) = if _is_serde {
-            (
-                quote! { #field_type::UNPADDED_SIZE },
-                quote! { 1 + #field_type_opt::UNPADDED_SIZE },
-            )
-        } else {
-            (
-                quote! { ::core::mem::size_of::<#field_type>() },
-                quote! { ::core::mem::size_of::<#field_type_opt>() },
-            )
-        };
-
-        if is_optional {
-            has_optional = true
-        }
-
-        let [method_ser, method_de_part] = if is_const {
-            let ser = quote! {
-                h = t; // PUT THIS AT THE END??
-                t += #size_of;
-                data[h..t].copy_from_slice(field_data)
-            };
-            let de = quote! {
-                h = t;
-                t += #size_of;
-            };
-            [ser, de]
-        } else {
-            let ser = quote! {
-                data.extend_from_slice(field_data)
-            };
-            let de = if is_vec {
-                quote! {
-                    h = t + 2;
-                    t += u16::from_le_bytes([bytes[t], bytes[t + 1]]) as usize /* ::core::mem::size_of::<VEC_VALUE_TYPE>() */ + 2;
-                }
-            } else {
-                quote! {
-                    h = t;
-                    t += #size_of;
-                }
-            };
-            [ser, de]
-        };
-
-        let [method_ser_opt, method_de_part_opt] = {
-            let ser = quote! {
-                data.extend_from_slice(field_data)
-            };
-            let de = if is_vec {
-                quote! {
-                    h = t + 2;
-                    t += u16::from_le_bytes([bytes[t], bytes[t + 1]]) as usize /* ::core::mem::size_of::<VEC_VALUE_TYPE>() */ + 2;
-                }
-            } else {
-                quote! {
-                    h = t;
-                    t += #size_of_opt;
-                }
-            };
-            [ser, de]
-        };
-
-        if let Some(field_name) = field_name_opt.cloned().map(|o| o.unwrap()) {
-            field_struct_new.push(quote! { #field_name });
-
-            let fix_len = if is_vec {
-                quote! { data.extend_from_slice((self.#field_name.len() as u16).to_le_bytes().as_slice()); }
-            } else {
-                quote! {}
-            };
-
-            #[cfg(feature = "bytemuck")]
-            {
-                if let Some([ser, de]) = _serde_fn {
-                    field_serialization.push(quote! {
-                        #fix_len;
-                        let field_data = #ser(&self.#field_name).as_ref();
-                        #method_ser;
-                    });
-                    field_deserialization.push(quote! {
-                        #method_de_part;
-                        let #field_name = #de(&bytes[h..t]);
-                    });
-                } else {
-                    field_serialization.push(if _is_serde {
-                        quote! {
-                            let field_data = &self.#field_name.serialize()[1..];
-                            #method_ser;
-                        }
-                    } else {
-                        quote! {
-                            let field_data = ::bytemuck::bytes_of(&self.#field_name);
-                            #method_ser;
-                        }
-                    });
-                    field_deserialization.push(if _is_serde {
-                        quote! {
-                            #method_de_part;
-                            let #field_name = #field_type::deserialize(&bytes[h..t]);
-                        }
-                    } else {
-                        quote! {
-                            #method_de_part;
-                            let #field_name = ::bytemuck::pod_read_unaligned(&bytes[h..t]);
-                        }
-                    });
-                }
+    pub fn write_len(len: usize, width: LenWidth, data: &mut Vec<u8>) -> Result<(), EncodeError> {
+        let overflow = |ty: &str| EncodeError::new(format!("{len} overflows `{ty}`"));
+        match width {
+            LenWidth::U8 => data.push(u8::try_from(len).map_err(|_| overflow("u8"))?),
+            LenWidth::U16 => {
+                let len = u16::try_from(len).map_err(|_| overflow("u16"))?;
+                data.extend_from_slice(&len.to_le_bytes())
             }
-
-            if is_skipped {
-                continue;
+            LenWidth::U32 => {
+                let len = u32::try_from(len).map_err(|_| overflow("u32"))?;
+                data.extend_from_slice(&len.to_le_bytes())
             }
-
-            if is_required {
-                #[cfg(feature = "bytemuck")]
-                {
-                    if let Some([ser, de]) = _serde_fn {
-                        field_serialization_opt.push(quote! {
-                            #fix_len;
-                            let field_data = #ser(&self.#field_name).as_ref();
-                            #method_ser_opt;
-                        });
-                        field_deserialization_opt.push(quote! {
-                            #method_de_part_opt;
-                            new.#field_name = #de(&bytes[h..t]);
-                        });
-                    } else {
-                        field_serialization_opt.push(if _is_serde {
-                            quote! {
-                                let field_data = &self.#field_name.serialize()[1..];
-                                #method_ser_opt;
-                            }
-                        } else {
-                            quote! {
-                                data.extend_from_slice(::bytemuck::bytes_of(&self.#field_name));
-                            }
-                        });
-                        field_deserialization_opt.push(if _is_serde {
-                            quote! {
-                                h = t;
-                                t += #size_of;
-                                new.#field_name = #field_type_opt::deserialize(&bytes[h..t]);
-                            }
-                        } else {
-                            quote! {
-                                h = t;
-                                t += #size_of;
-                                new.#field_name = ::bytemuck::pod_read_unaligned(&bytes[h..t]);
-                            }
-                        });
-                    }
-                }
-                fields.push(quote! { pub #field_name: #field_type_opt });
-                take.push(quote! { #field_name: self.#field_name });
-                into.push(quote! { #field_name: self.#field_name });
-            } else {
-                #[cfg(feature = "bytemuck")]
-                if !is_unit {
-                    let unit_name = Ident::new(
-                        &convert_case::Casing::to_case(
-                            &field_name.to_string(),
-                            convert_case::Case::Pascal,
-                        ),
-                        Span::call_site().into(),
-                    );
-
-                    let fix_len = if is_vec {
-                        quote! { data.extend_from_slice((val.len() as u16).to_le_bytes().as_slice()); }
-                    } else {
-                        quote! {}
-                    };
-
-                    if let Some([ser, de]) = _serde_fn {
-                        field_serialization_opt.push(quote! {
-                            if let Some(val) = self.#field_name.as_ref() {
-                                #fix_len;
-                                let field_data = #ser(val).as_ref();
-                                mask |= #unit::#unit_name;
-                                #method_ser_opt;
-                            }
-                        });
-                        field_deserialization_opt.push(quote! {
-                            #method_de_part_opt;
-                            new.#field_name = Some(#de(&bytes[h..t]));
-                        });
-                    } else {
-                        field_serialization_opt.push(if is_optional {
-                            quote! {
-                                if self.#field_name.is_modified() {
-                                    mask |= #unit::#unit_name;
-                                    data.extend_from_slice(&self.#field_name.serialize()[1..]);
-                                }
-                            }
-                        } else {
-                            quote! {
-                                if let Some(val) = self.#field_name.as_ref() {
-                                    let field_data = ::bytemuck::bytes_of(val);
-                                    mask |= #unit::#unit_name;
-                                    #method_ser_opt;
-                                }
-                            }
-                        });
-
-                        field_deserialization_opt.push(if is_optional {
-                            quote! {
-                                if mask.contains(#unit::#unit_name) {
-                                    h = t;
-                                    new.#field_name = #field_type_opt::deserialize_with(bytes, &mut h, &mut t);
-                                }
-                            }
-                        } else {
-                            quote! {
-                                if mask.contains(#unit::#unit_name) {
-                                    #method_de_part_opt;
-                                    new.#field_name = Some(::bytemuck::pod_read_unaligned(&bytes[h..t]));
-                                }
-                            }
-                        });
-                    }
-                }
-                fields.push(if is_optional {
-                    quote! { pub #field_name: #field_type_opt }
-                } else {
-                    quote! { pub #field_name: Option<#field_type_opt> }
-                });
-                upts.push(if is_optional {
-                    quote! { if rhs.#field_name.is_modified() {
-                        self.#field_name.patch(&mut rhs.#field_name)
-                    } }
-                } else {
-                    quote! { if let Some(#field_name) = rhs.#field_name {
-                        self.#field_name = #field_name
-                    } }
-                });
-                mods.push(if is_optional {
-                    quote! { self.#field_name.is_modified() }
-                } else {
-                    quote! { self.#field_name.is_some() }
-                });
-                take.push(quote! { #field_name: self.#field_name.take() });
-                into.push(if is_optional {
-                    quote! { #field_name: self.#field_name.into_opt() }
-                } else {
-                    quote! { #field_name: Some(self.#field_name) }
-                });
+            LenWidth::Varint => {
+                write_varint(u32::try_from(len).map_err(|_| overflow("u32"))?, data)
             }
-        } else {
-            let index = Index::from(i);
-            let var = Ident::new(&format!("_{i}"), Span::call_site().into());
-
-            let fix_len = if is_vec {
-                quote! { data.extend_from_slice((self.#index.len() as u16).to_le_bytes().as_slice()); }
-            } else {
-                quote! {}
-            };
-
-            let fix_len_opt = if is_vec {
-                quote! {
-                let size = if let Some(val) = &self.#index {
-                    (val.len() as u16).to_le_bytes()
-                } else {
-                    [0; 2]
-                };
-                data.extend_from_slice(size.as_slice()) }
-            } else {
-                quote! {}
-            };
-
-            field_struct_new.push(quote! { #index: #var });
-
-            #[cfg(feature = "bytemuck")]
-            {
-                if let Some([ser, de]) = _serde_fn {
-                    field_serialization.push(quote! {
-                        #fix_len;
-                        let field_data = #ser(&self.#index).as_ref();
-                        #method_ser;
-                    });
-                    field_deserialization.push(quote! {
-                        #method_de_part;
-                        let #var = #de(&bytes[h..t]);
-                    });
-                } else {
-                    field_serialization.push(if _is_serde {
-                        quote! {
-                            let field_data = &self.#index.serialize()[1..];
-                            #method_ser;
-                        }
-                    } else {
-                        quote! {
-                            let field_data = ::bytemuck::bytes_of(&self.#index);
-                            #method_ser;
-                        }
-                    });
-                    field_deserialization.push(if _is_serde {
-                        quote! {
-                            #method_de_part;
-                            let #var = #field_type::deserialize(&bytes[h..t]);
-                        }
-                    } else {
-                        quote! {
-                            #method_de_part;
-                            let #var = ::bytemuck::pod_read_unaligned(&bytes[h..t]);
-                        }
-                    });
-                }
-            }
-
-            if is_skipped {
-                continue;
-            }
-
-            if is_required {
-                #[cfg(feature = "bytemuck")]
-                if let Some([ser, de]) = _serde_fn {
-                    field_serialization_opt.push(quote! {
-                        #fix_len;
-                        let field_data = #ser(&self.#index).as_ref();
-                        #method_ser_opt;
-                    });
-                    field_deserialization_opt.push(quote! {
-                        #method_de_part_opt;
-                        new.#index = #de(&bytes[h..t]);
-                    });
-                } else {
-                    field_serialization_opt.push(if _is_serde {
-                        quote! {
-                            let field_data = &self.#index.serialize()[1..];
-                            #method_ser_opt;
-                        }
-                    } else {
-                        quote! {
-                            let field_data = ::bytemuck::bytes_of(&self.#index);
-                            #method_ser_opt;
-                        }
-                    });
-                    field_deserialization_opt.push(if _is_serde {
-                        quote! {
-                            #method_de_part_opt; // TODO - normal `#size_of`??
-                            new.#index = #field_type_opt::deserialize(&bytes[h..t]);
-                        }
-                    } else {
-                        quote! {
-                            #method_de_part_opt; // TODO - normal `#size_of`??
-                            new.#index = ::bytemuck::pod_read_unaligned(&bytes[h..t]);
-                        }
-                    });
-                }
-                fields.push(quote! { pub #field_type_opt });
-                take.push(quote! { #index: self.#index });
-                into.push(quote! { #index: self.#index });
-            } else {
-                #[cfg(feature = "bytemuck")]
-                if !is_unit {
-                    let unit_name = Ident::new(
-                        &format!("{}{}", enum_unit_core::prefix(), i),
-                        Span::call_site().into(),
-                    );
-
-                    if let Some([ser, de]) = _serde_fn {
-                        field_serialization_opt.push(quote! {
-                            if let Some(val) = self.#index.as_ref() {
-                                #fix_len_opt;
-                                let field_data = #ser(val).as_ref();
-                                mask |= #unit::#unit_name;
-                                #method_ser_opt;
-                            }
-                        });
-                        field_deserialization_opt.push(quote! {
-                            #method_de_part_opt;
-                            new.#index = Some(#de(&bytes[h..t]));
-                        });
-                    } else {
-                        field_serialization_opt.push(if is_optional {
-                            quote! {
-                                if self.#index.is_modified() {
-                                    mask |= #unit::#unit_name;
-                                    data.extend_from_slice(&self.#index.serialize()[1..]);
-                                }
-                            }
-                        } else {
-                            quote! {
-                                if let Some(val) = self.#index.as_ref() {
-                                    let field_data = ::bytemuck::bytes_of(val);
-                                    mask |= #unit::#unit_name;
-                                    #method_ser_opt;
-                                }
-                            }
-                        });
-
-                        field_deserialization_opt.push(if is_optional {
-                            quote! {
-                                if mask.contains(#unit::#unit_name) {
-                                    h = t;
-                                    new.#index = #field_type_opt::deserialize_with(bytes, &mut h, &mut t)
-                                }
-                            }
-                        } else {
-                            quote! {
-                                if mask.contains(#unit::#unit_name) {
-                                    #method_de_part_opt;
-                                    new.#index = Some(::bytemuck::pod_read_unaligned(&bytes[h..t]));
-                                }
-                            }
-                        });
-                    }
-                }
-                fields.push(if is_optional {
-                    quote! { pub #field_type_opt }
-                } else {
-                    quote! { pub Option<#field_type_opt> }
-                });
+        }
+        Ok(())
+    }
 
-                upts.push(if is_optional {
-                    quote! { if rhs.#index.is_modified() {
-                        self.#index.patch(&mut rhs.#index)
-                    } }
-                } else {
-                    quote! { if let Some(#var) = rhs.#index {
-                        self.#index = #var
-                    } }
-                });
-                mods.push(if is_optional {
-                    quote! { self.#index.is_modified() }
-                } else {
-                    quote! { self.#index.is_some() }
-                });
-                take.push(quote! { #index: self.#index.take() });
+    /// Reads a length (or an index) serialized by [`write_len`], starting at `tail` (moved past it).
+    #[cfg(feature = "bytemuck")]
+    pub fn read_len(bytes: &[u8], tail: &mut usize, width: LenWidth) -> Result<usize, DecodeError> {
+        Ok(match width {
+            LenWidth::U8 => read_pod::<u8>(bytes, tail)? as usize,
+            LenWidth::U16 => u16::from_le(read_pod(bytes, tail)?) as usize,
+            LenWidth::U32 => u32::from_le(read_pod(bytes, tail)?) as usize,
+            LenWidth::Varint => read_varint(bytes, tail)? as usize,
+        })
+    }
 
-                into.push(if is_optional {
-                    quote! { #index: self.#index.into_opt() }
-                } else {
-                    quote! { #index: Some(self.#index) }
-                });
-            }
-        };
-        size.push(size_of);
-        size_opt.push(size_of_opt);
+    /// Serializes `field_data` prefixed by its length (as `width`).
+    #[cfg(feature = "bytemuck")]
+    pub fn write_prefixed(
+        field_data: &[u8],
+        width: LenWidth,
+        data: &mut Vec<u8>,
+    ) -> Result<(), EncodeError> {
+        write_len(field_data.len(), width, data)?;
+        data.extend_from_slice(field_data);
+        Ok(())
     }
 
+    /// Reads the next `len` bytes, starting at `tail` (moved past them).
     #[cfg(feature = "bytemuck")]
-    let mut f = setup_counter_file();
+    pub fn read<'a>(
+        bytes: &'a [u8],
+        tail: &mut usize,
+        len: usize,
+    ) -> Result<&'a [u8], DecodeError> {
+        let rest = bytes.get(*tail..).unwrap_or_default();
+        let read = rest.get(..len).ok_or_else(|| {
+            DecodeError::new(*tail, format!("expected {len} bytes, found {}", rest.len()))
+        })?;
+        *tail += len;
+        Ok(read)
+    }
 
+    /// Reads a value of `T`, starting at `tail` (moved past it).
     #[cfg(feature = "bytemuck")]
-    let id_og = id.unwrap_or(next_id(&mut f));
+    pub fn read_pod<T: bytemuck::Pod>(bytes: &[u8], tail: &mut usize) -> Result<T, DecodeError> {
+        read(bytes, tail, core::mem::size_of::<T>()).map(bytemuck::pod_read_unaligned)
+    }
 
+    /// Reads bytes serialized by [`write_prefixed`], starting at `tail` (moved past them).
     #[cfg(feature = "bytemuck")]
-    let (serde_og, serde_opt) = if is_unit {
-        let serde = quote! {
-            pub const fn serialize() -> [u8; 1] {
-                [#id_og]
-            }
-        };
-        (serde, quote! {})
-    } else {
-        let serde_og = if _no_serde {
-            quote! {}
-        } else {
-            let ser = if is_const {
-                quote! {
-                    pub fn serialize(&self) -> [u8; 1 + Self::UNPADDED_SIZE] {
-                        let mut data = [0; 1 + Self::UNPADDED_SIZE];
-                        let [mut h, mut t] = [0, ::core::mem::size_of_val(&#id_og)];
-                        data[0] = #id_og;
-                        #(#field_serialization)*
-                        data
-                    }
-                }
-            } else {
-                quote! {
-                    pub fn serialize(&self) -> Vec<u8> {
-                        let mut data = Vec::with_capacity(1 + Self::UNPADDED_SIZE);
-                        data.push(#id_og);
-                        #(#field_serialization)*
-                        data
-                    }
-                }
-            };
-            let de = quote! {
-                pub fn deserialize(bytes: &[u8]) -> Self {
-                    let [mut h, mut t] = [0; 2];
-                    #(#field_deserialization)*
-                    Self { #(#field_struct_new),* }
-                }
-            };
-            quote! {
-                pub const ID: u8 = #id_og;
+    pub fn read_prefixed<'a>(
+        bytes: &'a [u8],
+        tail: &mut usize,
+        width: LenWidth,
+    ) -> Result<&'a [u8], DecodeError> {
+        let offset = *tail;
+        let len = read_len(bytes, tail, width)?;
+        let rest = bytes.len() - *tail;
+        if len > rest {
+            return Err(DecodeError::new(
+                offset,
+                format!("length prefix {len} exceeds the remaining {rest} bytes"),
+            ));
+        }
+        read(bytes, tail, len)
+    }
 
-                #ser
-                #de
-            }
-        };
+    /// The value of trusted bytes, panicking if they couldn't be decoded (or, with feature
+    /// `unchecked`, assuming they could).
+    #[cfg(feature = "bytemuck")]
+    pub fn decoded<T>(result: Result<T, DecodeError>) -> T {
+        #[cfg(not(feature = "unchecked"))]
+        return result.unwrap_or_else(|e| panic!("{e}"));
 
-        let try_into = quote! { mask_bytes.try_into().#unwrap() };
         #[cfg(feature = "unchecked")]
-        let try_into = quote! {
-            unsafe { #try_into }
-        };
-
-        let id_opt = next_id(&mut f);
-
-        let serde_opt = quote! {
-            pub const ID: u8 = #id_opt;
-
-            pub fn serialize(&self) -> Vec<u8> {
-                let mut data = Vec::with_capacity(
-                    1                               +   // identity byte
-                    ::core::mem::size_of::<#unit>() +   // bitmask data
-                    Self::UNPADDED_SIZE                 // field(s) data
-                );
-                data.push(#id_opt);
-                let mut mask = #unit::empty();
-                #(#field_serialization_opt)*
-                data.splice(1..1, mask.bits().to_le_bytes());
-                data
-            }
-
-            pub fn deserialize_with(bytes: &[u8], head: &mut usize, tail: &mut usize) -> Self {
-                let mut h = *head;
-                let mut t = h + ::core::mem::size_of::<#unit>();
-                let mut new = Self::default();
-                let mask_bytes = &bytes[h..t];
-                let mask_bits = <#unit as ::bitflags::Flags>::Bits::from_le_bytes(#try_into);
-                let mask = #unit::from_bits_retain(mask_bits);
-                #(#field_deserialization_opt)*
-                *head = h;
-                *tail = t;
-                new
-            }
-
-            pub fn deserialize(bytes: &[u8]) -> Self {
-                let mut new = Self::default();
-                let [mut h, mut t] = [0, ::core::mem::size_of::<#unit>()];
-                let mask_bytes = &bytes[..t];
-                let mask_bits = <#unit as ::bitflags::Flags>::Bits::from_le_bytes(#try_into);
-                let mask = #unit::from_bits_retain(mask_bits);
-                #(#field_deserialization_opt)*
-                new
-            }
-        };
-        (serde_og, serde_opt)
-    };
+        unsafe {
+            result.unwrap_unchecked()
+        }
+    }
 
-    // this is just filthy
-    if is_unit {
-        #[cfg(not(feature = "bytemuck"))]
-        return quote! {}.into();
+    /// The serialized bytes, panicking if a length overflowed its prefix.
+    #[cfg(feature = "bytemuck")]
+    pub fn encoded<T>(result: Result<T, EncodeError>) -> T {
+        result.unwrap_or_else(|e| panic!("{e}"))
+    }
 
-        #[cfg(feature = "bytemuck")]
-        return quote! {
-            impl #name {
-                pub const ID: u8 = #id_og;
-                #serde_og
-            }
+    /// Checks that `bytes` start with `id`, returning the offset past it.
+    #[cfg(feature = "bytemuck")]
+    pub fn check_id(bytes: &[u8], id: u32, width: crate::IdWidth) -> Result<usize, DecodeError> {
+        match width.decode(bytes)? {
+            (found, tail) if found == id => Ok(tail),
+            (found, _) => Err(DecodeError::new(
+                0,
+                format!("expected ID {id}, found {found}"),
+            )),
         }
-        .into();
     }
 
-    // generate the new struct
-    let structure = if is_named {
-        quote! {
-            #[derive(#(#derives),*)]
-            pub struct #opt_name {
-                #(#fields),*
+    /// Reads a LEB128 `u32`, starting at `tail` (moved past it).
+    #[cfg(feature = "bytemuck")]
+    pub fn read_varint(bytes: &[u8], tail: &mut usize) -> Result<u32, DecodeError> {
+        let offset = *tail;
+        let mut value = 0u64;
+        for shift in (0..35).step_by(7) {
+            let byte = read_pod::<u8>(bytes, tail)?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return u32::try_from(value)
+                    .map_err(|_| DecodeError::new(offset, "varint overflows `u32`"));
             }
         }
-    } else if is_unit {
-        quote! {}
-    } else {
-        quote! {
-            #[derive(#(#derives),*)]
-            pub struct #opt_name(#(#fields),*);
-        }
-    };
-
-    let (impl_name, impl_name_opt) = if upts.is_empty() || is_unit {
-        Default::default()
-    } else {
-        let let_stmt = if has_optional {
-            quote! { let mut rhs = rhs.take(); }
-        } else {
-            quote! { let rhs = rhs.take(); }
-        };
-        let patch = quote! {
-            pub fn patch(&mut self, rhs: &mut #opt_name) {
-                #let_stmt
-                #(#upts)*
-            }
-        };
-        let into_opt = if is_const {
-            quote! {
-                pub const fn into_opt(self) -> #opt_name {
-                    #opt_name { #(#into),* }
-                }
-            }
-        } else {
-            quote! {
-                pub fn into_opt(self) -> #opt_name {
-                    #opt_name { #(#into),* }
-                }
-            }
-        };
-        let is_modified = quote! {
-            pub const fn is_modified(&self) -> bool {
-                #(#mods)||*
-            }
-        };
-        let take = quote! {
-            pub const fn take(&mut self) -> Self {
-                Self { #(#take),* }
-            }
-        };
-        (
-            quote! {
-                #patch
-                #into_opt
-            },
-            quote! {
-                #is_modified
-                #take
-            },
-        )
-    };
+        Err(DecodeError::new(offset, "varint overflows `u32`"))
+    }
 
+    /// Serializes a LEB128 `u32`.
     #[cfg(feature = "bytemuck")]
-    let impl_name = quote! {
-        pub const UNPADDED_SIZE: usize = #(#size)+*;
-
-        #impl_name
-        #serde_og
-    };
-    let impl_name = quote! {
-        impl #name {
-            #impl_name
+    pub fn write_varint(mut value: u32, data: &mut Vec<u8>) {
+        while value >= 0x80 {
+            data.push(value as u8 | 0x80);
+            value >>= 7;
         }
-    };
+        data.push(value as u8)
+    }
 
+    /// Checks that nothing follows `tail`.
     #[cfg(feature = "bytemuck")]
-    let impl_name_opt = quote! {
-        pub const UNPADDED_SIZE: usize = #(#size)+*;
-
-        #impl_name_opt
-        #serde_opt
-    };
-    let impl_name_opt = quote! {
-        impl #opt_name {
-            #impl_name_opt
+    pub fn check_end(bytes: &[u8], tail: usize) -> Result<(), DecodeError> {
+        match bytes.len() - tail {
+            0 => Ok(()),
+            1 => Err(DecodeError::new(tail, "1 trailing byte")),
+            n => Err(DecodeError::new(tail, format!("{n} trailing bytes"))),
         }
-    };
-
-    quote! {
-        #structure
-        #impl_name
-        #impl_name_opt
     }
-    .into()
 }
@@ -0,0 +1,538 @@
+use crate::{Diff, Patch, PatchError, WithOpt};
+use core::{convert::Infallible, ops::Range};
+
+#[cfg(feature = "bytemuck")]
+use crate::{
+    __private::{read_len, read_pod, write_len},
+    DecodeError, EncodeError, LenWidth,
+};
+
+/// An operation on a list, as applied by [`ListPatch`].
+///
+/// Indices refer to the list as modified by the preceding operations.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ListOp<T, P = Infallible> {
+    /// Appends an element.
+    Push(T),
+    /// Removes the last element, if any.
+    Pop,
+    /// Inserts an element at an index.
+    Insert(usize, T),
+    /// Removes the element at an index.
+    Remove(usize),
+    /// Replaces a range of elements with others.
+    Splice(Range<usize>, Vec<T>),
+    /// Replaces the element at an index.
+    Set(usize, T),
+    /// Patches the element at an index with its own optional type.
+    Patch(usize, P),
+    /// Replaces every element.
+    Replace(Vec<T>),
+}
+
+/// The patch of a `#[wopt(list_ops)]` field, as a sequence of operations.
+///
+/// Only the modified elements are patched (and serialized), rather than the whole list:
+/// ```
+/// use wopt::*;
+///
+/// #[derive(Debug, PartialEq, WithOpt)]
+/// #[wopt(derive(Default))]
+/// struct Inventory {
+///     #[wopt(list_ops)]
+///     items: Vec<u32>,
+/// }
+///
+/// let mut inventory = Inventory { items: vec![1, 2, 3] };
+///
+/// inventory.patch(&mut InventoryOpt::default().items(|items| items.set(1, 4).push(5)));
+/// assert_eq!(inventory.items, [1, 4, 3, 5]);
+/// ```
+///
+/// Elements deriving [`WithOpt`](derive@crate::WithOpt) are patched per index with
+/// `#[wopt(list_ops, optional)]` (see [`ListOp::Patch`]).
+///
+/// Patching stops at the first operation whose index is out of bounds, leaving it (and those
+/// following it) in the patch, while `try_patch` rejects the patch as a whole (see
+/// [`ListPatch::check`]). [`ListOp::Pop`] on an empty list does nothing.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ListPatch<T, P = Infallible> {
+    ops: Vec<ListOp<T, P>>,
+}
+
+impl<T, P> ListPatch<T, P> {
+    /// A patch without any operation.
+    pub const fn new() -> Self {
+        Self { ops: Vec::new() }
+    }
+
+    /// The operations, in the order they're applied.
+    pub fn ops(&self) -> &[ListOp<T, P>] {
+        &self.ops
+    }
+
+    /// Whether there's any operation.
+    pub const fn is_modified(&self) -> bool {
+        !self.ops.is_empty()
+    }
+
+    /// Whether the operations set the whole value (i.e., there's a [`ListOp::Replace`], see `into_full`).
+    pub fn is_full(&self) -> bool {
+        self.ops.iter().any(|op| matches!(op, ListOp::Replace(_)))
+    }
+
+    /// Takes the operations, leaving `self` unmodified.
+    #[allow(clippy::mem_replace_with_default)] // `mem::take` isn't `const`
+    pub const fn take(&mut self) -> Self {
+        std::mem::replace(self, Self::new())
+    }
+
+    /// Merges a later patch into `self`, whose operations follow.
+    pub fn merge(&mut self, mut rhs: Self) {
+        self.ops.append(&mut rhs.ops)
+    }
+
+    /// Appends `op`.
+    pub fn op(mut self, op: ListOp<T, P>) -> Self {
+        self.ops.push(op);
+        self
+    }
+
+    /// Appends [`ListOp::Push`].
+    pub fn push(self, value: T) -> Self {
+        self.op(ListOp::Push(value))
+    }
+
+    /// Appends [`ListOp::Pop`].
+    pub fn pop(self) -> Self {
+        self.op(ListOp::Pop)
+    }
+
+    /// Appends [`ListOp::Insert`].
+    pub fn insert(self, index: usize, value: T) -> Self {
+        self.op(ListOp::Insert(index, value))
+    }
+
+    /// Appends [`ListOp::Remove`].
+    pub fn remove(self, index: usize) -> Self {
+        self.op(ListOp::Remove(index))
+    }
+
+    /// Appends [`ListOp::Splice`].
+    pub fn splice(self, range: Range<usize>, values: impl IntoIterator<Item = T>) -> Self {
+        self.op(ListOp::Splice(range, values.into_iter().collect()))
+    }
+
+    /// Appends [`ListOp::Set`].
+    pub fn set(self, index: usize, value: T) -> Self {
+        self.op(ListOp::Set(index, value))
+    }
+
+    /// Appends [`ListOp::Patch`].
+    pub fn patch_at(self, index: usize, patch: P) -> Self {
+        self.op(ListOp::Patch(index, patch))
+    }
+
+    /// Appends [`ListOp::Replace`].
+    pub fn replace(self, values: impl IntoIterator<Item = T>) -> Self {
+        self.op(ListOp::Replace(values.into_iter().collect()))
+    }
+
+    /// Checks that the index of every operation is within the bounds of a list of `len` elements,
+    /// as modified by the preceding operations.
+    pub fn check(&self, mut len: usize) -> Result<(), PatchError> {
+        for op in &self.ops {
+            len = Self::bounds(op, len)?;
+        }
+        Ok(())
+    }
+
+    /// The length of a list of `len` elements once modified by `op`, if within its bounds.
+    fn bounds(op: &ListOp<T, P>, len: usize) -> Result<usize, PatchError> {
+        let out_of_bounds =
+            |index: usize| PatchError::new(format!("index {index} out of bounds (len {len})"));
+        match op {
+            ListOp::Push(_) => Ok(len + 1),
+            ListOp::Pop => Ok(len.saturating_sub(1)),
+            ListOp::Insert(index, _) if *index > len => Err(out_of_bounds(*index)),
+            ListOp::Insert(..) => Ok(len + 1),
+            ListOp::Remove(index) | ListOp::Set(index, _) | ListOp::Patch(index, _)
+                if *index >= len =>
+            {
+                Err(out_of_bounds(*index))
+            }
+            ListOp::Remove(_) => Ok(len - 1),
+            ListOp::Set(..) | ListOp::Patch(..) => Ok(len),
+            ListOp::Splice(range, _) if range.start > range.end => Err(PatchError::new(format!(
+                "range {}..{} out of order",
+                range.start, range.end
+            ))),
+            ListOp::Splice(range, _) if range.end > len => Err(out_of_bounds(range.end)),
+            ListOp::Splice(range, values) => Ok(len - range.len() + values.len()),
+            ListOp::Replace(values) => Ok(values.len()),
+        }
+    }
+
+    /// Applies the operations to `list` like [`Self::apply`], given every index is within bounds
+    /// (otherwise, `list` is left unchanged).
+    fn try_apply(
+        mut self,
+        list: &mut Vec<T>,
+        patch: impl FnMut(&mut T, P) -> P,
+        undo: bool,
+    ) -> Result<Self, PatchError> {
+        self.check(list.len())?;
+        Ok(self.apply(list, patch, undo))
+    }
+
+    /// Takes and applies the operations to `list`, patching elements with `patch`, and returning
+    /// the operations reverting them if `undo`.
+    ///
+    /// The first operation out of bounds and those following it are left in `self`.
+    fn apply(
+        &mut self,
+        list: &mut Vec<T>,
+        mut patch: impl FnMut(&mut T, P) -> P,
+        undo: bool,
+    ) -> Self {
+        let mut reverted = Vec::new();
+        let mut ops = std::mem::take(&mut self.ops).into_iter();
+        while let Some(op) = ops.next() {
+            if Self::bounds(&op, list.len()).is_err() {
+                self.ops = std::iter::once(op).chain(ops).collect();
+                break;
+            }
+            let op = match op {
+                ListOp::Push(value) => {
+                    list.push(value);
+                    ListOp::Pop
+                }
+                ListOp::Pop => match list.pop() {
+                    Some(value) => ListOp::Push(value),
+                    None => continue,
+                },
+                ListOp::Insert(index, value) => {
+                    list.insert(index, value);
+                    ListOp::Remove(index)
+                }
+                ListOp::Remove(index) => ListOp::Insert(index, list.remove(index)),
+                ListOp::Splice(range, values) => {
+                    let inserted = range.start..range.start + values.len();
+                    ListOp::Splice(inserted, list.splice(range, values).collect())
+                }
+                ListOp::Set(index, value) => {
+                    ListOp::Set(index, std::mem::replace(&mut list[index], value))
+                }
+                ListOp::Patch(index, rhs) => ListOp::Patch(index, patch(&mut list[index], rhs)),
+                ListOp::Replace(values) => ListOp::Replace(std::mem::replace(list, values)),
+            };
+            if undo {
+                reverted.push(op);
+            }
+        }
+        reverted.reverse();
+        Self { ops: reverted }
+    }
+
+    /// The list set by the operations (from the last [`ListOp::Replace`]), if any.
+    fn apply_full(mut self, patch: impl FnMut(&mut T, P) -> P) -> Option<Vec<T>> {
+        let start = self
+            .ops
+            .iter()
+            .rposition(|op| matches!(op, ListOp::Replace(_)))?;
+        let mut list = Vec::new();
+        self.ops.drain(..start);
+        self.apply(&mut list, patch, false);
+        Some(list)
+    }
+
+    /// The operations turning `old` into `new`, replacing the changed elements with `changed`.
+    fn diff_with(
+        old: &[T],
+        new: &[T],
+        mut changed: impl FnMut(usize, &T, &T) -> ListOp<T, P>,
+    ) -> Self
+    where
+        T: PartialEq + Clone,
+    {
+        // only the elements between the common prefix and suffix differ
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let (old, new) = (&old[prefix..], &new[prefix..]);
+        let suffix = old
+            .iter()
+            .rev()
+            .zip(new.iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+
+        // the remaining elements are changed in place, before inserting or removing the rest
+        let common = old.len().min(new.len());
+        let mut ops = old
+            .iter()
+            .zip(new)
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(i, (a, b))| changed(prefix + i, a, b))
+            .collect::<Vec<_>>();
+        let (start, old, new) = (prefix + common, &old[common..], &new[common..]);
+        ops.extend(match (old.len(), new.len()) {
+            (0, 0) => None,
+            (0, 1) if suffix == 0 => Some(ListOp::Push(new[0].clone())),
+            (0, 1) => Some(ListOp::Insert(start, new[0].clone())),
+            (1, 0) if suffix == 0 => Some(ListOp::Pop),
+            (1, 0) => Some(ListOp::Remove(start)),
+            (len, _) => Some(ListOp::Splice(start..start + len, new.to_vec())),
+        });
+        Self { ops }
+    }
+}
+
+impl<T> ListPatch<T> {
+    /// Takes and applies the operations to `list`, up to the first one out of bounds.
+    pub fn patch(&mut self, list: &mut Vec<T>) {
+        self.apply(list, |_, rhs| match rhs {}, false);
+    }
+
+    /// Applies the operations to `list` like [`Self::patch`], returning the operations reverting
+    /// those applied.
+    pub fn patch_with_undo(&mut self, list: &mut Vec<T>) -> Self {
+        self.apply(list, |_, rhs| match rhs {}, true)
+    }
+
+    /// Applies the operations to `list`, given every index is within bounds (otherwise, `list` is
+    /// left unchanged).
+    pub fn try_patch(self, list: &mut Vec<T>) -> Result<(), PatchError> {
+        self.try_apply(list, |_, rhs| match rhs {}, false)
+            .map(|_| ())
+    }
+
+    /// The list set by the operations (from the last [`ListOp::Replace`]), if any.
+    pub fn into_full(self) -> Option<Vec<T>> {
+        self.apply_full(|_, rhs| match rhs {})
+    }
+
+    /// The operations turning `old` into `new`.
+    pub fn diff(old: &[T], new: &[T]) -> Self
+    where
+        T: PartialEq + Clone,
+    {
+        Self::diff_with(old, new, |index, _, new| ListOp::Set(index, new.clone()))
+    }
+}
+
+impl<T: WithOpt> ListPatch<T, T::Opt> {
+    /// Takes and applies the operations to `list` (up to the first one out of bounds), patching
+    /// its elements per index.
+    pub fn patch_nested(&mut self, list: &mut Vec<T>) {
+        self.apply(
+            list,
+            |value, mut rhs| {
+                value.patch(&mut rhs);
+                rhs
+            },
+            false,
+        );
+    }
+
+    /// Applies the operations to `list` like [`Self::patch_nested`], returning the operations
+    /// reverting those applied.
+    pub fn patch_nested_with_undo(&mut self, list: &mut Vec<T>) -> Self {
+        self.apply(list, |value, mut rhs| value.patch_with_undo(&mut rhs), true)
+    }
+
+    /// Applies the operations to `list` like [`Self::patch_nested`], given every index is within
+    /// bounds (otherwise, `list` is left unchanged).
+    pub fn try_patch_nested(self, list: &mut Vec<T>) -> Result<(), PatchError> {
+        let patch = |value: &mut T, mut rhs| {
+            value.patch(&mut rhs);
+            rhs
+        };
+        self.try_apply(list, patch, false).map(|_| ())
+    }
+
+    /// The list set by the operations (from the last [`ListOp::Replace`]), if any.
+    pub fn into_full_nested(self) -> Option<Vec<T>> {
+        self.apply_full(|value, mut rhs| {
+            value.patch(&mut rhs);
+            rhs
+        })
+    }
+
+    /// The operations turning `old` into `new`, patching the changed elements per index.
+    pub fn diff_nested(old: &[T], new: &[T]) -> Self
+    where
+        T: Diff + PartialEq + Clone,
+    {
+        Self::diff_with(old, new, |index, old, new| {
+            ListOp::Patch(index, old.diff(new))
+        })
+    }
+}
+
+impl<T, P> Default for ListPatch<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, P> From<Vec<T>> for ListPatch<T, P> {
+    /// A patch replacing every element with `values`.
+    fn from(values: Vec<T>) -> Self {
+        Self::new().op(ListOp::Replace(values))
+    }
+}
+
+impl<T, P> Patch for ListPatch<T, P> {
+    fn is_modified(&self) -> bool {
+        Self::is_modified(self)
+    }
+
+    fn take(&mut self) -> Self {
+        Self::take(self)
+    }
+
+    fn merge(&mut self, rhs: Self) {
+        Self::merge(self, rhs)
+    }
+}
+
+/// Serializes `list` as its length (as `len`), followed by its elements.
+#[cfg(feature = "bytemuck")]
+pub fn ser_list<T>(
+    list: &[T],
+    len: LenWidth,
+    data: &mut Vec<u8>,
+    mut ser: impl FnMut(&T, &mut Vec<u8>) -> Result<(), EncodeError>,
+) -> Result<(), EncodeError> {
+    write_len(list.len(), len, data)?;
+    list.iter().try_for_each(|value| ser(value, data))
+}
+
+/// Deserializes a list serialized by [`ser_list`], starting at `tail` (moved past it).
+#[cfg(feature = "bytemuck")]
+pub fn de_list<T>(
+    bytes: &[u8],
+    tail: &mut usize,
+    len: LenWidth,
+    mut de: impl FnMut(&[u8], &mut usize) -> Result<T, DecodeError>,
+) -> Result<Vec<T>, DecodeError> {
+    let len = read_count(bytes, tail, len)?;
+    let mut list = Vec::with_capacity(len);
+    for _ in 0..len {
+        list.push(de(bytes, tail)?)
+    }
+    Ok(list)
+}
+
+/// Serializes `patch` as its number of operations (as `len`), followed by each operation
+/// (a tag, then its indices as `len` and its values).
+#[cfg(feature = "bytemuck")]
+pub fn ser_list_patch<T, P>(
+    patch: &ListPatch<T, P>,
+    len: LenWidth,
+    data: &mut Vec<u8>,
+    mut ser: impl FnMut(&T, &mut Vec<u8>) -> Result<(), EncodeError>,
+    mut ser_patch: impl FnMut(&P, &mut Vec<u8>) -> Result<(), EncodeError>,
+) -> Result<(), EncodeError> {
+    write_len(patch.ops.len(), len, data)?;
+    for op in &patch.ops {
+        match op {
+            ListOp::Push(value) => {
+                data.push(0);
+                ser(value, data)?
+            }
+            ListOp::Pop => data.push(1),
+            ListOp::Insert(index, value) => {
+                data.push(2);
+                write_len(*index, len, data)?;
+                ser(value, data)?
+            }
+            ListOp::Remove(index) => {
+                data.push(3);
+                write_len(*index, len, data)?
+            }
+            ListOp::Splice(range, values) => {
+                data.push(4);
+                write_len(range.start, len, data)?;
+                write_len(range.end, len, data)?;
+                ser_list(values, len, data, &mut ser)?
+            }
+            ListOp::Set(index, value) => {
+                data.push(5);
+                write_len(*index, len, data)?;
+                ser(value, data)?
+            }
+            ListOp::Patch(index, patch) => {
+                data.push(6);
+                write_len(*index, len, data)?;
+                ser_patch(patch, data)?
+            }
+            ListOp::Replace(values) => {
+                data.push(7);
+                ser_list(values, len, data, &mut ser)?
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deserializes a patch serialized by [`ser_list_patch`], starting at `tail` (moved past it).
+#[cfg(feature = "bytemuck")]
+pub fn de_list_patch<T, P>(
+    bytes: &[u8],
+    tail: &mut usize,
+    len: LenWidth,
+    mut de: impl FnMut(&[u8], &mut usize) -> Result<T, DecodeError>,
+    mut de_patch: impl FnMut(&[u8], &mut usize) -> Result<P, DecodeError>,
+) -> Result<ListPatch<T, P>, DecodeError> {
+    let count = read_count(bytes, tail, len)?;
+    let mut ops = Vec::with_capacity(count);
+    for _ in 0..count {
+        let offset = *tail;
+        let index = |tail: &mut usize| read_len(bytes, tail, len);
+        ops.push(match read_pod::<u8>(bytes, tail)? {
+            0 => ListOp::Push(de(bytes, tail)?),
+            1 => ListOp::Pop,
+            2 => ListOp::Insert(index(tail)?, de(bytes, tail)?),
+            3 => ListOp::Remove(index(tail)?),
+            4 => {
+                let range = index(tail)?..index(tail)?;
+                ListOp::Splice(range, de_list(bytes, tail, len, &mut de)?)
+            }
+            5 => ListOp::Set(index(tail)?, de(bytes, tail)?),
+            6 => ListOp::Patch(index(tail)?, de_patch(bytes, tail)?),
+            7 => ListOp::Replace(de_list(bytes, tail, len, &mut de)?),
+            tag => {
+                return Err(DecodeError::new(
+                    offset,
+                    format!("unknown list operation `{tag}`"),
+                ));
+            }
+        })
+    }
+    Ok(ListPatch { ops })
+}
+
+/// Reads the number of elements (or operations) of a collection (as `len`), starting at `tail`
+/// (moved past it), given it doesn't exceed the remaining bytes.
+///
+/// Elements take at least a byte each, except zero-sized ones, whose arbitrarily large counts
+/// would otherwise be deserialized from nothing.
+#[cfg(feature = "bytemuck")]
+pub(crate) fn read_count(
+    bytes: &[u8],
+    tail: &mut usize,
+    len: LenWidth,
+) -> Result<usize, DecodeError> {
+    let offset = *tail;
+    let count = read_len(bytes, tail, len)?;
+    let remaining = bytes.len().saturating_sub(*tail);
+    if count > remaining {
+        return Err(DecodeError::new(
+            offset,
+            format!("length {count} exceeds the remaining {remaining} bytes"),
+        ));
+    }
+    Ok(count)
+}
@@ -0,0 +1,825 @@
+use crate::{Diff, Patch, PatchError, WithOpt};
+use core::{convert::Infallible, hash::BuildHasher, hash::Hash};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+#[cfg(feature = "bytemuck")]
+use crate::{
+    __private::{read_pod, write_len},
+    DecodeError, EncodeError, LenWidth,
+    list::read_count,
+};
+
+/// A map patched by [`MapPatch`] (i.e., `HashMap` or `BTreeMap`).
+pub trait Map: Default + IntoIterator<Item = (Self::Key, Self::Value)> {
+    type Key;
+    type Value;
+
+    /// The number of entries.
+    fn len(&self) -> usize;
+
+    /// Whether there are no entries.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value of `key`, if present.
+    fn get(&self, key: &Self::Key) -> Option<&Self::Value>;
+
+    /// The value of `key` (mutably), if present.
+    fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Value>;
+
+    /// Inserts the value of `key`, returning the one it replaced (if any).
+    fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value>;
+
+    /// Removes `key`, returning its value (if present).
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Value>;
+
+    /// The entries.
+    fn iter(&self) -> impl Iterator<Item = (&Self::Key, &Self::Value)>;
+}
+
+/// A set patched by [`SetPatch`] (i.e., `HashSet` or `BTreeSet`).
+pub trait Set: Default + IntoIterator<Item = Self::Key> {
+    type Key;
+
+    /// The number of keys.
+    fn len(&self) -> usize;
+
+    /// Whether there's no key.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether `key` is present.
+    fn contains(&self, key: &Self::Key) -> bool;
+
+    /// Inserts `key`, returning whether it wasn't present.
+    fn insert(&mut self, key: Self::Key) -> bool;
+
+    /// Removes `key`, returning whether it was present.
+    fn remove(&mut self, key: &Self::Key) -> bool;
+
+    /// The keys.
+    fn iter(&self) -> impl Iterator<Item = &Self::Key>;
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Default> Map for HashMap<K, V, S> {
+    type Key = K;
+    type Value = V;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get_mut(key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert(key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove(key)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.iter()
+    }
+}
+
+impl<K: Ord, V> Map for BTreeMap<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get_mut(key)
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert(key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove(key)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.iter()
+    }
+}
+
+impl<K: Eq + Hash, S: BuildHasher + Default> Set for HashSet<K, S> {
+    type Key = K;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        self.contains(key)
+    }
+
+    fn insert(&mut self, key: K) -> bool {
+        self.insert(key)
+    }
+
+    fn remove(&mut self, key: &K) -> bool {
+        self.remove(key)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &K> {
+        self.iter()
+    }
+}
+
+impl<K: Ord> Set for BTreeSet<K> {
+    type Key = K;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        self.contains(key)
+    }
+
+    fn insert(&mut self, key: K) -> bool {
+        self.insert(key)
+    }
+
+    fn remove(&mut self, key: &K) -> bool {
+        self.remove(key)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &K> {
+        self.iter()
+    }
+}
+
+/// An operation on a map, as applied by [`MapPatch`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MapOp<K, V, P = Infallible> {
+    /// Inserts (or replaces) the value of a key.
+    Insert(K, V),
+    /// Removes a key, if present.
+    Remove(K),
+    /// Patches the value of a key (if present) with its own optional type.
+    Patch(K, P),
+    /// Removes every key.
+    Clear,
+}
+
+/// The patch of a `#[wopt(map_ops)]` map field, as a sequence of operations on its keys.
+///
+/// Only the modified keys are patched (and serialized), rather than the whole map:
+/// ```
+/// use std::collections::HashMap;
+/// use wopt::*;
+///
+/// #[derive(Debug, PartialEq, WithOpt)]
+/// #[wopt(derive(Default))]
+/// struct Player {
+///     #[wopt(map_ops)]
+///     stats: HashMap<u8, u32>,
+/// }
+///
+/// let mut player = Player { stats: HashMap::from([(1, 10), (2, 20)]) };
+///
+/// player.patch(&mut PlayerOpt::default().stats(|stats| stats.insert(1, 11).remove(2)));
+/// assert_eq!(player.stats, HashMap::from([(1, 11)]));
+/// ```
+///
+/// Values deriving [`WithOpt`](derive@crate::WithOpt) are patched per key with
+/// `#[wopt(map_ops, optional)]` (see [`MapOp::Patch`]).
+///
+/// Patches of missing keys are skipped when applied, while `try_patch` rejects them (see
+/// [`MapPatch::check`]).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MapPatch<K, V, P = Infallible> {
+    ops: Vec<MapOp<K, V, P>>,
+}
+
+impl<K, V, P> MapPatch<K, V, P> {
+    /// A patch without any operation.
+    pub const fn new() -> Self {
+        Self { ops: Vec::new() }
+    }
+
+    /// A patch replacing every entry with those of `map`.
+    pub fn from_map<M: Map<Key = K, Value = V>>(map: M) -> Self {
+        let mut ops = Vec::with_capacity(1 + map.len());
+        ops.push(MapOp::Clear);
+        ops.extend(
+            map.into_iter()
+                .map(|(key, value)| MapOp::Insert(key, value)),
+        );
+        Self { ops }
+    }
+
+    /// The operations, in the order they're applied.
+    pub fn ops(&self) -> &[MapOp<K, V, P>] {
+        &self.ops
+    }
+
+    /// Whether there's any operation.
+    pub const fn is_modified(&self) -> bool {
+        !self.ops.is_empty()
+    }
+
+    /// Whether the operations set the whole value (i.e., there's a [`MapOp::Clear`], see `into_full`).
+    pub fn is_full(&self) -> bool {
+        self.ops.iter().any(|op| matches!(op, MapOp::Clear))
+    }
+
+    /// Takes the operations, leaving `self` unmodified.
+    #[allow(clippy::mem_replace_with_default)] // `mem::take` isn't `const`
+    pub const fn take(&mut self) -> Self {
+        std::mem::replace(self, Self::new())
+    }
+
+    /// Merges a later patch into `self`, whose operations follow.
+    pub fn merge(&mut self, mut rhs: Self) {
+        self.ops.append(&mut rhs.ops)
+    }
+
+    /// Appends `op`.
+    pub fn op(mut self, op: MapOp<K, V, P>) -> Self {
+        self.ops.push(op);
+        self
+    }
+
+    /// Appends [`MapOp::Insert`].
+    pub fn insert(self, key: K, value: V) -> Self {
+        self.op(MapOp::Insert(key, value))
+    }
+
+    /// Appends [`MapOp::Remove`].
+    pub fn remove(self, key: K) -> Self {
+        self.op(MapOp::Remove(key))
+    }
+
+    /// Appends [`MapOp::Patch`].
+    pub fn patch_at(self, key: K, patch: P) -> Self {
+        self.op(MapOp::Patch(key, patch))
+    }
+
+    /// Appends [`MapOp::Clear`].
+    pub fn clear(self) -> Self {
+        self.op(MapOp::Clear)
+    }
+
+    /// Checks that the key of every [`MapOp::Patch`] is present in `map`, as modified by the
+    /// preceding operations.
+    pub fn check<M: Map<Key = K, Value = V>>(&self, map: &M) -> Result<(), PatchError>
+    where
+        K: PartialEq,
+    {
+        // the keys inserted (or removed) by the preceding operations, the latest last
+        let mut keys = Vec::new();
+        let mut is_cleared = false;
+        for op in &self.ops {
+            match op {
+                MapOp::Insert(key, _) => keys.push((key, true)),
+                MapOp::Remove(key) => keys.push((key, false)),
+                MapOp::Clear => {
+                    keys.clear();
+                    is_cleared = true;
+                }
+                MapOp::Patch(key, _) => {
+                    let is_present = match keys.iter().rfind(|(k, _)| *k == key) {
+                        Some((_, is_present)) => *is_present,
+                        None => !is_cleared && map.get(key).is_some(),
+                    };
+                    if !is_present {
+                        return Err(PatchError::new("patched key not found"));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the operations to `map`, patching values with `patch`, and returning the
+    /// operations reverting them if `undo`.
+    fn apply<M>(self, map: &mut M, mut patch: impl FnMut(&mut V, P) -> P, undo: bool) -> Self
+    where
+        M: Map<Key = K, Value = V>,
+        K: Clone,
+    {
+        let mut reverted = Vec::new();
+        let mut revert = |op| {
+            if undo {
+                reverted.push(op)
+            }
+        };
+        for op in self.ops {
+            match op {
+                MapOp::Insert(key, value) => match map.insert(key.clone(), value) {
+                    Some(value) => revert(MapOp::Insert(key, value)),
+                    None => revert(MapOp::Remove(key)),
+                },
+                MapOp::Remove(key) => {
+                    if let Some(value) = map.remove(&key) {
+                        revert(MapOp::Insert(key, value))
+                    }
+                }
+                MapOp::Patch(key, rhs) => {
+                    if let Some(value) = map.get_mut(&key) {
+                        revert(MapOp::Patch(key, patch(value, rhs)))
+                    }
+                }
+                MapOp::Clear => {
+                    // the entries are inserted back once cleared (i.e., after reversing)
+                    for (key, value) in std::mem::take(map) {
+                        revert(MapOp::Insert(key, value))
+                    }
+                    revert(MapOp::Clear)
+                }
+            }
+        }
+        reverted.reverse();
+        Self { ops: reverted }
+    }
+
+    /// The map set by the operations (from the last [`MapOp::Clear`]), if any.
+    fn apply_full<M>(mut self, patch: impl FnMut(&mut V, P) -> P) -> Option<M>
+    where
+        M: Map<Key = K, Value = V>,
+        K: Clone,
+    {
+        let start = self.ops.iter().rposition(|op| matches!(op, MapOp::Clear))?;
+        let mut map = M::default();
+        self.ops.drain(..start);
+        self.apply(&mut map, patch, false);
+        Some(map)
+    }
+
+    /// The operations turning `old` into `new`, replacing the changed values with `changed`.
+    fn diff_with<M>(old: &M, new: &M, mut changed: impl FnMut(&K, &V, &V) -> MapOp<K, V, P>) -> Self
+    where
+        M: Map<Key = K, Value = V>,
+        K: Clone,
+        V: PartialEq + Clone,
+    {
+        let mut ops = new
+            .iter()
+            .filter_map(|(key, value)| match old.get(key) {
+                Some(old) if old == value => None,
+                Some(old) => Some(changed(key, old, value)),
+                None => Some(MapOp::Insert(key.clone(), value.clone())),
+            })
+            .collect::<Vec<_>>();
+        ops.extend(
+            old.iter()
+                .filter(|(key, _)| new.get(key).is_none())
+                .map(|(key, _)| MapOp::Remove(key.clone())),
+        );
+        Self { ops }
+    }
+}
+
+impl<K: Clone, V> MapPatch<K, V> {
+    /// Applies the operations to `map`.
+    pub fn patch<M: Map<Key = K, Value = V>>(self, map: &mut M) {
+        self.apply(map, |_, rhs| match rhs {}, false);
+    }
+
+    /// Applies the operations to `map`, returning the operations reverting them.
+    pub fn patch_with_undo<M: Map<Key = K, Value = V>>(self, map: &mut M) -> Self {
+        self.apply(map, |_, rhs| match rhs {}, true)
+    }
+
+    /// The map set by the operations (from the last [`MapOp::Clear`]), if any.
+    pub fn into_full<M: Map<Key = K, Value = V>>(self) -> Option<M> {
+        self.apply_full(|_, rhs| match rhs {})
+    }
+
+    /// The operations turning `old` into `new`.
+    pub fn diff<M: Map<Key = K, Value = V>>(old: &M, new: &M) -> Self
+    where
+        V: PartialEq + Clone,
+    {
+        Self::diff_with(old, new, |key, _, new| {
+            MapOp::Insert(key.clone(), new.clone())
+        })
+    }
+}
+
+impl<K: Clone, V: WithOpt> MapPatch<K, V, V::Opt> {
+    /// Applies the operations to `map`, patching its values per key.
+    pub fn patch_nested<M: Map<Key = K, Value = V>>(self, map: &mut M) {
+        self.apply(
+            map,
+            |value, mut rhs| {
+                value.patch(&mut rhs);
+                rhs
+            },
+            false,
+        );
+    }
+
+    /// Applies the operations to `map` like [`Self::patch_nested`], returning the operations
+    /// reverting them.
+    pub fn patch_nested_with_undo<M: Map<Key = K, Value = V>>(self, map: &mut M) -> Self {
+        self.apply(map, |value, mut rhs| value.patch_with_undo(&mut rhs), true)
+    }
+
+    /// The map set by the operations (from the last [`MapOp::Clear`]), if any.
+    pub fn into_full_nested<M: Map<Key = K, Value = V>>(self) -> Option<M> {
+        self.apply_full(|value, mut rhs| {
+            value.patch(&mut rhs);
+            rhs
+        })
+    }
+
+    /// The operations turning `old` into `new`, patching the changed values per key.
+    pub fn diff_nested<M: Map<Key = K, Value = V>>(old: &M, new: &M) -> Self
+    where
+        V: Diff + PartialEq + Clone,
+    {
+        Self::diff_with(old, new, |key, old, new| {
+            MapOp::Patch(key.clone(), old.diff(new))
+        })
+    }
+}
+
+impl<K, V, P> Default for MapPatch<K, V, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, P> Patch for MapPatch<K, V, P> {
+    fn is_modified(&self) -> bool {
+        Self::is_modified(self)
+    }
+
+    fn take(&mut self) -> Self {
+        Self::take(self)
+    }
+
+    fn merge(&mut self, rhs: Self) {
+        Self::merge(self, rhs)
+    }
+}
+
+/// An operation on a set, as applied by [`SetPatch`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SetOp<K> {
+    /// Inserts a key.
+    Insert(K),
+    /// Removes a key, if present.
+    Remove(K),
+    /// Removes every key.
+    Clear,
+}
+
+/// The patch of a `#[wopt(map_ops)]` set field, as a sequence of insertions and removals.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SetPatch<K> {
+    ops: Vec<SetOp<K>>,
+}
+
+impl<K> SetPatch<K> {
+    /// A patch without any operation.
+    pub const fn new() -> Self {
+        Self { ops: Vec::new() }
+    }
+
+    /// A patch replacing every key with those of `set`.
+    pub fn from_set<S: Set<Key = K>>(set: S) -> Self {
+        let mut ops = Vec::with_capacity(1 + set.len());
+        ops.push(SetOp::Clear);
+        ops.extend(set.into_iter().map(SetOp::Insert));
+        Self { ops }
+    }
+
+    /// The operations, in the order they're applied.
+    pub fn ops(&self) -> &[SetOp<K>] {
+        &self.ops
+    }
+
+    /// Whether there's any operation.
+    pub const fn is_modified(&self) -> bool {
+        !self.ops.is_empty()
+    }
+
+    /// Whether the operations set the whole value (i.e., there's a [`SetOp::Clear`], see `into_full`).
+    pub fn is_full(&self) -> bool {
+        self.ops.iter().any(|op| matches!(op, SetOp::Clear))
+    }
+
+    /// Takes the operations, leaving `self` unmodified.
+    #[allow(clippy::mem_replace_with_default)] // `mem::take` isn't `const`
+    pub const fn take(&mut self) -> Self {
+        std::mem::replace(self, Self::new())
+    }
+
+    /// Merges a later patch into `self`, whose operations follow.
+    pub fn merge(&mut self, mut rhs: Self) {
+        self.ops.append(&mut rhs.ops)
+    }
+
+    /// Appends `op`.
+    pub fn op(mut self, op: SetOp<K>) -> Self {
+        self.ops.push(op);
+        self
+    }
+
+    /// Appends [`SetOp::Insert`].
+    pub fn insert(self, key: K) -> Self {
+        self.op(SetOp::Insert(key))
+    }
+
+    /// Appends [`SetOp::Remove`].
+    pub fn remove(self, key: K) -> Self {
+        self.op(SetOp::Remove(key))
+    }
+
+    /// Appends [`SetOp::Clear`].
+    pub fn clear(self) -> Self {
+        self.op(SetOp::Clear)
+    }
+
+    /// Applies the operations to `set`.
+    pub fn patch<S: Set<Key = K>>(self, set: &mut S) {
+        for op in self.ops {
+            match op {
+                SetOp::Insert(key) => {
+                    set.insert(key);
+                }
+                SetOp::Remove(key) => {
+                    set.remove(&key);
+                }
+                SetOp::Clear => *set = S::default(),
+            }
+        }
+    }
+
+    /// Applies the operations to `set`, returning the operations reverting them.
+    pub fn patch_with_undo<S: Set<Key = K>>(self, set: &mut S) -> Self
+    where
+        K: Clone,
+    {
+        let mut reverted = Vec::new();
+        for op in self.ops {
+            match op {
+                SetOp::Insert(key) => {
+                    if set.insert(key.clone()) {
+                        reverted.push(SetOp::Remove(key))
+                    }
+                }
+                SetOp::Remove(key) => {
+                    if set.remove(&key) {
+                        reverted.push(SetOp::Insert(key))
+                    }
+                }
+                SetOp::Clear => {
+                    // the keys are inserted back once cleared (i.e., after reversing)
+                    reverted.extend(std::mem::take(set).into_iter().map(SetOp::Insert));
+                    reverted.push(SetOp::Clear)
+                }
+            }
+        }
+        reverted.reverse();
+        Self { ops: reverted }
+    }
+
+    /// The set set by the operations (from the last [`SetOp::Clear`]), if any.
+    pub fn into_full<S: Set<Key = K>>(mut self) -> Option<S> {
+        let start = self.ops.iter().rposition(|op| matches!(op, SetOp::Clear))?;
+        let mut set = S::default();
+        self.ops.drain(..start);
+        self.patch(&mut set);
+        Some(set)
+    }
+
+    /// The operations turning `old` into `new`.
+    pub fn diff<S: Set<Key = K>>(old: &S, new: &S) -> Self
+    where
+        K: Clone,
+    {
+        let mut ops = new
+            .iter()
+            .filter(|key| !old.contains(key))
+            .map(|key| SetOp::Insert(key.clone()))
+            .collect::<Vec<_>>();
+        ops.extend(
+            old.iter()
+                .filter(|key| !new.contains(key))
+                .map(|key| SetOp::Remove(key.clone())),
+        );
+        Self { ops }
+    }
+}
+
+impl<K> Default for SetPatch<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> Patch for SetPatch<K> {
+    fn is_modified(&self) -> bool {
+        Self::is_modified(self)
+    }
+
+    fn take(&mut self) -> Self {
+        Self::take(self)
+    }
+
+    fn merge(&mut self, rhs: Self) {
+        Self::merge(self, rhs)
+    }
+}
+
+/// Serializes `map` as its length (as `len`), followed by its entries.
+#[cfg(feature = "bytemuck")]
+pub fn ser_map<M: Map>(
+    map: &M,
+    len: LenWidth,
+    data: &mut Vec<u8>,
+    mut ser_key: impl FnMut(&M::Key, &mut Vec<u8>) -> Result<(), EncodeError>,
+    mut ser: impl FnMut(&M::Value, &mut Vec<u8>) -> Result<(), EncodeError>,
+) -> Result<(), EncodeError> {
+    write_len(map.len(), len, data)?;
+    for (key, value) in map.iter() {
+        ser_key(key, data)?;
+        ser(value, data)?
+    }
+    Ok(())
+}
+
+/// Deserializes a map serialized by [`ser_map`], starting at `tail` (moved past it).
+#[cfg(feature = "bytemuck")]
+pub fn de_map<M: Map>(
+    bytes: &[u8],
+    tail: &mut usize,
+    len: LenWidth,
+    mut de_key: impl FnMut(&[u8], &mut usize) -> Result<M::Key, DecodeError>,
+    mut de: impl FnMut(&[u8], &mut usize) -> Result<M::Value, DecodeError>,
+) -> Result<M, DecodeError> {
+    let mut map = M::default();
+    for _ in 0..read_count(bytes, tail, len)? {
+        let key = de_key(bytes, tail)?;
+        map.insert(key, de(bytes, tail)?);
+    }
+    Ok(map)
+}
+
+/// Serializes `patch` as its number of operations (as `len`), followed by each operation
+/// (a tag, then its key and value).
+#[cfg(feature = "bytemuck")]
+pub fn ser_map_patch<K, V, P>(
+    patch: &MapPatch<K, V, P>,
+    len: LenWidth,
+    data: &mut Vec<u8>,
+    mut ser_key: impl FnMut(&K, &mut Vec<u8>) -> Result<(), EncodeError>,
+    mut ser: impl FnMut(&V, &mut Vec<u8>) -> Result<(), EncodeError>,
+    mut ser_patch: impl FnMut(&P, &mut Vec<u8>) -> Result<(), EncodeError>,
+) -> Result<(), EncodeError> {
+    write_len(patch.ops.len(), len, data)?;
+    for op in &patch.ops {
+        match op {
+            MapOp::Insert(key, value) => {
+                data.push(0);
+                ser_key(key, data)?;
+                ser(value, data)?
+            }
+            MapOp::Remove(key) => {
+                data.push(1);
+                ser_key(key, data)?
+            }
+            MapOp::Patch(key, patch) => {
+                data.push(2);
+                ser_key(key, data)?;
+                ser_patch(patch, data)?
+            }
+            MapOp::Clear => data.push(3),
+        }
+    }
+    Ok(())
+}
+
+/// Deserializes a patch serialized by [`ser_map_patch`], starting at `tail` (moved past it).
+#[cfg(feature = "bytemuck")]
+pub fn de_map_patch<K, V, P>(
+    bytes: &[u8],
+    tail: &mut usize,
+    len: LenWidth,
+    mut de_key: impl FnMut(&[u8], &mut usize) -> Result<K, DecodeError>,
+    mut de: impl FnMut(&[u8], &mut usize) -> Result<V, DecodeError>,
+    mut de_patch: impl FnMut(&[u8], &mut usize) -> Result<P, DecodeError>,
+) -> Result<MapPatch<K, V, P>, DecodeError> {
+    let len = read_count(bytes, tail, len)?;
+    let mut ops = Vec::with_capacity(len);
+    for _ in 0..len {
+        let offset = *tail;
+        ops.push(match read_pod::<u8>(bytes, tail)? {
+            0 => MapOp::Insert(de_key(bytes, tail)?, de(bytes, tail)?),
+            1 => MapOp::Remove(de_key(bytes, tail)?),
+            2 => MapOp::Patch(de_key(bytes, tail)?, de_patch(bytes, tail)?),
+            3 => MapOp::Clear,
+            tag => {
+                return Err(DecodeError::new(
+                    offset,
+                    format!("unknown map operation `{tag}`"),
+                ));
+            }
+        })
+    }
+    Ok(MapPatch { ops })
+}
+
+/// Serializes `set` as its length (as `len`), followed by its keys.
+#[cfg(feature = "bytemuck")]
+pub fn ser_set<S: Set>(
+    set: &S,
+    len: LenWidth,
+    data: &mut Vec<u8>,
+    mut ser_key: impl FnMut(&S::Key, &mut Vec<u8>) -> Result<(), EncodeError>,
+) -> Result<(), EncodeError> {
+    write_len(set.len(), len, data)?;
+    set.iter().try_for_each(|key| ser_key(key, data))
+}
+
+/// Deserializes a set serialized by [`ser_set`], starting at `tail` (moved past it).
+#[cfg(feature = "bytemuck")]
+pub fn de_set<S: Set>(
+    bytes: &[u8],
+    tail: &mut usize,
+    len: LenWidth,
+    mut de_key: impl FnMut(&[u8], &mut usize) -> Result<S::Key, DecodeError>,
+) -> Result<S, DecodeError> {
+    let mut set = S::default();
+    for _ in 0..read_count(bytes, tail, len)? {
+        set.insert(de_key(bytes, tail)?);
+    }
+    Ok(set)
+}
+
+/// Serializes `patch` as its number of operations (as `len`), followed by each operation
+/// (a tag, then its key).
+#[cfg(feature = "bytemuck")]
+pub fn ser_set_patch<K>(
+    patch: &SetPatch<K>,
+    len: LenWidth,
+    data: &mut Vec<u8>,
+    mut ser_key: impl FnMut(&K, &mut Vec<u8>) -> Result<(), EncodeError>,
+) -> Result<(), EncodeError> {
+    write_len(patch.ops.len(), len, data)?;
+    for op in &patch.ops {
+        match op {
+            SetOp::Insert(key) => {
+                data.push(0);
+                ser_key(key, data)?
+            }
+            SetOp::Remove(key) => {
+                data.push(1);
+                ser_key(key, data)?
+            }
+            SetOp::Clear => data.push(2),
+        }
+    }
+    Ok(())
+}
+
+/// Deserializes a patch serialized by [`ser_set_patch`], starting at `tail` (moved past it).
+#[cfg(feature = "bytemuck")]
+pub fn de_set_patch<K>(
+    bytes: &[u8],
+    tail: &mut usize,
+    len: LenWidth,
+    mut de_key: impl FnMut(&[u8], &mut usize) -> Result<K, DecodeError>,
+) -> Result<SetPatch<K>, DecodeError> {
+    let len = read_count(bytes, tail, len)?;
+    let mut ops = Vec::with_capacity(len);
+    for _ in 0..len {
+        let offset = *tail;
+        ops.push(match read_pod::<u8>(bytes, tail)? {
+            0 => SetOp::Insert(de_key(bytes, tail)?),
+            1 => SetOp::Remove(de_key(bytes, tail)?),
+            2 => SetOp::Clear,
+            tag => {
+                return Err(DecodeError::new(
+                    offset,
+                    format!("unknown set operation `{tag}`"),
+                ));
+            }
+        })
+    }
+    Ok(SetPatch { ops })
+}
@@ -0,0 +1,687 @@
+use crate::{Diff, MissingFields, Patch, PatchError, Track, WithOpt};
+
+#[cfg(feature = "bytemuck")]
+use crate::{DecodeError, EncodeError};
+use std::{rc::Rc, sync::Arc};
+
+/// The patch of an `Option<T>` field with `#[wopt(optional)]`, patching its value in place.
+///
+/// ```
+/// use wopt::*;
+///
+/// #[derive(Debug, PartialEq, WithOpt)]
+/// #[wopt(derive(Default))]
+/// struct Server {
+///     port: u16,
+///     limit: u32,
+/// }
+///
+/// #[derive(Debug, PartialEq, WithOpt)]
+/// #[wopt(derive(Default), no_serde)]
+/// struct Config {
+///     #[wopt(optional)]
+///     server: Option<Server>,
+/// }
+///
+/// let mut config = Config { server: Some(Server { port: 80, limit: 8 }) };
+///
+/// config.patch(&mut ConfigOpt::default().server(|_| OptionPatch::Patch(ServerOpt::default().port(443))));
+/// assert_eq!(config.server, Some(Server { port: 443, limit: 8 }));
+///
+/// config.patch(&mut ConfigOpt::default().server(|_| OptionPatch::Clear));
+/// assert_eq!(config.server, None);
+/// ```
+///
+/// A value is only set from a patch with every field (see `try_into_full`), an incomplete one
+/// leaving it as-is (and being left in `rhs`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OptionPatch<P> {
+    /// Leaves the field as-is.
+    #[default]
+    Unchanged,
+    /// Patches the value (or sets it, if `None`).
+    Patch(P),
+    /// Replaces the value (whether `None` or not).
+    Set(P),
+    /// Sets the field to `None`.
+    Clear,
+}
+
+impl<P> OptionPatch<P> {
+    /// Whether the field is either patched, set or cleared.
+    pub const fn is_modified(&self) -> bool {
+        !matches!(self, Self::Unchanged)
+    }
+
+    /// Takes the modification, leaving `self` unchanged.
+    pub const fn take(&mut self) -> Self {
+        std::mem::replace(self, Self::Unchanged)
+    }
+}
+
+impl<P: Patch> OptionPatch<P> {
+    /// Merges a later modification into `self`, which takes precedence.
+    pub fn merge(&mut self, rhs: Self) {
+        *self = match (self.take(), rhs) {
+            (lhs, Self::Unchanged) => lhs,
+            (Self::Patch(mut lhs), Self::Patch(rhs)) => {
+                lhs.merge(rhs);
+                Self::Patch(lhs)
+            }
+            (Self::Set(mut lhs), Self::Patch(rhs)) => {
+                lhs.merge(rhs);
+                Self::Set(lhs)
+            }
+            // patching a cleared value sets it
+            (Self::Clear, Self::Patch(rhs)) => Self::Set(rhs),
+            (_, rhs) => rhs,
+        }
+    }
+}
+
+impl<P: Patch> Patch for OptionPatch<P> {
+    fn is_modified(&self) -> bool {
+        Self::is_modified(self)
+    }
+
+    fn take(&mut self) -> Self {
+        Self::take(self)
+    }
+
+    fn merge(&mut self, rhs: Self) {
+        Self::merge(self, rhs)
+    }
+}
+
+/// The changes of an `Option<T>` field with `#[wopt(optional)]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OptionChanges<C> {
+    /// The value didn't change.
+    #[default]
+    Unchanged,
+    /// The value was patched in place, with its own changes.
+    Changed(C),
+    /// The value was set or cleared (i.e., replaced as a whole).
+    Replaced,
+}
+
+/// The items of a derivation used by the wrappers nesting it (e.g., `Box<T>`).
+#[doc(hidden)]
+pub trait Nested: WithOpt {
+    /// Converts a patch with every field set (see `try_into_full`).
+    fn try_from_opt(opt: Self::Opt) -> Result<Self, MissingFields>;
+
+    /// Whether [`Self::try_from_opt`] succeeds (see `is_full`).
+    fn is_full(opt: &Self::Opt) -> bool;
+
+    /// Checks every field (see `validate`).
+    fn validate(&self) -> Result<(), PatchError>;
+
+    /// Checks the fields patched by the patch returning `undo`.
+    fn validate_patched(&self, undo: &Self::Opt) -> Result<(), PatchError>;
+
+    /// Checks that `rhs` applies to `self` (i.e., that its list operations are within bounds, and
+    /// that its map operations patch present keys).
+    fn check_patch(&self, rhs: &Self::Opt) -> Result<(), PatchError>;
+}
+
+/// The (de)serialization of the patches of a derivation, used by the wrappers nesting it.
+#[doc(hidden)]
+#[cfg(feature = "bytemuck")]
+pub trait NestedWire: WithOpt {
+    /// Serializes a patch (without its identity).
+    fn ser_opt(opt: &Self::Opt, data: &mut Vec<u8>) -> Result<(), EncodeError>;
+
+    /// Deserializes a patch starting at `tail` (moved past it).
+    fn de_opt(bytes: &[u8], tail: &mut usize) -> Result<Self::Opt, DecodeError>;
+}
+
+impl<T: WithOpt> WithOpt for Box<T> {
+    type Opt = T::Opt;
+
+    fn patch(&mut self, rhs: &mut Self::Opt) {
+        T::patch(self, rhs)
+    }
+
+    fn patch_with_undo(&mut self, rhs: &mut Self::Opt) -> Self::Opt {
+        T::patch_with_undo(self, rhs)
+    }
+
+    fn into_opt(self) -> Self::Opt {
+        T::into_opt(*self)
+    }
+}
+
+impl<T: Nested> Nested for Box<T> {
+    fn try_from_opt(opt: Self::Opt) -> Result<Self, MissingFields> {
+        T::try_from_opt(opt).map(Self::new)
+    }
+
+    fn is_full(opt: &Self::Opt) -> bool {
+        T::is_full(opt)
+    }
+
+    fn validate(&self) -> Result<(), PatchError> {
+        T::validate(self)
+    }
+
+    fn validate_patched(&self, undo: &Self::Opt) -> Result<(), PatchError> {
+        T::validate_patched(self, undo)
+    }
+
+    fn check_patch(&self, rhs: &Self::Opt) -> Result<(), PatchError> {
+        T::check_patch(self, rhs)
+    }
+}
+
+impl<T: Diff> Diff for Box<T> {
+    fn diff(&self, other: &Self) -> Self::Opt {
+        T::diff(self, other)
+    }
+}
+
+impl<T: Track> Track for Box<T> {
+    type Changes = T::Changes;
+
+    fn patch_tracked(&mut self, rhs: &mut Self::Opt) -> Self::Changes {
+        T::patch_tracked(self, rhs)
+    }
+
+    fn changes(&self, undo: &Self::Opt) -> Self::Changes {
+        T::changes(self, undo)
+    }
+
+    fn is_unchanged(changes: &Self::Changes) -> bool {
+        T::is_unchanged(changes)
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<T: NestedWire> NestedWire for Box<T> {
+    fn ser_opt(opt: &Self::Opt, data: &mut Vec<u8>) -> Result<(), EncodeError> {
+        T::ser_opt(opt, data)
+    }
+
+    fn de_opt(bytes: &[u8], tail: &mut usize) -> Result<Self::Opt, DecodeError> {
+        T::de_opt(bytes, tail)
+    }
+}
+
+// shared values are patched copy-on-write (i.e., cloned only if shared and patched)
+macro_rules! impl_shared {
+    ($($shared:ident),*) => {$(
+        impl<T: WithOpt + Clone> WithOpt for $shared<T> {
+            type Opt = T::Opt;
+
+            fn patch(&mut self, rhs: &mut Self::Opt) {
+                if rhs.is_modified() {
+                    T::patch($shared::make_mut(self), rhs)
+                }
+            }
+
+            fn patch_with_undo(&mut self, rhs: &mut Self::Opt) -> Self::Opt {
+                if rhs.is_modified() {
+                    T::patch_with_undo($shared::make_mut(self), rhs)
+                } else {
+                    rhs.take()
+                }
+            }
+
+            fn into_opt(self) -> Self::Opt {
+                T::into_opt($shared::unwrap_or_clone(self))
+            }
+        }
+
+        impl<T: Nested + Clone> Nested for $shared<T> {
+            fn try_from_opt(opt: Self::Opt) -> Result<Self, MissingFields> {
+                T::try_from_opt(opt).map(Self::new)
+            }
+
+            fn is_full(opt: &Self::Opt) -> bool {
+                T::is_full(opt)
+            }
+
+            fn validate(&self) -> Result<(), PatchError> {
+                T::validate(self)
+            }
+
+            fn validate_patched(&self, undo: &Self::Opt) -> Result<(), PatchError> {
+                T::validate_patched(self, undo)
+            }
+
+            fn check_patch(&self, rhs: &Self::Opt) -> Result<(), PatchError> {
+                T::check_patch(self, rhs)
+            }
+        }
+
+        impl<T: Diff + Clone> Diff for $shared<T> {
+            fn diff(&self, other: &Self) -> Self::Opt {
+                T::diff(self, other)
+            }
+        }
+
+        impl<T: Track + Clone> Track for $shared<T> {
+            type Changes = T::Changes;
+
+            fn patch_tracked(&mut self, rhs: &mut Self::Opt) -> Self::Changes {
+                let undo = self.patch_with_undo(rhs);
+                self.changes(&undo)
+            }
+
+            fn changes(&self, undo: &Self::Opt) -> Self::Changes {
+                T::changes(self, undo)
+            }
+
+            fn is_unchanged(changes: &Self::Changes) -> bool {
+                T::is_unchanged(changes)
+            }
+        }
+
+        #[cfg(feature = "bytemuck")]
+        impl<T: NestedWire + Clone> NestedWire for $shared<T> {
+            fn ser_opt(opt: &Self::Opt, data: &mut Vec<u8>) -> Result<(), EncodeError> {
+                T::ser_opt(opt, data)
+            }
+
+            fn de_opt(bytes: &[u8], tail: &mut usize) -> Result<Self::Opt, DecodeError> {
+                T::de_opt(bytes, tail)
+            }
+        }
+    )*};
+}
+
+impl_shared!(Arc, Rc);
+
+impl<T: Nested> WithOpt for Option<T> {
+    type Opt = OptionPatch<T::Opt>;
+
+    fn patch(&mut self, rhs: &mut Self::Opt) {
+        self.patch_with_undo(rhs);
+    }
+
+    fn patch_with_undo(&mut self, rhs: &mut Self::Opt) -> Self::Opt {
+        match (rhs.take(), self.as_mut()) {
+            (OptionPatch::Unchanged, _) | (OptionPatch::Clear, None) => OptionPatch::Unchanged,
+            (OptionPatch::Patch(mut rhs), Some(value)) => {
+                OptionPatch::Patch(value.patch_with_undo(&mut rhs))
+            }
+            // an incomplete value isn't set, and is left in `rhs`
+            (OptionPatch::Patch(opt), _) if !T::is_full(&opt) => {
+                *rhs = OptionPatch::Patch(opt);
+                OptionPatch::Unchanged
+            }
+            (OptionPatch::Set(opt), _) if !T::is_full(&opt) => {
+                *rhs = OptionPatch::Set(opt);
+                OptionPatch::Unchanged
+            }
+            (OptionPatch::Patch(rhs) | OptionPatch::Set(rhs), _) => {
+                let old = std::mem::replace(self, T::try_from_opt(rhs).ok());
+                match old {
+                    Some(old) => OptionPatch::Set(old.into_opt()),
+                    None => OptionPatch::Clear,
+                }
+            }
+            (OptionPatch::Clear, Some(_)) => OptionPatch::Set(self.take().unwrap().into_opt()),
+        }
+    }
+
+    fn into_opt(self) -> Self::Opt {
+        match self {
+            Some(value) => OptionPatch::Set(value.into_opt()),
+            None => OptionPatch::Clear,
+        }
+    }
+}
+
+impl<T: Nested> Nested for Option<T> {
+    fn try_from_opt(opt: Self::Opt) -> Result<Self, MissingFields> {
+        match opt {
+            OptionPatch::Patch(opt) | OptionPatch::Set(opt) => T::try_from_opt(opt).map(Some),
+            OptionPatch::Clear => Ok(None),
+            OptionPatch::Unchanged => Err(MissingFields::new()),
+        }
+    }
+
+    fn is_full(opt: &Self::Opt) -> bool {
+        match opt {
+            OptionPatch::Patch(opt) | OptionPatch::Set(opt) => T::is_full(opt),
+            OptionPatch::Clear => true,
+            OptionPatch::Unchanged => false,
+        }
+    }
+
+    fn validate(&self) -> Result<(), PatchError> {
+        self.as_ref().map_or(Ok(()), T::validate)
+    }
+
+    fn validate_patched(&self, undo: &Self::Opt) -> Result<(), PatchError> {
+        match (self, undo) {
+            (Some(value), OptionPatch::Patch(undo)) => value.validate_patched(undo),
+            (Some(value), OptionPatch::Set(_) | OptionPatch::Clear) => value.validate(),
+            _ => Ok(()),
+        }
+    }
+
+    // set values are converted from their patch, rather than patched
+    fn check_patch(&self, rhs: &Self::Opt) -> Result<(), PatchError> {
+        match (self, rhs) {
+            (Some(value), OptionPatch::Patch(rhs)) => value.check_patch(rhs),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<T: Nested + Diff + Clone> Diff for Option<T> {
+    fn diff(&self, other: &Self) -> Self::Opt {
+        match (self, other) {
+            (Some(value), Some(other)) => {
+                let diff = value.diff(other);
+                if diff.is_modified() {
+                    OptionPatch::Patch(diff)
+                } else {
+                    OptionPatch::Unchanged
+                }
+            }
+            (None, Some(other)) => OptionPatch::Set(other.clone().into_opt()),
+            (Some(_), None) => OptionPatch::Clear,
+            (None, None) => OptionPatch::Unchanged,
+        }
+    }
+}
+
+impl<T: Nested + Track> Track for Option<T> {
+    type Changes = OptionChanges<T::Changes>;
+
+    fn patch_tracked(&mut self, rhs: &mut Self::Opt) -> Self::Changes {
+        let undo = self.patch_with_undo(rhs);
+        self.changes(&undo)
+    }
+
+    fn changes(&self, undo: &Self::Opt) -> Self::Changes {
+        match (self, undo) {
+            (_, OptionPatch::Unchanged) => OptionChanges::Unchanged,
+            (Some(value), OptionPatch::Patch(undo)) => {
+                let changes = value.changes(undo);
+                if T::is_unchanged(&changes) {
+                    OptionChanges::Unchanged
+                } else {
+                    OptionChanges::Changed(changes)
+                }
+            }
+            _ => OptionChanges::Replaced,
+        }
+    }
+
+    fn is_unchanged(changes: &Self::Changes) -> bool {
+        matches!(changes, OptionChanges::Unchanged)
+    }
+}
+
+// modifications are serialized with a leading tag
+#[cfg(feature = "bytemuck")]
+impl<T: Nested + NestedWire> NestedWire for Option<T> {
+    fn ser_opt(opt: &Self::Opt, data: &mut Vec<u8>) -> Result<(), EncodeError> {
+        match opt {
+            OptionPatch::Unchanged => data.push(0),
+            OptionPatch::Patch(opt) => {
+                data.push(1);
+                T::ser_opt(opt, data)?
+            }
+            OptionPatch::Set(opt) => {
+                data.push(2);
+                T::ser_opt(opt, data)?
+            }
+            OptionPatch::Clear => data.push(3),
+        }
+        Ok(())
+    }
+
+    fn de_opt(bytes: &[u8], tail: &mut usize) -> Result<Self::Opt, DecodeError> {
+        let offset = *tail;
+        Ok(match crate::__private::read_pod::<u8>(bytes, tail)? {
+            0 => OptionPatch::Unchanged,
+            1 => OptionPatch::Patch(T::de_opt(bytes, tail)?),
+            2 => OptionPatch::Set(T::de_opt(bytes, tail)?),
+            3 => OptionPatch::Clear,
+            tag => return Err(DecodeError::new(offset, format!("unknown tag `{tag}`"))),
+        })
+    }
+}
+
+impl<P: Patch, const N: usize> Patch for [P; N] {
+    fn is_modified(&self) -> bool {
+        self.iter().any(P::is_modified)
+    }
+
+    fn take(&mut self) -> Self {
+        self.each_mut().map(P::take)
+    }
+
+    fn merge(&mut self, rhs: Self) {
+        for (lhs, rhs) in self.iter_mut().zip(rhs) {
+            lhs.merge(rhs)
+        }
+    }
+}
+
+// elements are patched per index
+impl<T: WithOpt, const N: usize> WithOpt for [T; N] {
+    type Opt = [T::Opt; N];
+
+    fn patch(&mut self, rhs: &mut Self::Opt) {
+        for (value, rhs) in self.iter_mut().zip(rhs) {
+            value.patch(rhs)
+        }
+    }
+
+    fn patch_with_undo(&mut self, rhs: &mut Self::Opt) -> Self::Opt {
+        std::array::from_fn(|i| self[i].patch_with_undo(&mut rhs[i]))
+    }
+
+    fn into_opt(self) -> Self::Opt {
+        self.map(T::into_opt)
+    }
+}
+
+impl<T: Nested, const N: usize> Nested for [T; N] {
+    fn try_from_opt(opt: Self::Opt) -> Result<Self, MissingFields> {
+        let values = opt.map(T::try_from_opt);
+        let mut missing = MissingFields::new();
+        for (i, value) in values.iter().enumerate() {
+            if let Err(fields) = value {
+                missing.nest(&i.to_string(), fields.clone())
+            }
+        }
+        if !missing.fields().is_empty() {
+            return Err(missing);
+        }
+        Ok(values.map(|value| value.unwrap()))
+    }
+
+    fn is_full(opt: &Self::Opt) -> bool {
+        opt.iter().all(T::is_full)
+    }
+
+    fn validate(&self) -> Result<(), PatchError> {
+        for (i, value) in self.iter().enumerate() {
+            value.validate().map_err(|e| e.nest(&i.to_string()))?
+        }
+        Ok(())
+    }
+
+    fn validate_patched(&self, undo: &Self::Opt) -> Result<(), PatchError> {
+        for (i, (value, undo)) in self.iter().zip(undo).enumerate() {
+            value
+                .validate_patched(undo)
+                .map_err(|e| e.nest(&i.to_string()))?
+        }
+        Ok(())
+    }
+
+    fn check_patch(&self, rhs: &Self::Opt) -> Result<(), PatchError> {
+        for (i, (value, rhs)) in self.iter().zip(rhs).enumerate() {
+            value.check_patch(rhs).map_err(|e| e.nest(&i.to_string()))?
+        }
+        Ok(())
+    }
+}
+
+impl<T: Diff, const N: usize> Diff for [T; N] {
+    fn diff(&self, other: &Self) -> Self::Opt {
+        std::array::from_fn(|i| self[i].diff(&other[i]))
+    }
+}
+
+impl<T: Track, const N: usize> Track for [T; N] {
+    type Changes = [T::Changes; N];
+
+    fn patch_tracked(&mut self, rhs: &mut Self::Opt) -> Self::Changes {
+        std::array::from_fn(|i| self[i].patch_tracked(&mut rhs[i]))
+    }
+
+    fn changes(&self, undo: &Self::Opt) -> Self::Changes {
+        std::array::from_fn(|i| self[i].changes(&undo[i]))
+    }
+
+    fn is_unchanged(changes: &Self::Changes) -> bool {
+        changes.iter().all(T::is_unchanged)
+    }
+}
+
+// elements are serialized in order, each with its own bitmask
+#[cfg(feature = "bytemuck")]
+impl<T: NestedWire, const N: usize> NestedWire for [T; N] {
+    fn ser_opt(opt: &Self::Opt, data: &mut Vec<u8>) -> Result<(), EncodeError> {
+        for (i, opt) in opt.iter().enumerate() {
+            T::ser_opt(opt, data).map_err(|e| e.nest(&i.to_string()))?
+        }
+        Ok(())
+    }
+
+    fn de_opt(bytes: &[u8], tail: &mut usize) -> Result<Self::Opt, DecodeError> {
+        let mut opts = Vec::with_capacity(N);
+        for i in 0..N {
+            opts.push(T::de_opt(bytes, tail).map_err(|e| e.nest(&i.to_string()))?);
+        }
+        Ok(opts.try_into().unwrap_or_else(|_| unreachable!()))
+    }
+}
+
+// the elements of tuples are patched like fields (named by their index)
+macro_rules! impl_tuples {
+    ($(($($t:ident $i:tt),+)),*) => {$(
+        impl<$($t: Patch),+> Patch for ($($t,)+) {
+            fn is_modified(&self) -> bool {
+                $(self.$i.is_modified())||+
+            }
+
+            fn take(&mut self) -> Self {
+                ($(self.$i.take(),)+)
+            }
+
+            fn merge(&mut self, rhs: Self) {
+                $(self.$i.merge(rhs.$i);)+
+            }
+        }
+
+        impl<$($t: WithOpt),+> WithOpt for ($($t,)+) {
+            type Opt = ($($t::Opt,)+);
+
+            fn patch(&mut self, rhs: &mut Self::Opt) {
+                $(self.$i.patch(&mut rhs.$i);)+
+            }
+
+            fn patch_with_undo(&mut self, rhs: &mut Self::Opt) -> Self::Opt {
+                ($(self.$i.patch_with_undo(&mut rhs.$i),)+)
+            }
+
+            fn into_opt(self) -> Self::Opt {
+                ($(self.$i.into_opt(),)+)
+            }
+        }
+
+        impl<$($t: Nested),+> Nested for ($($t,)+) {
+            #[allow(non_snake_case)]
+            fn try_from_opt(opt: Self::Opt) -> Result<Self, MissingFields> {
+                let mut missing = MissingFields::new();
+                $(
+                    let $t = $t::try_from_opt(opt.$i)
+                        .map_err(|fields| missing.nest(stringify!($i), fields))
+                        .ok();
+                )+
+                match ($($t,)+) {
+                    ($(Some($t),)+) => Ok(($($t,)+)),
+                    _ => Err(missing),
+                }
+            }
+
+            fn is_full(opt: &Self::Opt) -> bool {
+                $($t::is_full(&opt.$i))&&+
+            }
+
+            fn validate(&self) -> Result<(), PatchError> {
+                $(self.$i.validate().map_err(|e| e.nest(stringify!($i)))?;)+
+                Ok(())
+            }
+
+            fn validate_patched(&self, undo: &Self::Opt) -> Result<(), PatchError> {
+                $(
+                    self.$i
+                        .validate_patched(&undo.$i)
+                        .map_err(|e| e.nest(stringify!($i)))?;
+                )+
+                Ok(())
+            }
+
+            fn check_patch(&self, rhs: &Self::Opt) -> Result<(), PatchError> {
+                $(self.$i.check_patch(&rhs.$i).map_err(|e| e.nest(stringify!($i)))?;)+
+                Ok(())
+            }
+        }
+
+        impl<$($t: Diff),+> Diff for ($($t,)+) {
+            fn diff(&self, other: &Self) -> Self::Opt {
+                ($(self.$i.diff(&other.$i),)+)
+            }
+        }
+
+        impl<$($t: Track),+> Track for ($($t,)+) {
+            type Changes = ($($t::Changes,)+);
+
+            fn patch_tracked(&mut self, rhs: &mut Self::Opt) -> Self::Changes {
+                ($(self.$i.patch_tracked(&mut rhs.$i),)+)
+            }
+
+            fn changes(&self, undo: &Self::Opt) -> Self::Changes {
+                ($(self.$i.changes(&undo.$i),)+)
+            }
+
+            fn is_unchanged(changes: &Self::Changes) -> bool {
+                $($t::is_unchanged(&changes.$i))&&+
+            }
+        }
+
+        #[cfg(feature = "bytemuck")]
+        impl<$($t: NestedWire),+> NestedWire for ($($t,)+) {
+            fn ser_opt(opt: &Self::Opt, data: &mut Vec<u8>) -> Result<(), EncodeError> {
+                $($t::ser_opt(&opt.$i, data).map_err(|e| e.nest(stringify!($i)))?;)+
+                Ok(())
+            }
+
+            fn de_opt(bytes: &[u8], tail: &mut usize) -> Result<Self::Opt, DecodeError> {
+                Ok(($(
+                    $t::de_opt(bytes, tail).map_err(|e| e.nest(stringify!($i)))?,
+                )+))
+            }
+        }
+    )*};
+}
+
+impl_tuples!(
+    (A 0),
+    (A 0, B 1),
+    (A 0, B 1, C 2),
+    (A 0, B 1, C 2, D 3),
+    (A 0, B 1, C 2, D 3, E 4),
+    (A 0, B 1, C 2, D 3, E 4, F 5)
+);
@@ -0,0 +1,113 @@
+use crate::Patch;
+
+/// The patch of an `Option<T>` field, distinguishing leaving it unchanged from clearing it.
+///
+/// Fields of type `Option<T>` (or marked `#[wopt(nullable)]`) are patched with it:
+/// ```
+/// use wopt::*;
+///
+/// #[derive(Debug, PartialEq, WithOpt)]
+/// #[wopt(derive(Default))]
+/// struct User {
+///     id: u32,
+///     name: Option<u8>,
+/// }
+///
+/// let mut user = User { id: 1, name: Some(2) };
+///
+/// user.patch(&mut UserOpt { id: None, name: Nullable::Unchanged });
+/// assert_eq!(user.name, Some(2));
+///
+/// user.patch(&mut UserOpt { id: None, name: Nullable::Clear });
+/// assert_eq!(user.name, None);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Nullable<T> {
+    /// Leaves the field as-is.
+    #[default]
+    Unchanged,
+    /// Sets the field to `Some(T)`.
+    Set(T),
+    /// Sets the field to `None`.
+    Clear,
+}
+
+impl<T> Nullable<T> {
+    /// Whether the field is either set or cleared.
+    pub const fn is_modified(&self) -> bool {
+        !matches!(self, Self::Unchanged)
+    }
+
+    /// Takes the modification, leaving `self` unchanged.
+    pub const fn take(&mut self) -> Self {
+        std::mem::replace(self, Self::Unchanged)
+    }
+
+    /// Merges a later modification into `self`, which takes precedence.
+    pub fn merge(&mut self, rhs: Self) {
+        if rhs.is_modified() {
+            *self = rhs
+        }
+    }
+
+    /// The new value of the field, if modified.
+    pub fn into_option(self) -> Option<Option<T>> {
+        match self {
+            Self::Unchanged => None,
+            Self::Set(value) => Some(Some(value)),
+            Self::Clear => Some(None),
+        }
+    }
+
+    /// The new value of the field (`None` unless set).
+    pub fn into_value(self) -> Option<T> {
+        self.into_option().flatten()
+    }
+
+    /// Borrows the new value of the field, if set.
+    pub const fn as_ref(&self) -> Nullable<&T> {
+        match self {
+            Self::Unchanged => Nullable::Unchanged,
+            Self::Set(value) => Nullable::Set(value),
+            Self::Clear => Nullable::Clear,
+        }
+    }
+
+    /// Patches `value` with the modification.
+    pub fn patch(self, value: &mut Option<T>) {
+        if let Some(new) = self.into_option() {
+            *value = new
+        }
+    }
+
+    /// Patches `value` like [`Self::patch`], returning the modification restoring it.
+    pub fn patch_with_undo(self, value: &mut Option<T>) -> Self {
+        match self.into_option() {
+            Some(new) => std::mem::replace(value, new).into(),
+            None => Self::Unchanged,
+        }
+    }
+}
+
+impl<T> From<Option<T>> for Nullable<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Self::Set(value),
+            None => Self::Clear,
+        }
+    }
+}
+
+impl<T> Patch for Nullable<T> {
+    fn is_modified(&self) -> bool {
+        Self::is_modified(self)
+    }
+
+    fn take(&mut self) -> Self {
+        Self::take(self)
+    }
+
+    fn merge(&mut self, rhs: Self) {
+        Self::merge(self, rhs)
+    }
+}
@@ -0,0 +1,81 @@
+mod common;
+use common::{flat::*, named::*, unnamed::*};
+use wopt::*;
+
+#[derive(Debug, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq))]
+#[cfg_attr(feature = "bytemuck", wopt(id = 101))]
+struct ExampleReserved {
+    take: u8,
+    r#type: u8,
+}
+
+#[test]
+fn test_accessors_fluent() {
+    let opt = ExampleNamedOpt::default().a(1).c(-3);
+    assert_eq!(
+        opt,
+        ExampleNamedOpt {
+            a: Some(1),
+            b: None,
+            c: Some(-3),
+        }
+    );
+
+    let opt = ExampleUnnamedReqOpt::default()._0(1)._1(2.0);
+    assert_eq!(opt, ExampleUnnamedReqOpt(Some(1), 2.0, None));
+}
+
+#[test]
+fn test_accessors_named() {
+    let mut opt = ExampleNamedOpt::default();
+    opt.set_a(1).set_b(2.0);
+    assert!(opt.is_a_set());
+    assert_eq!(opt.get_a(), Some(&1));
+    assert!(!opt.is_c_set());
+    assert_eq!(opt.get_c(), None);
+
+    opt.clear_a();
+    assert!(!opt.is_a_set());
+    assert_eq!(opt, ExampleNamedOpt::default().b(2.0));
+}
+
+#[test]
+fn test_accessors_unnamed() {
+    let mut opt = ExampleUnnamedReqOpt::default();
+    opt.set_0(1).set_1(2.0);
+    assert!(opt.is_0_set());
+    assert_eq!(opt.get_1(), &2.0);
+
+    opt.clear_0();
+    assert_eq!(opt, ExampleUnnamedReqOpt(None, 2.0, None));
+}
+
+#[test]
+fn test_accessors_nested() {
+    let opt = ExampleNestedOpt::default().a(1).b(|b| b.c(2));
+    assert!(opt.is_b_set());
+    assert_eq!(opt.get_b(), &ExampleNamedOpt::default().c(2));
+
+    let mut opt = opt;
+    opt.clear_b();
+    assert!(!opt.is_b_set());
+    opt.set_b(ExampleNamedOpt::default().a(3));
+    assert_eq!(opt.get_b().get_a(), Some(&3));
+
+    let mut ex = ExampleNested::default();
+    ex.patch(&mut opt);
+    assert_eq!((ex.a, ex.b.a), (1, 3));
+}
+
+#[test]
+fn test_accessors_reserved() {
+    // `take` has no fluent setter, as it's already a method
+    let mut opt = ExampleReservedOpt::default().r#type(1);
+    opt.set_take(2);
+    assert!(opt.is_type_set());
+    assert_eq!(
+        opt.try_into_full(),
+        Ok(ExampleReserved { take: 2, r#type: 1 })
+    );
+}
@@ -0,0 +1,74 @@
+mod common;
+use common::attrs::*;
+
+#[test]
+fn test_attrs_cfg() {
+    let mut ex = ExampleCfg::default();
+
+    let mut ex_opt = ExampleCfgOpt::default().a(A);
+    #[cfg(feature = "bytemuck")]
+    {
+        ex_opt = ex_opt.b(B);
+    }
+    #[cfg(not(feature = "bytemuck"))]
+    {
+        ex_opt = ex_opt.c(C);
+    }
+
+    // forwarded `#[derive(Clone, Copy)]`
+    let copy = ex_opt;
+    assert_eq!(ex.patch_tracked(&mut ex_opt).fields, copy.modified_fields());
+    assert_eq!(
+        ex.diff(&ExampleCfg::default())
+            .modified_fields()
+            .iter()
+            .count(),
+        2
+    );
+    assert_eq!(ex.into_opt().try_into_full(), Ok(ex));
+
+    // disabled fields don't exist
+    #[cfg(feature = "bytemuck")]
+    assert_eq!(ExampleCfgFields::FIELD_NAMES, ["a", "b"]);
+    #[cfg(not(feature = "bytemuck"))]
+    assert_eq!(ExampleCfgFields::FIELD_NAMES, ["a", "c"]);
+
+    #[cfg(feature = "bytemuck")]
+    assert_eq!(ex, ExampleCfg { a: A, b: B });
+    #[cfg(not(feature = "bytemuck"))]
+    assert_eq!(ex, ExampleCfg { a: A, c: C });
+}
+
+#[test]
+fn test_attrs_merged() {
+    let mut ex = ExampleMerged { a: A, b: B, c: C };
+
+    // `a` is required (i.e., not patched) despite its doc comment, `b` is skipped after another attribute
+    let mut ex_opt = ExampleMergedOpt {
+        a: 0,
+        ..Default::default()
+    }
+    .c(0);
+    assert_eq!(ExampleMergedFields::FIELD_NAMES, ["a", "c"]);
+
+    // forwarded `#[derive(Clone, Copy)]`, from the same attribute as the derives
+    let copy = ex_opt;
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex, ExampleMerged { a: A, b: B, c: 0 });
+    assert_eq!(copy.modified_fields(), ExampleMergedFields::C);
+}
+
+#[cfg(feature = "bytemuck")]
+mod wire {
+    use super::*;
+
+    #[test]
+    fn test_attrs_cfg_serialize() {
+        let ex = ExampleCfg { a: A, b: B };
+        assert_eq!(ExampleCfg::UNPADDED_SIZE, 5);
+        assert_eq!(ExampleCfg::deserialize(&ex.serialize()[1..]), ex);
+
+        let ex_opt = ExampleCfgOpt::default().b(B);
+        assert_eq!(ExampleCfgOpt::deserialize(&ex_opt.serialize()[1..]), ex_opt);
+    }
+}
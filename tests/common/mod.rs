@@ -24,6 +24,7 @@ pub mod named {
 
     #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
     #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 1))]
     pub struct ExampleNamed {
         pub a: u8,
         pub b: f32,
@@ -32,6 +33,7 @@ pub mod named {
 
     #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
     #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 2))]
     pub struct ExampleNamedReq {
         pub a: u8,
         #[wopt(required)]
@@ -41,6 +43,7 @@ pub mod named {
 
     #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
     #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 3))]
     pub struct ExampleNamedWith {
         pub a: u8,
         pub b: f32,
@@ -50,6 +53,7 @@ pub mod named {
     #[cfg(feature = "bytemuck")]
     #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
     #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 4))]
     pub struct ExampleNamedFlat {
         pub a: u8,
         #[wopt(optional, serde)]
@@ -60,6 +64,7 @@ pub mod named {
     #[cfg(feature = "bytemuck")]
     #[derive(Clone, Debug, Default, PartialEq, WithOpt)]
     #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 5))]
     pub struct ExampleNamedVec {
         pub a: u8,
         #[wopt(ser = "super::util::ser", de = "super::util::de")]
@@ -75,24 +80,29 @@ pub mod unnamed {
 
     #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
     #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 6))]
     pub struct ExampleUnnamed(pub u8, pub f32, pub i32);
 
     #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
     #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 7))]
     pub struct ExampleUnnamedReq(pub u8, #[wopt(required)] pub f32, pub i32);
 
     #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
     #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 8))]
     pub struct ExampleUnnamedWith(pub u8, pub f32, pub i32);
 
     #[cfg(feature = "bytemuck")]
     #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
     #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 9))]
     pub struct ExampleUnnamedFlat(pub u8, #[wopt(optional, serde)] pub ExampleUnnamed, pub i32);
 
     #[cfg(feature = "bytemuck")]
     #[derive(Clone, Debug, Default, PartialEq, WithOpt)]
     #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 10))]
     pub struct ExampleUnnamedVec(
         pub u8,
         #[wopt(ser = "super::util::ser", de = "super::util::de")] pub Vec<u8>,
@@ -100,6 +110,415 @@ pub mod unnamed {
     );
 }
 
+pub mod generic {
+    pub use super::params::*;
+    use wopt::*;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 11))]
+    pub struct ExampleGeneric<T: Copy, U = i32>
+    where
+        U: Copy,
+    {
+        pub a: T,
+        pub b: f32,
+        pub c: U,
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 12))]
+    pub struct ExampleGenericUnnamed<T: Copy>(pub T, #[wopt(required)] pub f32, pub i32);
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 13))]
+    pub struct ExampleLifetime<'a> {
+        pub a: &'a str,
+        pub b: f32,
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 14))]
+    pub struct ExampleGenericPod<T: bytemuck::Pod> {
+        pub a: T,
+        pub b: f32,
+        pub c: i32,
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 15))]
+    pub struct ExampleGenericFlat<T: bytemuck::Pod + Default> {
+        pub a: u8,
+        #[wopt(optional, serde)]
+        pub b: ExampleGenericPod<T>,
+        pub c: i32,
+    }
+}
+
+pub mod enums {
+    pub use super::params::*;
+    use wopt::*;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 16))]
+    pub enum ExampleEnum {
+        #[default]
+        Idle,
+        Named {
+            a: u8,
+            b: f32,
+            c: i32,
+        },
+        Unnamed(u8, #[wopt(required)] f32),
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 17))]
+    pub enum ExampleEnumGeneric<T: Copy> {
+        Value(T),
+        Pair { a: T, b: T },
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 18))]
+    pub struct ExampleEnumFlat {
+        pub a: u8,
+        #[wopt(optional, serde)]
+        pub b: ExampleEnum,
+        pub c: i32,
+    }
+}
+
+pub mod nullable {
+    pub use super::params::*;
+    use wopt::*;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 19))]
+    pub struct ExampleNullable {
+        pub a: u8,
+        pub b: Option<f32>,
+        pub c: Option<i32>,
+    }
+
+    pub type MaybeU8 = Option<u8>;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 20))]
+    pub struct ExampleNullableAlias(pub u8, #[wopt(nullable)] pub MaybeU8);
+
+    #[derive(Clone, Copy, Debug, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 21))]
+    pub enum ExampleEnumNullable {
+        Idle,
+        Value { a: u8, b: Option<i32> },
+    }
+}
+
+pub mod list {
+    pub use super::named::{ExampleNamed, ExampleNamedOpt};
+    pub use super::params::*;
+    use wopt::*;
+
+    #[derive(Clone, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 22))]
+    pub struct ExampleList {
+        pub a: u8,
+        #[wopt(list_ops)]
+        pub b: Vec<i32>,
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 23))]
+    pub struct ExampleListNested {
+        pub a: u8,
+        #[wopt(list_ops, optional)]
+        pub b: Vec<ExampleNamed>,
+    }
+}
+
+pub mod map {
+    pub use super::named::{ExampleNamed, ExampleNamedOpt};
+    pub use super::params::*;
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+    use wopt::*;
+
+    #[derive(Clone, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 24))]
+    pub struct ExampleMap {
+        pub a: u8,
+        #[wopt(map_ops)]
+        pub b: HashMap<u8, i32>,
+        #[wopt(map_ops)]
+        pub c: BTreeSet<u16>,
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 25))]
+    pub struct ExampleMapNested {
+        pub a: u8,
+        #[wopt(map_ops, optional)]
+        pub b: BTreeMap<u8, ExampleNamed>,
+    }
+}
+
+pub mod validate {
+    pub use super::params::*;
+    use wopt::*;
+
+    pub fn even(value: &u8) -> Result<(), &'static str> {
+        if value.is_multiple_of(2) {
+            Ok(())
+        } else {
+            Err("not even")
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 26))]
+    pub struct ExampleValidate {
+        #[wopt(range = "0..=100")]
+        pub a: u8,
+        #[wopt(validate = "even")]
+        pub b: u8,
+        #[wopt(range = "-10..10")]
+        pub c: Option<i32>,
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 27))]
+    #[wopt(validate = "ExampleValidateBounds::check")]
+    pub struct ExampleValidateBounds {
+        pub min: u8,
+        pub max: u8,
+    }
+
+    impl ExampleValidateBounds {
+        fn check(&self) -> Result<(), String> {
+            if self.min <= self.max {
+                Ok(())
+            } else {
+                Err(format!("{} > {}", self.min, self.max))
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 28))]
+    pub struct ExampleValidateNested {
+        #[wopt(range = "1..")]
+        pub a: u8,
+        #[wopt(optional, serde)]
+        pub b: ExampleValidate,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 29))]
+    pub enum ExampleEnumValidate {
+        Idle,
+        Value(#[wopt(validate = "even")] u8),
+    }
+}
+
+pub mod track {
+    pub use super::named::{
+        ExampleNamed, ExampleNamedChanges, ExampleNamedFields, ExampleNamedOpt,
+    };
+    pub use super::params::*;
+    use std::cell::RefCell;
+    use wopt::*;
+
+    thread_local! {
+        /// The calls of the `on_change` callbacks.
+        pub static CALLS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn on_a(value: &u8) {
+        CALLS.with_borrow_mut(|calls| calls.push(format!("a = {value}")));
+    }
+
+    fn on_change(value: &ExampleTrack, changes: &ExampleTrackChanges) {
+        CALLS.with_borrow_mut(|calls| calls.push(format!("{:?} ({:?})", changes.fields, value.b)));
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 30))]
+    #[wopt(on_change = "on_change")]
+    pub struct ExampleTrack {
+        #[wopt(on_change = "on_a")]
+        pub a: u8,
+        pub b: Option<i32>,
+        #[wopt(optional, serde)]
+        pub c: ExampleNamed,
+    }
+}
+
+pub mod attrs {
+    pub use super::params::*;
+    use wopt::*;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 31))]
+    #[wopt(attr(derive(Clone, Copy)))]
+    pub struct ExampleCfg {
+        #[wopt(attr(allow(dead_code)))]
+        /// Always there.
+        pub a: u8,
+        /// Only with `bytemuck`.
+        #[cfg(feature = "bytemuck")]
+        pub b: f32,
+        #[cfg(not(feature = "bytemuck"))]
+        #[cfg_attr(not(feature = "bytemuck"), allow(dead_code))]
+        pub c: i32,
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq), attr(derive(Clone, Copy)))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 32))]
+    pub struct ExampleMerged {
+        /// Documented, then required.
+        #[wopt(required)]
+        pub a: u8,
+        #[allow(unused)]
+        #[wopt(skip)]
+        pub b: f32,
+        #[wopt(attr(allow(dead_code)))]
+        /// Split across attributes.
+        #[wopt(non_const, attr(doc(hidden)))]
+        pub c: i32,
+    }
+}
+
+pub mod vis {
+    pub use super::params::*;
+    use bytemuck::{Pod, Zeroable};
+    use wopt::*;
+
+    /// Only known to this module.
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    #[repr(transparent)]
+    struct Secret(i32);
+
+    unsafe impl Zeroable for Secret {}
+    unsafe impl Pod for Secret {}
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(
+        derive(Debug, Default, PartialEq),
+        vis = "pub(crate)",
+        name = "ExampleInnerPatch"
+    )]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 33))]
+    struct ExampleInner {
+        b: f32,
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 34))]
+    pub(crate) struct ExampleVis {
+        pub a: u8,
+        #[wopt(optional = "ExampleInnerPatch", serde)]
+        inner: ExampleInner,
+        secret: Secret,
+    }
+
+    impl ExampleVis {
+        pub fn new(a: u8, b: f32, c: i32) -> Self {
+            Self {
+                a,
+                inner: ExampleInner { b },
+                secret: Secret(c),
+            }
+        }
+    }
+
+    impl ExampleVisOpt {
+        pub fn with_inner(self, b: f32) -> Self {
+            self.inner(|inner| inner.b(b))
+        }
+    }
+}
+
+pub mod nested {
+    pub use super::named::{
+        ExampleNamed, ExampleNamedChanges, ExampleNamedFields, ExampleNamedOpt,
+    };
+    pub use super::params::*;
+    use std::{rc::Rc, sync::Arc};
+    use wopt::*;
+
+    // wrappers aren't `Pod`
+    #[derive(Clone, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq), no_serde)]
+    pub struct ExampleNested {
+        #[wopt(optional)]
+        pub boxed: Box<ExampleNamed>,
+        #[wopt(optional)]
+        pub shared: Arc<ExampleNamed>,
+        #[wopt(optional)]
+        pub local: Rc<ExampleNamed>,
+        #[wopt(optional)]
+        pub maybe: Option<ExampleNamed>,
+        #[wopt(optional)]
+        pub array: [ExampleNamed; 2],
+        #[wopt(optional)]
+        pub tuple: (ExampleNamed, Option<Box<ExampleNamed>>),
+    }
+
+    impl ExampleNested {
+        pub fn new(named: ExampleNamed) -> Self {
+            Self {
+                boxed: Box::new(named),
+                shared: Arc::new(named),
+                local: Rc::new(named),
+                maybe: Some(named),
+                array: [named; 2],
+                tuple: (named, Some(Box::new(named))),
+            }
+        }
+    }
+}
+
+pub mod flat {
+    pub use super::named::{
+        ExampleNamed, ExampleNamedChanges, ExampleNamedFields, ExampleNamedOpt,
+    };
+    use wopt::*;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 38))]
+    pub struct ExampleNested {
+        pub a: u8,
+        #[wopt(optional, serde)]
+        pub b: ExampleNamed,
+    }
+}
+
 pub mod unit {
     use bytemuck::{Pod, Zeroable};
     use wopt::*;
@@ -107,6 +526,7 @@ pub mod unit {
     #[cfg(feature = "bytemuck")]
     #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
     #[wopt(derive(Debug, Default, PartialEq))]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 35))]
     pub struct ExampleUnit;
 }
 
@@ -120,6 +540,7 @@ mod bf {
     #[wopt(
         bf = "++++[++++>---<]>+.[-->+++<]>-.---.[--->+<]>-.++[->+++<]>++.-[--->+<]>--.++[->++<]>.[-->+++<]>-.+.-----.--[--->+<]>.---------.++++++++.[---->+<]>+++.[-->+++++<]>.[------->++<]>+.--[--->+<]>---.++.-----------.--------.+++++++++++.[->+++<]>+.-[--->+<]>+++++."
     )]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 36))]
     pub struct ExampleNamed {
         pub a: u8,
         #[wopt(required)]
@@ -132,5 +553,6 @@ mod bf {
     #[wopt(
         bf = "++++[++++>---<]>+.[-->+++<]>-.---.[--->+<]>-.++[->+++<]>++.-[--->+<]>--.++[->++<]>.[-->+++<]>-.+.-----.--[--->+<]>.---------.++++++++.[---->+<]>+++.[-->+++++<]>.[------->++<]>+.--[--->+<]>---.++.-----------.--------.+++++++++++.[->+++<]>+.-[--->+<]>+++++."
     )]
+    #[cfg_attr(feature = "bytemuck", wopt(id = 37))]
     pub struct ExampleUnnamed(pub u8, #[wopt(required)] pub f32, #[wopt(skip)] pub i32);
 }
@@ -0,0 +1,167 @@
+#![cfg(feature = "bytemuck")]
+
+mod common;
+use common::enums::*;
+use common::list::*;
+use common::named::*;
+use common::nullable::*;
+use wopt::*;
+
+const NAMED: ExampleNamed = ExampleNamed { a: A, b: B, c: C };
+
+#[test]
+fn test_decode_valid() {
+    assert_eq!(ExampleNamed::try_deserialize(&NAMED.serialize()), Ok(NAMED));
+
+    let ex_opt = ExampleNamedOpt::default().a(A).c(C);
+    assert_eq!(
+        ExampleNamedOpt::try_deserialize(&ex_opt.serialize()),
+        Ok(ex_opt)
+    );
+
+    let ex = ExampleEnum::Named { a: A, b: B, c: C };
+    assert_eq!(ExampleEnum::try_deserialize(&ex.serialize()), Ok(ex));
+}
+
+#[test]
+fn test_decode_framing() {
+    let bytes = NAMED.serialize();
+
+    let e = ExampleNamedOpt::try_deserialize(&bytes).unwrap_err();
+    assert_eq!((e.offset(), e.field()), (0, None));
+    assert_eq!(
+        e.reason(),
+        format!(
+            "expected ID {}, found {}",
+            ExampleNamedOpt::ID,
+            ExampleNamed::ID
+        )
+    );
+
+    let mut bytes = bytes.to_vec();
+    bytes.push(0);
+    let e = ExampleNamed::try_deserialize(&bytes).unwrap_err();
+    assert_eq!(e.to_string(), "invalid bytes at offset 10: 1 trailing byte");
+}
+
+#[test]
+fn test_decode_head_tail() {
+    // the head is left at the last field read and the tail past the value
+    let bytes = [NAMED.serialize(), NAMED.serialize()].concat();
+    let [mut h, mut t] = [1, 0];
+    assert_eq!(
+        ExampleNamed::deserialize_with(&bytes, &mut h, &mut t),
+        NAMED
+    );
+    assert_eq!((h, t), (6, 10));
+
+    // which is where the next value starts
+    h = t + 1;
+    assert_eq!(
+        ExampleNamed::deserialize_with(&bytes, &mut h, &mut t),
+        NAMED
+    );
+    assert_eq!((h, t), (16, 20));
+
+    let ex_opt = ExampleNamedOpt::default().a(A).b(B);
+    let bytes = ex_opt.serialize();
+    let [mut h, mut t] = [1, 0];
+    assert_eq!(
+        ExampleNamedOpt::deserialize_with(&bytes, &mut h, &mut t),
+        ex_opt
+    );
+    assert_eq!((h, t), (3, bytes.len()));
+}
+
+#[test]
+fn test_decode_truncated() {
+    let bytes = NAMED.serialize();
+    let e = ExampleNamed::try_deserialize(&bytes[..7]).unwrap_err();
+    assert_eq!(e.field(), Some("c"));
+    assert_eq!(
+        e.to_string(),
+        "invalid field `c` at offset 6: expected 4 bytes, found 1"
+    );
+
+    // nested fields are joined by a dot
+    let ex_opt = ExampleNamedFlatOpt::default().b(|b| b.c(C));
+    let bytes = ex_opt.serialize();
+    let e = ExampleNamedFlatOpt::try_deserialize(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(e.field(), Some("b.c"));
+}
+
+#[test]
+fn test_decode_length_prefix() {
+    let ex = ExampleNamedVec {
+        a: A,
+        b: vec![1, 2, 3],
+        c: C,
+    };
+    let mut bytes = ex.serialize();
+    assert_eq!(ExampleNamedVec::try_deserialize(&bytes), Ok(ex));
+
+    bytes[2] = 8;
+    let e = ExampleNamedVec::try_deserialize(&bytes).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "invalid field `b` at offset 2: length prefix 8 exceeds the remaining 7 bytes"
+    );
+}
+
+// zero-sized elements take no bytes
+#[derive(Debug, Default, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq), id = 100)]
+struct ExampleZst {
+    #[wopt(list_ops)]
+    a: Vec<()>,
+}
+
+#[test]
+fn test_decode_count() {
+    // while their count can't exceed the remaining bytes
+    let e = ExampleZst::try_deserialize(&[ExampleZst::ID, 0xFF, 0xFF]).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "invalid field `a` at offset 1: length 65535 exceeds the remaining 0 bytes"
+    );
+    let ex = ExampleZst::default();
+    assert_eq!(ExampleZst::try_deserialize(&ex.serialize()), Ok(ex));
+
+    // nor can the number of operations of a patch
+    let mut bytes = ExampleListOpt::default().b(|b| b.push(1)).serialize();
+    bytes[2..4].copy_from_slice(&u16::MAX.to_le_bytes());
+    let e = ExampleListOpt::try_deserialize(&bytes).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "invalid field `b` at offset 2: length 65535 exceeds the remaining 5 bytes"
+    );
+}
+
+#[test]
+fn test_decode_unknown() {
+    // mask bits of no field
+    let e = ExampleNamedOpt::try_deserialize(&[ExampleNamedOpt::ID, 0b1000]).unwrap_err();
+    assert_eq!((e.offset(), e.reason()), (1, "unknown mask bits"));
+
+    let e = ExampleEnumOpt::try_deserialize(&[ExampleEnumOpt::ID, 2, 0b100]).unwrap_err();
+    assert_eq!((e.offset(), e.reason()), (2, "unknown mask bits"));
+
+    let e = ExampleEnum::try_deserialize(&[ExampleEnum::ID, 3]).unwrap_err();
+    assert_eq!((e.offset(), e.reason()), (1, "unknown variant `3`"));
+
+    // tags of no value (or operation)
+    let mut bytes = ExampleNullable::default().serialize();
+    bytes[2] = 2;
+    let e = ExampleNullable::try_deserialize(&bytes).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "invalid field `b` at offset 2: unknown tag `2`"
+    );
+
+    let ex_opt = ExampleListOpt::default().b(|b| b.pop());
+    let mut bytes = ex_opt.serialize();
+    *bytes.last_mut().unwrap() = 8;
+    let e = ExampleListOpt::try_deserialize(&bytes).unwrap_err();
+    assert_eq!(e.field(), Some("b"));
+    assert_eq!(e.reason(), "unknown list operation `8`");
+}
@@ -0,0 +1,140 @@
+mod common;
+use common::{enums::*, flat::*, named::*, unnamed::*};
+use wopt::*;
+
+/// Lacks `PartialEq`, so `diff` isn't generated (rather than failing to compile).
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(transparent)]
+struct NoEq(u8);
+
+unsafe impl bytemuck::Zeroable for NoEq {}
+unsafe impl bytemuck::Pod for NoEq {}
+
+#[derive(Clone, Copy, Debug, Default, WithOpt)]
+#[cfg_attr(feature = "bytemuck", wopt(id = 101))]
+struct ExampleNoEq {
+    a: u8,
+    b: NoEq,
+}
+
+#[test]
+fn test_diff_named() {
+    let a = ExampleNamed { a: A, b: B, c: C };
+    let b = ExampleNamed { a: A, b: 1.0, c: 2 };
+
+    let mut diff = a.diff(&b);
+    assert_eq!(
+        diff,
+        ExampleNamedOpt {
+            a: None,
+            b: Some(1.0),
+            c: Some(2),
+        }
+    );
+
+    let mut ex = a;
+    ex.patch(&mut diff);
+    assert_eq!(ex, b);
+
+    // identical values don't differ
+    assert!(!a.diff(&a).is_modified());
+}
+
+#[test]
+fn test_diff_unnamed() {
+    let a = ExampleUnnamed(A, B, C);
+    let b = ExampleUnnamed(1, B, C);
+    assert_eq!(a.diff(&b), ExampleUnnamedOpt(Some(1), None, None));
+
+    // required fields are always set
+    let a = ExampleUnnamedReq(A, B, C);
+    let b = ExampleUnnamedReq(A, 1.0, C);
+    assert_eq!(a.diff(&b), ExampleUnnamedReqOpt(None, 1.0, None));
+}
+
+#[test]
+fn test_diff_nested() {
+    let a = ExampleNested {
+        a: A,
+        b: ExampleNamed { a: A, b: B, c: C },
+    };
+    let b = ExampleNested {
+        a: A,
+        b: ExampleNamed { a: A, b: B, c: 1 },
+    };
+
+    let mut diff = Diff::diff(&a, &b);
+    assert_eq!(
+        diff,
+        ExampleNestedOpt {
+            a: None,
+            b: ExampleNamedOpt {
+                c: Some(1),
+                ..Default::default()
+            },
+        }
+    );
+
+    let mut ex = a;
+    ex.patch(&mut diff);
+    assert_eq!(ex, b);
+}
+
+#[test]
+fn test_diff_enum() {
+    let a = ExampleEnum::Named { a: A, b: B, c: C };
+
+    // same variant, only the differing fields
+    let b = ExampleEnum::Named { a: A, b: B, c: 1 };
+    assert_eq!(
+        a.diff(&b),
+        ExampleEnumOpt::Named {
+            a: None,
+            b: None,
+            c: Some(1),
+        }
+    );
+    assert_eq!(a.diff(&a), ExampleEnumOpt::Unmodified);
+    assert_eq!(
+        ExampleEnum::Idle.diff(&ExampleEnum::Idle),
+        ExampleEnumOpt::Unmodified
+    );
+
+    // another variant, with all of its fields
+    let b = ExampleEnum::Unnamed(1, 2.0);
+    let mut diff = a.diff(&b);
+    assert_eq!(diff, ExampleEnumOpt::Unnamed(Some(1), 2.0));
+
+    let mut ex = a;
+    ex.patch(&mut diff);
+    assert_eq!(ex, b);
+}
+
+#[test]
+fn test_diff_no_eq() {
+    let mut ex = ExampleNoEq { a: A, b: NoEq(1) };
+    ex.patch(&mut ExampleNoEqOpt {
+        a: None,
+        b: Some(NoEq(2)),
+    });
+    assert_eq!(ex.b.0, 2);
+}
+
+#[test]
+fn test_diff_generic() {
+    use common::generic::*;
+
+    let a = ExampleLifetime { a: "a", b: B };
+    let b = ExampleLifetime { a: "b", b: B };
+    assert_eq!(
+        a.diff(&b),
+        ExampleLifetimeOpt {
+            a: Some("b"),
+            b: None,
+        }
+    );
+
+    let a = ExampleGeneric::<u8, i64> { a: A, b: B, c: 7 };
+    let b = ExampleGeneric::<u8, i64> { a: A, b: B, c: 8 };
+    assert_eq!(a.diff(&b).c, Some(8));
+}
@@ -0,0 +1,224 @@
+mod common;
+use common::enums::*;
+
+#[test]
+fn test_enum_stable_same_variant() {
+    let mut ex = ExampleEnum::Named { a: A, b: B, c: C };
+
+    let mut ex_opt = ExampleEnumOpt::Named {
+        a: Some(1),
+        b: None,
+        c: Some(-3),
+    };
+    assert!(ex_opt.is_modified());
+
+    ex.patch(&mut ex_opt);
+    assert!(!ex_opt.is_modified());
+
+    assert_eq!(ex, ExampleEnum::Named { a: 1, b: B, c: -3 })
+}
+
+#[test]
+fn test_enum_stable_switch_variant() {
+    let mut ex = ExampleEnum::Idle;
+
+    let mut ex_opt = ExampleEnumOpt::Unnamed(Some(1), 2.0);
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex, ExampleEnum::Unnamed(1, 2.0));
+
+    let mut ex_opt = ExampleEnumOpt::Idle;
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex, ExampleEnum::Idle)
+}
+
+#[test]
+fn test_enum_stable_req() {
+    let mut ex = ExampleEnum::Unnamed(A, B);
+
+    // required fields are never patched in-place
+    let mut ex_opt = ExampleEnumOpt::Unnamed(Some(1), 2.0);
+    ex.patch(&mut ex_opt);
+
+    assert_eq!(ex, ExampleEnum::Unnamed(1, B))
+}
+
+#[test]
+fn test_enum_stable_incomplete_switch() {
+    let mut ex = ExampleEnum::Idle;
+
+    // switching variants requires every field
+    let mut ex_opt = ExampleEnumOpt::Named {
+        a: Some(1),
+        b: None,
+        c: None,
+    };
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex, ExampleEnum::Idle);
+
+    // the patch is left unconsumed
+    assert_eq!(
+        ex_opt,
+        ExampleEnumOpt::Named {
+            a: Some(1),
+            b: None,
+            c: None,
+        }
+    );
+
+    // until complete
+    ex_opt.merge(ExampleEnumOpt::Named {
+        a: None,
+        b: Some(B),
+        c: Some(C),
+    });
+    ex.patch(&mut ex_opt);
+    assert!(!ex_opt.is_modified());
+    assert_eq!(ex, ExampleEnum::Named { a: 1, b: B, c: C })
+}
+
+#[test]
+fn test_enum_stable_unmodified() {
+    let mut ex = ExampleEnum::Named { a: A, b: B, c: C };
+
+    let mut ex_opt = ExampleEnumOpt::default();
+    assert!(!ex_opt.is_modified());
+
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex, ExampleEnum::Named { a: A, b: B, c: C })
+}
+
+#[test]
+fn test_enum_into_opt() {
+    let ex = ExampleEnum::Named { a: A, b: B, c: C };
+    assert_eq!(
+        ex.into_opt(),
+        ExampleEnumOpt::Named {
+            a: Some(A),
+            b: Some(B),
+            c: Some(C)
+        }
+    );
+
+    let mut ex = ExampleEnum::Idle;
+    ex.patch(&mut ExampleEnum::Unnamed(A, B).into_opt());
+    assert_eq!(ex, ExampleEnum::Unnamed(A, B))
+}
+
+#[test]
+fn test_enum_generic_stable() {
+    let mut ex = ExampleEnumGeneric::Pair { a: 1u64, b: 2 };
+
+    let mut ex_opt = ExampleEnumGenericOpt::Pair {
+        a: None,
+        b: Some(3),
+    };
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex, ExampleEnumGeneric::Pair { a: 1, b: 3 });
+
+    let mut ex_opt = ExampleEnumGenericOpt::Value(Some(4));
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex, ExampleEnumGeneric::Value(4))
+}
+
+#[test]
+#[cfg(feature = "bytemuck")]
+fn test_enum_bytemuck_serialize() {
+    let ex = ExampleEnum::Named { a: A, b: B, c: C };
+    let serialized = ex.serialize();
+    assert_eq!(
+        [ExampleEnum::ID, 1, 69, 0, 0, 210, 67, 0, 248, 255, 255].as_slice(),
+        serialized,
+    );
+
+    // smaller variants are padded
+    let ex = ExampleEnum::Idle;
+    let serialized = ex.serialize();
+    assert_eq!(
+        [ExampleEnum::ID, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0].as_slice(),
+        serialized,
+    );
+}
+
+#[test]
+#[cfg(feature = "bytemuck")]
+fn test_enum_bytemuck_serialize_opt() {
+    let ex = ExampleEnumOpt::Named {
+        a: Some(A),
+        b: None,
+        c: Some(C),
+    };
+    let serialized = ex.serialize();
+    assert_eq!(
+        [ExampleEnumOpt::ID, 1, 5, 69, 0, 248, 255, 255].as_slice(),
+        serialized
+    );
+
+    let ex = ExampleEnumOpt::Unmodified;
+    let serialized = ex.serialize();
+    assert_eq!([ExampleEnumOpt::ID, u8::MAX].as_slice(), serialized);
+}
+
+#[test]
+#[cfg(feature = "bytemuck")]
+fn test_enum_bytemuck_deserialize() {
+    for ex in [
+        ExampleEnum::Idle,
+        ExampleEnum::Named { a: A, b: B, c: C },
+        ExampleEnum::Unnamed(A, B),
+    ] {
+        let bytes = ex.serialize();
+        let deserialized = ExampleEnum::deserialize(&bytes[1..]);
+        assert_eq!(ex, deserialized);
+    }
+}
+
+#[test]
+#[cfg(feature = "bytemuck")]
+fn test_enum_bytemuck_deserialize_opt() {
+    for ex in [
+        ExampleEnumOpt::Unmodified,
+        ExampleEnumOpt::Idle,
+        ExampleEnumOpt::Named {
+            a: Some(A),
+            b: None,
+            c: Some(C),
+        },
+        ExampleEnumOpt::Unnamed(None, B),
+    ] {
+        let bytes = ex.serialize();
+        let deserialized = ExampleEnumOpt::deserialize(&bytes[1..]);
+        assert_eq!(ex, deserialized);
+    }
+}
+
+#[test]
+#[cfg(feature = "bytemuck")]
+fn test_enum_bytemuck_deserialize_flat_opt() {
+    let ex = ExampleEnumFlatOpt {
+        a: Some(A),
+        b: ExampleEnumOpt::Named {
+            a: None,
+            b: Some(B),
+            c: None,
+        },
+        c: Some(C),
+    };
+    let bytes = ex.serialize();
+    let deserialized = ExampleEnumFlatOpt::deserialize(&bytes[1..]);
+    assert_eq!(ex, deserialized);
+
+    let mut flat = ExampleEnumFlat {
+        a: 0,
+        b: ExampleEnum::Named { a: A, b: 0.0, c: C },
+        c: 0,
+    };
+    flat.patch(&mut ExampleEnumFlatOpt::deserialize(&bytes[1..]));
+    assert_eq!(
+        flat,
+        ExampleEnumFlat {
+            a: A,
+            b: ExampleEnum::Named { a: A, b: B, c: C },
+            c: C
+        }
+    )
+}
@@ -0,0 +1,113 @@
+mod common;
+use common::enums::*;
+use common::named::*;
+use common::nullable::*;
+
+#[test]
+fn test_fields_modified() {
+    let ex_opt = ExampleNamedOpt::default().a(A).c(C);
+    assert_eq!(
+        ex_opt.modified_fields(),
+        ExampleNamedFields::A | ExampleNamedFields::C
+    );
+    assert!(ExampleNamedOpt::default().modified_fields().is_empty());
+
+    // clearing is a modification too
+    let ex_opt = ExampleNullableOpt::default().b(None);
+    assert_eq!(ex_opt.modified_fields(), ExampleNullableFields::B);
+
+    // required fields are never modified
+    let ex_opt = ExampleNamedReqOpt {
+        a: None,
+        b: B,
+        c: Some(C),
+    };
+    assert_eq!(ex_opt.modified_fields(), ExampleNamedReqFields::C);
+}
+
+#[test]
+fn test_fields_retain_remove() {
+    let mut ex_opt = ExampleNamedOpt::default().a(A).b(B).c(C);
+    ex_opt.retain(ExampleNamedFields::A | ExampleNamedFields::B);
+    assert_eq!(ex_opt, ExampleNamedOpt::default().a(A).b(B));
+
+    ex_opt.remove(ExampleNamedFields::A | ExampleNamedFields::C);
+    assert_eq!(ex_opt, ExampleNamedOpt::default().b(B));
+
+    ex_opt.retain(ExampleNamedFields::empty());
+    assert_eq!(ex_opt, ExampleNamedOpt::default());
+}
+
+#[test]
+fn test_fields_to_opt_masked() {
+    let ex = ExampleNamed { a: A, b: B, c: C };
+    assert_eq!(
+        ex.to_opt_masked(ExampleNamedFields::B),
+        ExampleNamedOpt::default().b(B)
+    );
+    assert_eq!(ex.to_opt_masked(ExampleNamedFields::all()), ex.into_opt());
+
+    let ex = ExampleNullable {
+        a: A,
+        b: None,
+        c: Some(C),
+    };
+    assert_eq!(
+        ex.to_opt_masked(ExampleNullableFields::B),
+        ExampleNullableOpt::default().b(None)
+    );
+}
+
+#[test]
+fn test_fields_names() {
+    assert_eq!(ExampleNamedFields::FIELD_NAMES, ["a", "b", "c"]);
+
+    let fields = ExampleNamedFields::A | ExampleNamedFields::C;
+    assert_eq!(fields.names().collect::<Vec<_>>(), ["a", "c"]);
+    assert_eq!(fields.iter().count(), 2);
+    assert_eq!(ExampleNamedFields::empty().names().count(), 0);
+}
+
+#[test]
+fn test_fields_enum() {
+    let ex_opt = ExampleEnumOpt::Named {
+        a: Some(A),
+        b: None,
+        c: Some(C),
+    };
+    assert_eq!(
+        ex_opt.modified_fields(),
+        ExampleEnumFields::NamedA | ExampleEnumFields::NamedC
+    );
+    assert!(ExampleEnumOpt::Unmodified.modified_fields().is_empty());
+    assert!(ExampleEnumOpt::Idle.modified_fields().is_empty());
+
+    // required fields are never modified
+    let ex_opt = ExampleEnumOpt::Unnamed(None, B);
+    assert!(ex_opt.modified_fields().is_empty());
+
+    // the fields of other variants are ignored
+    let mut ex_opt = ExampleEnumOpt::Unnamed(Some(A), B);
+    ex_opt.remove(ExampleEnumFields::NamedA);
+    assert_eq!(ex_opt, ExampleEnumOpt::Unnamed(Some(A), B));
+    ex_opt.retain(ExampleEnumFields::NamedA);
+    assert_eq!(ex_opt, ExampleEnumOpt::Unnamed(None, B));
+
+    let ex = ExampleEnum::Named { a: A, b: B, c: C };
+    assert_eq!(
+        ex.to_opt_masked(ExampleEnumFields::NamedB),
+        ExampleEnumOpt::Named {
+            a: None,
+            b: Some(B),
+            c: None,
+        }
+    );
+    assert_eq!(ex.to_opt_masked(ExampleEnumFields::all()), ex.into_opt());
+
+    assert_eq!(
+        ExampleEnumFields::FIELD_NAMES,
+        ["Named.a", "Named.b", "Named.c", "Unnamed.0", "Unnamed.1"]
+    );
+    let fields = ExampleEnumFields::NamedC | ExampleEnumFields::UnnamedF0;
+    assert_eq!(fields.names().collect::<Vec<_>>(), ["Named.c", "Unnamed.0"]);
+}
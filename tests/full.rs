@@ -0,0 +1,98 @@
+mod common;
+use common::{enums::*, flat::*, generic::*, named::*, unnamed::*};
+use wopt::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq))]
+#[cfg_attr(feature = "bytemuck", wopt(id = 100))]
+struct ExampleSkip {
+    a: u8,
+    #[wopt(skip)]
+    b: f32,
+    #[wopt(skip, default = C * 2)]
+    c: i32,
+}
+
+#[test]
+fn test_full_named() {
+    let ex = ExampleNamed { a: A, b: B, c: C };
+    assert_eq!(ex.into_opt().try_into_full(), Ok(ex));
+
+    let missing = ExampleNamedOpt {
+        a: Some(A),
+        b: None,
+        c: None,
+    }
+    .try_into_full()
+    .unwrap_err();
+    assert_eq!(missing.fields(), ["b", "c"]);
+    assert_eq!(missing.to_string(), "missing fields: b, c");
+}
+
+#[test]
+fn test_full_unnamed() {
+    let ex = ExampleUnnamedReq::try_from(ExampleUnnamedReqOpt(Some(A), B, Some(C)));
+    assert_eq!(ex, Ok(ExampleUnnamedReq(A, B, C)));
+
+    let missing = ExampleUnnamedReqOpt(None, B, Some(C)).try_into_full();
+    assert_eq!(missing.unwrap_err().fields(), ["0"]);
+}
+
+#[test]
+fn test_full_nested() {
+    let missing = ExampleNestedOpt {
+        a: None,
+        b: ExampleNamedOpt {
+            a: Some(A),
+            ..Default::default()
+        },
+    }
+    .try_into_full()
+    .unwrap_err();
+    assert_eq!(missing.fields(), ["a", "b.b", "b.c"]);
+
+    let ex = ExampleNested {
+        a: A,
+        b: ExampleNamed { a: A, b: B, c: C },
+    };
+    assert_eq!(ExampleNested::try_from(ex.into_opt()), Ok(ex));
+}
+
+#[test]
+fn test_full_skip() {
+    let ex = ExampleSkipOpt { a: Some(A) }.try_into_full();
+    assert_eq!(
+        ex,
+        Ok(ExampleSkip {
+            a: A,
+            b: 0.0,
+            c: C * 2,
+        })
+    );
+}
+
+#[test]
+fn test_full_enum() {
+    let ex = ExampleEnumOpt::Unnamed(Some(A), B).try_into_full();
+    assert_eq!(ex, Ok(ExampleEnum::Unnamed(A, B)));
+
+    let missing = ExampleEnumOpt::Named {
+        a: None,
+        b: Some(B),
+        c: None,
+    }
+    .try_into_full()
+    .unwrap_err();
+    assert_eq!(missing.fields(), ["a", "c"]);
+
+    // an unmodified enum is missing as a whole
+    let missing = ExampleEnumOpt::Unmodified.try_into_full().unwrap_err();
+    assert!(missing.fields().is_empty());
+    assert_eq!(missing.to_string(), "missing value");
+}
+
+#[test]
+fn test_full_generic() {
+    let ex = ExampleGeneric::<u8, i64> { a: A, b: B, c: 7 };
+    assert_eq!(ExampleGeneric::try_from(ex.into_opt()), Ok(ex));
+}
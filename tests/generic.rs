@@ -0,0 +1,114 @@
+mod common;
+use common::generic::*;
+
+#[test]
+fn test_generic_stable() {
+    let mut ex = ExampleGeneric { a: A, b: B, c: C };
+
+    let mut ex_opt = ExampleGenericOpt::default();
+    assert!(!ex_opt.is_modified());
+
+    ex_opt.a = Some(1);
+    ex_opt.c = Some(-3);
+    assert!(ex_opt.is_modified());
+
+    ex.patch(&mut ex_opt);
+    assert!(!ex_opt.is_modified());
+
+    assert_eq!(ex, ExampleGeneric { a: 1, b: B, c: -3 })
+}
+
+#[test]
+fn test_generic_unnamed_stable() {
+    let mut ex = ExampleGenericUnnamed(A, B, C);
+
+    let mut ex_opt = ExampleGenericUnnamedOpt(Some(1), 2.0, None);
+
+    ex.patch(&mut ex_opt);
+    assert!(!ex_opt.is_modified());
+
+    assert_eq!(ex, ExampleGenericUnnamed(1, B, C))
+}
+
+#[test]
+fn test_generic_into_opt() {
+    let ex = ExampleGeneric::<u8, i64> { a: A, b: B, c: 7 };
+    assert_eq!(
+        ex.into_opt(),
+        ExampleGenericOpt {
+            a: Some(A),
+            b: Some(B),
+            c: Some(7)
+        }
+    )
+}
+
+#[test]
+fn test_lifetime_stable() {
+    let s = String::from("patched");
+    let mut ex = ExampleLifetime {
+        a: "original",
+        b: B,
+    };
+
+    let mut ex_opt = ExampleLifetimeOpt {
+        a: Some(s.as_str()),
+        b: None,
+    };
+    ex.patch(&mut ex_opt);
+
+    assert_eq!(ex, ExampleLifetime { a: "patched", b: B })
+}
+
+#[test]
+#[cfg(feature = "bytemuck")]
+fn test_generic_bytemuck_serialize() {
+    let ex = ExampleGenericPod { a: A, b: B, c: C };
+    let serialized = ex.serialize();
+    assert_eq!(
+        [
+            ExampleGenericPod::<u8>::ID,
+            69,
+            0,
+            0,
+            210,
+            67,
+            0,
+            248,
+            255,
+            255
+        ]
+        .as_slice(),
+        serialized,
+    );
+}
+
+#[test]
+#[cfg(feature = "bytemuck")]
+fn test_generic_bytemuck_deserialize() {
+    let ex = ExampleGenericPod {
+        a: 7u16,
+        b: B,
+        c: C,
+    };
+    let bytes = ex.serialize();
+    let deserialized = ExampleGenericPod::deserialize(&bytes[1..]);
+    assert_eq!(ex, deserialized);
+}
+
+#[test]
+#[cfg(feature = "bytemuck")]
+fn test_generic_bytemuck_deserialize_flat_opt() {
+    let ex = ExampleGenericFlatOpt {
+        a: Some(A),
+        b: ExampleGenericPodOpt {
+            a: Some(7u32),
+            b: None,
+            c: Some(C),
+        },
+        c: None,
+    };
+    let bytes = ex.serialize();
+    let deserialized = ExampleGenericFlatOpt::deserialize(&bytes[1..]);
+    assert_eq!(ex, deserialized);
+}
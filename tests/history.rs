@@ -0,0 +1,162 @@
+mod common;
+use common::{enums::*, list::*, named::*};
+use wopt::*;
+
+fn patch(a: Option<u8>, b: Option<f32>, c: Option<i32>) -> ExampleNamedOpt {
+    ExampleNamedOpt { a, b, c }
+}
+
+#[test]
+fn test_history_undo_redo() {
+    let original = ExampleNamed { a: A, b: B, c: C };
+    let mut history = History::new(original);
+    assert!(!history.can_undo());
+
+    history.apply(&mut patch(Some(1), None, None));
+    history.apply(&mut patch(None, Some(2.0), Some(3)));
+    assert_eq!(history.get(), &ExampleNamed { a: 1, b: 2.0, c: 3 });
+
+    assert!(history.undo());
+    assert_eq!(history.get(), &ExampleNamed { a: 1, b: B, c: C });
+    assert!(history.undo());
+    assert_eq!(history.get(), &original);
+    assert!(!history.undo());
+
+    assert!(history.redo());
+    assert!(history.redo());
+    assert!(!history.redo());
+    assert_eq!(history.get(), &ExampleNamed { a: 1, b: 2.0, c: 3 });
+
+    // applying forgets the undone steps
+    history.undo();
+    history.apply(&mut patch(Some(4), None, None));
+    assert!(!history.can_redo());
+    assert_eq!(history.into_inner(), ExampleNamed { a: 4, b: B, c: C });
+}
+
+#[test]
+fn test_history_unmodified() {
+    let mut history = History::new(ExampleNamed { a: A, b: B, c: C });
+    history.apply(&mut ExampleNamedOpt::default());
+    assert!(!history.can_undo());
+
+    // ignored enum patches aren't recorded either
+    let mut history = History::new(ExampleEnum::Idle);
+    history.apply(&mut ExampleEnumOpt::Unnamed(None, B));
+    assert!(!history.can_undo());
+}
+
+#[test]
+fn test_history_depth() {
+    let mut history = History::with_depth(ExampleNamed { a: 0, b: B, c: C }, 2);
+    for a in 1..=4 {
+        history.apply(&mut patch(Some(a), None, None));
+    }
+
+    assert!(history.undo());
+    assert!(history.undo());
+    assert!(!history.undo());
+    assert_eq!(history.get().a, 2);
+
+    history.set_depth(0);
+    assert!(history.redo());
+    assert!(!history.can_undo());
+}
+
+#[test]
+fn test_history_group() {
+    let original = ExampleNamed { a: A, b: B, c: C };
+    let mut history = History::new(original);
+
+    history.begin_group();
+    history.apply(&mut patch(Some(1), None, None));
+    history.begin_group();
+    history.apply(&mut patch(Some(2), Some(2.0), None));
+    history.end_group();
+    history.apply(&mut patch(None, None, Some(3)));
+    history.end_group();
+
+    history.apply(&mut patch(None, None, Some(4)));
+
+    assert!(history.undo());
+    assert_eq!(history.get(), &ExampleNamed { a: 2, b: 2.0, c: 3 });
+
+    // the whole group at once
+    assert!(history.undo());
+    assert_eq!(history.get(), &original);
+    assert!(!history.can_undo());
+
+    assert!(history.redo());
+    assert_eq!(history.get(), &ExampleNamed { a: 2, b: 2.0, c: 3 });
+}
+
+#[test]
+fn test_history_enum() {
+    let original = ExampleEnum::Named { a: A, b: B, c: C };
+    let mut history = History::new(original);
+
+    history.apply(&mut ExampleEnumOpt::Unnamed(Some(1), 2.0));
+    history.apply(&mut ExampleEnumOpt::Unnamed(Some(3), 2.0));
+    assert_eq!(history.get(), &ExampleEnum::Unnamed(3, 2.0));
+
+    history.undo();
+    history.undo();
+    assert_eq!(history.get(), &original);
+}
+
+#[test]
+fn test_history_group_merged() {
+    // the operations of the group are undone in reverse
+    let original = ExampleNamed { a: A, b: B, c: C };
+    let mut history = History::new(original);
+
+    history.begin_group();
+    history.apply(&mut patch(Some(1), None, None));
+    history.apply(&mut patch(Some(2), Some(2.0), None));
+    history.apply(&mut patch(None, None, Some(3)));
+    history.end_group();
+    assert_eq!(history.get(), &ExampleNamed { a: 2, b: 2.0, c: 3 });
+
+    assert!(history.undo());
+    assert_eq!(history.get(), &original);
+    assert!(history.redo());
+    assert_eq!(history.get(), &ExampleNamed { a: 2, b: 2.0, c: 3 });
+
+    // list operations included
+    let original = ExampleList {
+        a: A,
+        b: vec![1, 2],
+    };
+    let mut history = History::new(original.clone());
+
+    history.begin_group();
+    history.apply(&mut ExampleListOpt::default().b(|b| b.push(3).remove(0)));
+    history.apply(&mut ExampleListOpt::default().a(1).b(|b| b.set(0, 4)));
+    history.apply(&mut ExampleListOpt::default().a(2).b(|b| b.insert(0, 5)));
+    history.end_group();
+    assert_eq!(history.get().b, [5, 4, 3]);
+
+    assert!(history.undo());
+    assert_eq!(history.get(), &original);
+    assert!(history.redo());
+    assert_eq!(
+        history.get(),
+        &ExampleList {
+            a: 2,
+            b: vec![5, 4, 3]
+        }
+    );
+
+    // switching variants within a group
+    let original = ExampleEnum::Named { a: A, b: B, c: C };
+    let mut history = History::new(original);
+
+    history.begin_group();
+    history.apply(&mut ExampleEnumOpt::Unnamed(Some(1), 2.0));
+    history.apply(&mut ExampleEnumOpt::Unnamed(Some(3), 2.0));
+    history.end_group();
+
+    assert!(history.undo());
+    assert_eq!(history.get(), &original);
+    assert!(!history.can_undo());
+}
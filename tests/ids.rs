@@ -0,0 +1,434 @@
+#![cfg(feature = "bytemuck")]
+
+mod common;
+use common::named::*;
+use wopt::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq), id = 42)]
+struct ExampleId {
+    a: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq), id = 300, id_width = "u16")]
+struct ExampleIdWide {
+    a: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq), id = 200, id_width = "varint")]
+struct ExampleIdVarint {
+    a: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq), id_width = "varint")]
+struct HashedVarint {
+    a: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq), id_width = "varint")]
+struct ExampleIdNested {
+    a: u8,
+    #[wopt(optional, serde)]
+    b: ExampleIdWide,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq))]
+struct Hashed {
+    a: u8,
+    b: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq))]
+struct HashedOther {
+    a: u8,
+    b: f32,
+}
+
+// the same type but for how its fields are serialized
+mod layout {
+    use wopt::*;
+
+    pub mod required {
+        use super::*;
+
+        #[derive(WithOpt)]
+        pub struct Hashed {
+            pub a: u8,
+            #[wopt(required)]
+            pub b: f32,
+        }
+    }
+
+    pub mod len {
+        use super::*;
+
+        #[derive(WithOpt)]
+        #[wopt(len = "u32")]
+        pub struct Hashed {
+            pub a: u8,
+            pub b: f32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq), id = 301, id_width = "u16")]
+enum ExampleIdEnum {
+    Idle,
+    Value { a: u8, b: i32 },
+}
+
+// a protocol of many types, none with an explicit identity
+mod protocol {
+    use wopt::*;
+
+    macro_rules! protocol {
+        ($($name:ident),* $(,)?) => {
+            $(
+                #[derive(Clone, Copy, Debug, PartialEq, WithOpt)]
+                #[wopt(derive(Debug, Default, PartialEq))]
+                pub struct $name {
+                    pub a: u8,
+                    pub b: u32,
+                }
+            )*
+
+            // fails to compile if any two identities are equal
+            registry! {
+                #[derive(Debug, PartialEq)]
+                pub enum Message {
+                    $($name),*
+                }
+            }
+
+            pub fn messages() -> Vec<Message> {
+                vec![$(
+                    Message::$name($name { a: 1, b: 2 }),
+                    Message::from(<$name as WithOpt>::Opt::default().b(3)),
+                )*]
+            }
+        };
+    }
+
+    protocol!(
+        PlayerJoin,
+        PlayerLeave,
+        PlayerUpdate,
+        PlayerRequest,
+        PlayerResponse,
+        PlayerCreate,
+        PlayerDelete,
+        PlayerSync,
+        PlayerInvite,
+        PlayerNotify,
+        ChatJoin,
+        ChatLeave,
+        ChatUpdate,
+        ChatRequest,
+        ChatResponse,
+        ChatCreate,
+        ChatDelete,
+        ChatSync,
+        ChatInvite,
+        ChatNotify,
+        LobbyJoin,
+        LobbyLeave,
+        LobbyUpdate,
+        LobbyRequest,
+        LobbyResponse,
+        LobbyCreate,
+        LobbyDelete,
+        LobbySync,
+        LobbyInvite,
+        LobbyNotify,
+        MatchJoin,
+        MatchLeave,
+        MatchUpdate,
+        MatchRequest,
+        MatchResponse,
+        MatchCreate,
+        MatchDelete,
+        MatchSync,
+        MatchInvite,
+        MatchNotify,
+        InventoryJoin,
+        InventoryLeave,
+        InventoryUpdate,
+        InventoryRequest,
+        InventoryResponse,
+        InventoryCreate,
+        InventoryDelete,
+        InventorySync,
+        InventoryInvite,
+        InventoryNotify,
+        ItemJoin,
+        ItemLeave,
+        ItemUpdate,
+        ItemRequest,
+        ItemResponse,
+        ItemCreate,
+        ItemDelete,
+        ItemSync,
+        ItemInvite,
+        ItemNotify,
+        QuestJoin,
+        QuestLeave,
+        QuestUpdate,
+        QuestRequest,
+        QuestResponse,
+        QuestCreate,
+        QuestDelete,
+        QuestSync,
+        QuestInvite,
+        QuestNotify,
+        GuildJoin,
+        GuildLeave,
+        GuildUpdate,
+        GuildRequest,
+        GuildResponse,
+        GuildCreate,
+        GuildDelete,
+        GuildSync,
+        GuildInvite,
+        GuildNotify,
+        PartyJoin,
+        PartyLeave,
+        PartyUpdate,
+        PartyRequest,
+        PartyResponse,
+        PartyCreate,
+        PartyDelete,
+        PartySync,
+        PartyInvite,
+        PartyNotify,
+        TradeJoin,
+        TradeLeave,
+        TradeUpdate,
+        TradeRequest,
+        TradeResponse,
+        TradeCreate,
+        TradeDelete,
+        TradeSync,
+        TradeInvite,
+        TradeNotify,
+        MapJoin,
+        MapLeave,
+        MapUpdate,
+        MapRequest,
+        MapResponse,
+        MapCreate,
+        MapDelete,
+        MapSync,
+        MapInvite,
+        MapNotify,
+        EntityJoin,
+        EntityLeave,
+        EntityUpdate,
+        EntityRequest,
+        EntityResponse,
+        EntityCreate,
+        EntityDelete,
+        EntitySync,
+        EntityInvite,
+        EntityNotify,
+        SessionJoin,
+        SessionLeave,
+        SessionUpdate,
+        SessionRequest,
+        SessionResponse,
+        SessionCreate,
+        SessionDelete,
+        SessionSync,
+        SessionInvite,
+        SessionNotify,
+        FriendJoin,
+        FriendLeave,
+        FriendUpdate,
+        FriendRequest,
+        FriendResponse,
+        FriendCreate,
+        FriendDelete,
+        FriendSync,
+        FriendInvite,
+        FriendNotify,
+        AssetJoin,
+        AssetLeave,
+        AssetUpdate,
+        AssetRequest,
+        AssetResponse,
+        AssetCreate,
+        AssetDelete,
+        AssetSync,
+        AssetInvite,
+        AssetNotify,
+    );
+}
+
+registry! {
+    #[derive(Debug, PartialEq)]
+    enum Varints {
+        ExampleIdVarint,
+        HashedVarint,
+    }
+}
+
+// fails to compile if any two are equal
+check_ids!(
+    ExampleNamed,
+    ExampleNamedOpt,
+    ExampleNamedReq,
+    ExampleNamedReqOpt,
+    ExampleId,
+    ExampleIdOpt,
+    ExampleIdWide,
+    ExampleIdWideOpt,
+);
+
+#[test]
+fn test_ids_hashed() {
+    // hashed identities are varints, the optional type setting the low bit
+    assert_eq!(<Hashed as WireEncode>::ID_WIDTH, IdWidth::Varint);
+    assert_eq!(Hashed::ID & 1, 0);
+    assert_eq!(HashedOpt::ID, Hashed::ID | 1);
+
+    // hashed from the crate, the name and the schema only (not the file or module), so that
+    // moving the type within the crate keeps its identity
+    assert_eq!(Hashed::ID, 2_268_836_426);
+
+    // hashed from the name, not only the schema
+    assert_ne!(Hashed::ID, HashedOther::ID);
+
+    // and from how the fields are serialized
+    assert_ne!(Hashed::ID, layout::required::Hashed::ID);
+    assert_ne!(Hashed::ID, layout::len::Hashed::ID);
+}
+
+#[test]
+fn test_ids_hashed_header() {
+    // unlike the single byte of explicit identities, hashed ones are `u32` varints of 4 or 5 bytes
+    let id: u32 = Hashed::ID;
+    assert_eq!(IdWidth::Varint.len(id), 5);
+    let bytes = Hashed { a: A, b: B }.serialize();
+    assert_eq!(bytes.len(), 5 + Hashed::UNPADDED_SIZE);
+    assert_eq!(bytes[..5], [0xca, 0xe4, 0xee, 0xb9, 0x08]);
+
+    for message in protocol::messages() {
+        assert!((4..=5).contains(&IdWidth::Varint.len(message.id())));
+    }
+}
+
+#[test]
+fn test_ids_hashed_protocol() {
+    let messages = protocol::messages();
+    assert_eq!(messages.len(), 300);
+    for message in messages {
+        let bytes = message.encode();
+        let id = message.id();
+        assert_eq!(
+            IdWidth::Varint.decode(&bytes),
+            Ok((id, IdWidth::Varint.len(id)))
+        );
+        assert_eq!(protocol::Message::decode(&bytes), Ok(message));
+    }
+}
+
+#[test]
+fn test_ids_explicit() {
+    assert_eq!(ExampleId::ID, 42);
+    assert_eq!(ExampleIdOpt::ID, 42 | 0x80);
+    assert_eq!(ExampleId { a: A }.serialize()[0], 42);
+}
+
+#[test]
+fn test_ids_local() {
+    // types derived in functions are checked crate-wide too
+    #[derive(Clone, Copy, Debug, Default, PartialEq, WithOpt)]
+    #[wopt(derive(Debug, Default, PartialEq), id = 43)]
+    struct ExampleIdLocal {
+        a: u8,
+    }
+    assert_eq!(ExampleIdLocal::ID, 43);
+    check_ids!(ExampleId, ExampleIdLocal);
+}
+
+#[test]
+fn test_ids_wide() {
+    assert_eq!(ExampleIdWide::ID, 300u16);
+    assert_eq!(ExampleIdWideOpt::ID, 300 | 0x8000);
+    assert_eq!(<ExampleIdWide as WireEncode>::ID_WIDTH, IdWidth::U16);
+
+    let ex = ExampleIdWide { a: A };
+    let bytes = ex.serialize();
+    assert_eq!(bytes, [44, 1, A]);
+    assert_eq!(ExampleIdWide::try_deserialize(&bytes), Ok(ex));
+    assert_eq!(ExampleIdWide::deserialize(&bytes[2..]), ex);
+
+    let ex_opt = ExampleIdWideOpt::default().a(A);
+    let bytes = ex_opt.serialize();
+    assert_eq!(bytes, [44, 129, 1, A]);
+    assert_eq!(ExampleIdWideOpt::try_deserialize(&bytes), Ok(ex_opt));
+}
+
+#[test]
+fn test_ids_varint() {
+    // the optional type sets the low bit instead
+    assert_eq!(ExampleIdVarint::ID, 400u32);
+    assert_eq!(ExampleIdVarintOpt::ID, 401);
+    assert_eq!(HashedVarint::ID & 1, 0);
+    assert!(IdWidth::Varint.len(HashedVarintOpt::ID) <= 5);
+
+    let ex = ExampleIdVarint { a: A };
+    let bytes = ex.serialize();
+    assert_eq!(bytes, [0x90, 0x03, A]);
+    assert_eq!(ExampleIdVarint::try_deserialize(&bytes), Ok(ex));
+    assert_eq!(Varints::decode(&bytes), Ok(Varints::ExampleIdVarint(ex)));
+
+    let ex_opt = HashedVarintOpt::default().a(A);
+    let message = Varints::from(ex_opt);
+    assert_eq!(message.id(), HashedVarintOpt::ID);
+    assert_eq!(Varints::decode(&message.encode()), Ok(message));
+
+    // varints past `u32` are rejected
+    let e = Varints::decode(&[0xff; 5]).unwrap_err();
+    assert_eq!(e.reason(), "varint overflows `u32`");
+}
+
+#[test]
+fn test_ids_wide_enum() {
+    let ex = ExampleIdEnum::Idle;
+    let bytes = ex.serialize();
+    assert_eq!(bytes[..2], ExampleIdEnum::ID.to_le_bytes());
+    assert_eq!(ExampleIdEnum::try_deserialize(&bytes), Ok(ex));
+
+    let ex_opt = ExampleIdEnumOpt::Value {
+        a: None,
+        b: Some(C),
+    };
+    let bytes = ex_opt.serialize();
+    assert_eq!(bytes[..2], ExampleIdEnumOpt::ID.to_le_bytes());
+    assert_eq!(ExampleIdEnumOpt::try_deserialize(&bytes), Ok(ex_opt));
+}
+
+#[test]
+fn test_ids_nested() {
+    // nested values are serialized without their identity, whatever its width
+    let ex = ExampleIdNested {
+        a: A,
+        b: ExampleIdWide { a: A },
+    };
+    let bytes = ex.serialize();
+    assert_eq!(bytes[IdWidth::Varint.len(ExampleIdNested::ID)..], [A, A]);
+    assert_eq!(ExampleIdNested::try_deserialize(&bytes), Ok(ex));
+
+    let ex_opt = ExampleIdNestedOpt::default().b(|b| b.a(A));
+    assert_eq!(
+        ExampleIdNestedOpt::try_deserialize(&ex_opt.serialize()),
+        Ok(ex_opt)
+    );
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "bytemuck")]
+
+mod common;
+use common::named::*;
+use wopt::*;
+
+fn ser(data: &Vec<u8>) -> &[u8] {
+    data.as_slice()
+}
+
+fn de(data: &[u8]) -> Vec<u8> {
+    data.to_vec()
+}
+
+#[derive(Clone, Debug, Default, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq))]
+struct ExampleLen {
+    #[wopt(ser = "ser", de = "de", len = "u8")]
+    a: Vec<u8>,
+    #[wopt(ser = "ser", de = "de", len = "u32")]
+    b: Vec<u8>,
+    #[wopt(ser = "ser", de = "de", len = "varint")]
+    c: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, WithOpt)]
+#[wopt(derive(Debug, Default, PartialEq), len = "u32")]
+struct ExampleAsset {
+    #[wopt(ser = "ser", de = "de")]
+    blob: Vec<u8>,
+    #[wopt(list_ops, len = "u8")]
+    tags: Vec<u16>,
+    #[wopt(list_ops, optional)]
+    parts: Vec<ExampleLen>,
+}
+
+#[test]
+fn test_len_prefixes() {
+    let ex = ExampleLen {
+        a: vec![1, 2],
+        b: vec![3],
+        c: vec![4; 200],
+    };
+    let bytes = ex.serialize();
+    // past the (hashed) identity
+    let fields = &bytes[IdWidth::Varint.len(ExampleLen::ID)..];
+    assert_eq!(fields[..8], [2, 1, 2, 1, 0, 0, 0, 3]);
+    assert_eq!(fields[8..10], [0xc8, 0x01]);
+    assert_eq!(fields.len(), 10 + 200);
+    assert_eq!(ExampleLen::try_deserialize(&bytes), Ok(ex.clone()));
+
+    let ex_opt = ExampleLenOpt::default().c(vec![4; 200]);
+    let bytes = ex_opt.serialize();
+    let n = IdWidth::Varint.len(ExampleLenOpt::ID);
+    assert_eq!(bytes[n + 1..n + 3], [0xc8, 0x01]);
+    assert_eq!(ExampleLenOpt::try_deserialize(&bytes), Ok(ex_opt));
+}
+
+#[test]
+fn test_len_large() {
+    // beyond the default `u16` prefix
+    let blob = (0..100_000).map(|i| i as u8).collect::<Vec<_>>();
+    let ex = ExampleAsset {
+        blob: blob.clone(),
+        tags: vec![1, 2],
+        parts: Vec::new(),
+    };
+    let bytes = ex.try_serialize().unwrap();
+    let n = IdWidth::Varint.len(ExampleAsset::ID);
+    assert_eq!(bytes[n..n + 4], 100_000u32.to_le_bytes());
+    assert_eq!(ExampleAsset::try_deserialize(&bytes), Ok(ex.clone()));
+
+    let ex_opt = ExampleAssetOpt::default().blob(blob.clone());
+    let bytes = ex_opt.try_serialize().unwrap();
+    assert_eq!(ExampleAssetOpt::try_deserialize(&bytes), Ok(ex_opt));
+
+    let ex = ExampleNamedVec {
+        a: A,
+        b: blob,
+        c: C,
+    };
+    let e = ex.try_serialize().unwrap_err();
+    assert_eq!(e.field(), Some("b"));
+    assert_eq!(
+        e.to_string(),
+        "unencodable field `b`: 100000 overflows `u16`"
+    );
+}
+
+#[test]
+fn test_len_overflow() {
+    let ex = ExampleLen {
+        a: vec![0; 256],
+        ..Default::default()
+    };
+    let e = ex.try_serialize().unwrap_err();
+    assert_eq!((e.field(), e.reason()), (Some("a"), "256 overflows `u8`"));
+
+    let ex_opt = ExampleLenOpt::default().a(vec![0; 256]);
+    assert_eq!(ex_opt.try_serialize(), Err(e));
+
+    // indices and numbers of operations are prefixed alike
+    let ex_opt = ExampleAssetOpt::default().tags(|tags| tags.set(300, 1));
+    let e = ex_opt.try_serialize().unwrap_err();
+    assert_eq!(
+        (e.field(), e.reason()),
+        (Some("tags"), "300 overflows `u8`")
+    );
+
+    // nested fields are joined by a dot
+    let part = ExampleLenOpt::default().a(vec![0; 256]);
+    let ex_opt = ExampleAssetOpt::default().parts(|parts| parts.patch_at(0, part));
+    let e = ex_opt.try_serialize().unwrap_err();
+    assert_eq!(e.field(), Some("parts.a"));
+}
+
+#[test]
+#[should_panic(expected = "unencodable field `a`: 256 overflows `u8`")]
+fn test_len_overflow_panics() {
+    ExampleLen {
+        a: vec![0; 256],
+        ..Default::default()
+    }
+    .serialize();
+}
+
+#[test]
+fn test_len_truncated() {
+    let ex = ExampleLen {
+        c: vec![4; 200],
+        ..Default::default()
+    };
+    let bytes = ex.serialize();
+    let e = ExampleLen::try_deserialize(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(e.offset(), IdWidth::Varint.len(ExampleLen::ID) + 5);
+    assert_eq!(
+        e.to_string(),
+        format!(
+            "invalid field `c` at offset {}: length prefix 200 exceeds the remaining 199 bytes",
+            e.offset()
+        )
+    );
+
+    // the number of operations isn't trusted for allocations
+    let mut bytes = ExampleAssetOpt::default()
+        .tags(|tags| tags.pop())
+        .serialize();
+    let end = bytes.len();
+    bytes[end - 2] = u8::MAX;
+    let e = ExampleAssetOpt::try_deserialize(&bytes).unwrap_err();
+    assert_eq!(e.field(), Some("tags"));
+}
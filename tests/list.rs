@@ -0,0 +1,279 @@
+mod common;
+use common::list::*;
+use wopt::*;
+
+#[test]
+fn test_list_ops() {
+    let mut ex = ExampleList {
+        a: A,
+        b: vec![1, 2, 3],
+    };
+
+    let mut ex_opt = ExampleListOpt::default().b(|b| b.push(4).set(0, 5).remove(1));
+    assert!(ex_opt.is_modified());
+    ex.patch(&mut ex_opt);
+    assert!(!ex_opt.is_modified());
+    assert_eq!(ex.b, [5, 3, 4]);
+
+    ex.patch(&mut ExampleListOpt::default().b(|b| b.insert(1, 6).pop().splice(0..2, [7, 8, 9])));
+    assert_eq!(ex.b, [7, 8, 9, 3]);
+
+    ex.patch(&mut ExampleListOpt::default().b(|b| b.replace([1])));
+    assert_eq!(ex.b, [1]);
+
+    // popping an empty list is ignored
+    ex.patch(&mut ExampleListOpt::default().b(|b| b.pop().pop()));
+    assert!(ex.b.is_empty());
+}
+
+#[test]
+fn test_list_out_of_bounds() {
+    let original = ExampleList { a: A, b: vec![1] };
+    let mut ex = original.clone();
+
+    // rejected as a whole, leaving both unchanged
+    let mut ex_opt = ExampleListOpt::default().b(|b| b.push(2).remove(2).set(0, 3));
+    let e = ex.try_patch(&mut ex_opt).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "invalid field `b`: index 2 out of bounds (len 2)"
+    );
+    assert_eq!(ex, original);
+    assert!(ex_opt.is_modified());
+
+    let e = ListPatch::new()
+        .splice(0..3, [4])
+        .try_patch(&mut ex.b)
+        .unwrap_err();
+    assert_eq!(e.reason(), "index 3 out of bounds (len 1)");
+    assert_eq!(ex, original);
+
+    // otherwise applied up to the first operation out of bounds, left in the patch
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex.b, [1, 2]);
+    assert_eq!(
+        ex_opt,
+        ExampleListOpt::default().b(|b| b.remove(2).set(0, 3))
+    );
+
+    // which applies once in bounds
+    ex.b.push(3);
+    let mut undo = ex.patch_with_undo(&mut ex_opt);
+    assert_eq!(ex.b, [3, 2]);
+    assert!(!ex_opt.is_modified());
+    ex.patch(&mut undo);
+    assert_eq!(ex.b, [1, 2, 3]);
+
+    // the undo patch only reverts the applied operations
+    let mut ex_opt = ExampleListOpt::default().b(|b| b.set(0, 4).insert(5, 5).pop());
+    let undo = ex.patch_with_undo(&mut ex_opt);
+    assert_eq!(ex.b, [4, 2, 3]);
+    assert_eq!(undo, ExampleListOpt::default().b(|b| b.set(0, 1)));
+    assert_eq!(
+        ex_opt,
+        ExampleListOpt::default().b(|b| b.insert(5, 5).pop())
+    );
+
+    let mut ops = ListPatch::new().splice(0..4, [4]);
+    ops.patch(&mut ex.b);
+    assert_eq!(ex.b, [4, 2, 3]);
+    assert_eq!(ops.ops(), [ListOp::Splice(0..4, vec![4])]);
+}
+
+#[test]
+fn test_list_undo() {
+    let original = ExampleList {
+        a: A,
+        b: vec![1, 2, 3],
+    };
+    let mut ex = original.clone();
+
+    let mut undo = ex.patch_with_undo(&mut ExampleListOpt::default().b(|b| {
+        b.push(4)
+            .set(0, 5)
+            .remove(1)
+            .insert(0, 6)
+            .splice(1..3, [7])
+            .pop()
+            .pop()
+    }));
+    assert_eq!(ex.b, [6]);
+
+    ex.patch(&mut undo);
+    assert_eq!(ex, original);
+}
+
+#[test]
+fn test_list_merge() {
+    let mut a = ExampleListOpt::default().b(|b| b.push(4));
+    a.merge(ExampleListOpt::default().b(|b| b.set(3, 5)));
+    assert_eq!(a.b.ops(), [ListOp::Push(4), ListOp::Set(3, 5)]);
+
+    let mut ex = ExampleList {
+        a: A,
+        b: vec![1, 2, 3],
+    };
+    ex.patch(&mut a);
+    assert_eq!(ex.b, [1, 2, 3, 5]);
+}
+
+#[test]
+fn test_list_diff() {
+    let diff = |old: &[i32], new: &[i32]| ListPatch::diff(old, new).ops().to_vec();
+
+    assert_eq!(diff(&[1, 2, 3], &[1, 2, 3]), []);
+    assert_eq!(diff(&[1, 2, 3], &[1, 4, 3]), [ListOp::Set(1, 4)]);
+    assert_eq!(diff(&[1, 2, 3], &[1, 2, 3, 4]), [ListOp::Push(4)]);
+    assert_eq!(diff(&[1, 2, 3], &[1, 4, 2, 3]), [ListOp::Insert(1, 4)]);
+    assert_eq!(diff(&[1, 2, 3], &[1, 2]), [ListOp::Pop]);
+    assert_eq!(diff(&[1, 2, 3], &[1, 3]), [ListOp::Remove(1)]);
+    assert_eq!(diff(&[1, 2, 3], &[1]), [ListOp::Splice(1..3, vec![])]);
+    assert_eq!(
+        diff(&[1, 2, 3, 4], &[1, 5, 6, 7, 8, 4]),
+        [
+            ListOp::Set(1, 5),
+            ListOp::Set(2, 6),
+            ListOp::Splice(3..3, vec![7, 8])
+        ]
+    );
+
+    let a = ExampleList {
+        a: A,
+        b: vec![1, 2, 3],
+    };
+    let b = ExampleList {
+        a: A,
+        b: vec![1, 2, 4, 5],
+    };
+    let mut ex = a.clone();
+    ex.patch(&mut a.diff(&b));
+    assert_eq!(ex, b);
+}
+
+#[test]
+fn test_list_full() {
+    let ex = ExampleList {
+        a: A,
+        b: vec![1, 2, 3],
+    };
+    assert_eq!(ex.clone().into_opt().try_into_full(), Ok(ex));
+
+    let ex_opt = ExampleListOpt {
+        a: Some(A),
+        b: ListPatch::new().push(1),
+    };
+    assert_eq!(ex_opt.try_into_full().unwrap_err().fields(), ["b"]);
+
+    // the operations following a replacement are applied to it
+    let ex_opt = ExampleListOpt {
+        a: Some(A),
+        b: ListPatch::new().push(1).replace([2]).push(3),
+    };
+    assert_eq!(ex_opt.try_into_full().unwrap().b, [2, 3]);
+}
+
+#[test]
+fn test_list_nested() {
+    let original = ExampleListNested {
+        a: A,
+        b: vec![ExampleNamed { a: A, b: B, c: C }; 2],
+    };
+    let mut ex = original.clone();
+
+    let mut ex_opt = ExampleListNestedOpt::default().b(|b| {
+        b.patch_at(1, ExampleNamedOpt::default().c(1))
+            .push(ExampleNamed::default())
+    });
+    let mut undo = ex.patch_with_undo(&mut ex_opt);
+    assert_eq!(ex.b[0], original.b[0]);
+    assert_eq!(ex.b[1], ExampleNamed { a: A, b: B, c: 1 });
+    assert_eq!(ex.b[2], ExampleNamed::default());
+
+    // only the changed fields of the changed elements differ
+    let diff = original.diff(&ex);
+    assert_eq!(
+        diff.b.ops(),
+        [
+            ListOp::Patch(1, ExampleNamedOpt::default().c(1)),
+            ListOp::Push(ExampleNamed::default()),
+        ]
+    );
+
+    ex.patch(&mut undo);
+    assert_eq!(ex, original);
+}
+
+#[cfg(feature = "bytemuck")]
+mod wire {
+    use super::*;
+
+    #[test]
+    fn test_list_serialize() {
+        let ex = ExampleList {
+            a: A,
+            b: vec![1, -1],
+        };
+        let serialized = ex.serialize();
+        assert_eq!(serialized[1..], [A, 2, 0, 1, 0, 0, 0, 255, 255, 255, 255]);
+        assert_eq!(ExampleList::deserialize(&serialized[1..]), ex);
+    }
+
+    #[test]
+    fn test_list_serialize_opt() {
+        let ex_opt = ExampleListOpt::default().b(|b| b.set(300, 1).pop());
+        let serialized = ex_opt.serialize();
+        assert_eq!(
+            serialized[1..],
+            [
+                ExampleListFields::B.bits(),
+                2,
+                0, // number of operations
+                5,
+                44,
+                1,
+                1,
+                0,
+                0,
+                0, // set
+                1, // pop
+            ]
+        );
+        assert_eq!(ExampleListOpt::deserialize(&serialized[1..]), ex_opt);
+
+        let ex_opt = ExampleListOpt::default().b(|b| {
+            b.push(1)
+                .insert(0, 2)
+                .remove(1)
+                .splice(0..1, [3, 4])
+                .replace([5])
+        });
+        let serialized = ex_opt.serialize();
+        assert_eq!(ExampleListOpt::deserialize(&serialized[1..]), ex_opt);
+    }
+
+    #[test]
+    fn test_list_untrusted() {
+        let mut ex = ExampleList { a: A, b: vec![1] };
+        let bytes = ExampleListOpt::default().b(|b| b.remove(10)).serialize();
+        let mut ex_opt = ExampleListOpt::try_deserialize(&bytes).unwrap();
+        let e = ex.try_patch(&mut ex_opt).unwrap_err();
+        assert_eq!(e.reason(), "index 10 out of bounds (len 1)");
+        assert_eq!(ex.b, [1]);
+    }
+
+    #[test]
+    fn test_list_serialize_nested() {
+        let ex = ExampleListNested {
+            a: A,
+            b: vec![ExampleNamed { a: A, b: B, c: C }],
+        };
+        assert_eq!(ExampleListNested::deserialize(&ex.serialize()[1..]), ex);
+
+        let ex_opt = ExampleListNestedOpt::default().b(|b| {
+            b.patch_at(0, ExampleNamedOpt::default().c(1))
+                .push(ExampleNamed::default())
+        });
+        let serialized = ex_opt.serialize();
+        assert_eq!(ExampleListNestedOpt::deserialize(&serialized[1..]), ex_opt);
+    }
+}
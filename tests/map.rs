@@ -0,0 +1,259 @@
+mod common;
+use common::map::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use wopt::*;
+
+fn example() -> ExampleMap {
+    ExampleMap {
+        a: A,
+        b: HashMap::from([(1, 10), (2, 20)]),
+        c: BTreeSet::from([1, 2]),
+    }
+}
+
+#[test]
+fn test_map_ops() {
+    let mut ex = example();
+
+    let mut ex_opt = ExampleMapOpt::default()
+        .b(|b| b.insert(1, 11).insert(3, 30).remove(2))
+        .c(|c| c.insert(3).remove(1));
+    assert!(ex_opt.is_modified());
+    ex.patch(&mut ex_opt);
+    assert!(!ex_opt.is_modified());
+    assert_eq!(ex.b, HashMap::from([(1, 11), (3, 30)]));
+    assert_eq!(ex.c, BTreeSet::from([2, 3]));
+
+    ex.patch(
+        &mut ExampleMapOpt::default()
+            .b(|b| b.clear().insert(4, 40))
+            .c(|c| c.clear()),
+    );
+    assert_eq!(ex.b, HashMap::from([(4, 40)]));
+    assert!(ex.c.is_empty());
+}
+
+#[test]
+fn test_map_undo() {
+    let original = example();
+    let mut ex = original.clone();
+
+    let mut undo = ex.patch_with_undo(
+        &mut ExampleMapOpt::default()
+            .b(|b| b.insert(1, 11).insert(3, 30).remove(2).remove(5))
+            .c(|c| c.insert(1).insert(3).clear().insert(4)),
+    );
+    assert_eq!(ex.b, HashMap::from([(1, 11), (3, 30)]));
+    assert_eq!(ex.c, BTreeSet::from([4]));
+
+    ex.patch(&mut undo);
+    assert_eq!(ex, original);
+
+    let mut undo = ex.patch_with_undo(&mut ExampleMapOpt::default().b(|b| b.clear()));
+    assert!(ex.b.is_empty());
+    ex.patch(&mut undo);
+    assert_eq!(ex, original);
+}
+
+#[test]
+fn test_map_merge() {
+    let mut a = ExampleMapOpt::default().b(|b| b.insert(1, 11));
+    a.merge(
+        ExampleMapOpt::default()
+            .b(|b| b.remove(1))
+            .c(|c| c.insert(3)),
+    );
+    assert_eq!(a.b.ops(), [MapOp::Insert(1, 11), MapOp::Remove(1)]);
+    assert_eq!(a.c.ops(), [SetOp::Insert(3)]);
+
+    let mut ex = example();
+    ex.patch(&mut a);
+    assert_eq!(ex.b, HashMap::from([(2, 20)]));
+}
+
+#[test]
+fn test_map_diff() {
+    let a = example();
+    let b = ExampleMap {
+        a: A,
+        b: HashMap::from([(1, 10), (2, 21), (3, 30)]),
+        c: BTreeSet::from([2, 3]),
+    };
+
+    let mut diff = a.diff(&b);
+    assert_eq!(diff.a, None);
+    let mut ops = diff.b.ops().to_vec();
+    ops.sort_by_key(|op| match op {
+        MapOp::Insert(key, _) | MapOp::Remove(key) | MapOp::Patch(key, _) => *key,
+        MapOp::Clear => 0,
+    });
+    assert_eq!(ops, [MapOp::Insert(2, 21), MapOp::Insert(3, 30)]);
+    assert_eq!(diff.c.ops(), [SetOp::Insert(3), SetOp::Remove(1)]);
+
+    let mut ex = a.clone();
+    ex.patch(&mut diff);
+    assert_eq!(ex, b);
+
+    assert!(!a.diff(&a).is_modified());
+}
+
+#[test]
+fn test_map_full() {
+    let ex = example();
+    assert_eq!(ex.clone().into_opt().try_into_full(), Ok(ex));
+
+    let ex_opt = ExampleMapOpt {
+        a: Some(A),
+        b: MapPatch::new().clear(),
+        c: SetPatch::new().insert(1),
+    };
+    assert_eq!(ex_opt.try_into_full().unwrap_err().fields(), ["c"]);
+}
+
+#[test]
+fn test_map_nested() {
+    let original = ExampleMapNested {
+        a: A,
+        b: BTreeMap::from([(1, ExampleNamed { a: A, b: B, c: C })]),
+    };
+    let mut ex = original.clone();
+
+    let mut undo = ex.patch_with_undo(&mut ExampleMapNestedOpt::default().b(|b| {
+        b.patch_at(1, ExampleNamedOpt::default().c(1))
+            .patch_at(2, ExampleNamedOpt::default().c(2))
+            .insert(3, ExampleNamed::default())
+    }));
+
+    // missing keys aren't patched
+    assert_eq!(
+        ex.b,
+        BTreeMap::from([
+            (1, ExampleNamed { a: A, b: B, c: 1 }),
+            (3, ExampleNamed::default()),
+        ])
+    );
+
+    // only the changed fields of the changed values differ
+    assert_eq!(
+        original.diff(&ex).b.ops(),
+        [
+            MapOp::Patch(1, ExampleNamedOpt::default().c(1)),
+            MapOp::Insert(3, ExampleNamed::default()),
+        ]
+    );
+
+    ex.patch(&mut undo);
+    assert_eq!(ex, original);
+}
+
+#[test]
+fn test_map_missing_key() {
+    let original = ExampleMapNested {
+        a: A,
+        b: BTreeMap::from([(1, ExampleNamed { a: A, b: B, c: C })]),
+    };
+    let mut ex = original.clone();
+
+    // rejected as a whole, leaving both unchanged
+    let mut ex_opt = ExampleMapNestedOpt::default().b(|b| {
+        b.patch_at(1, ExampleNamedOpt::default().c(1))
+            .patch_at(2, ExampleNamedOpt::default().c(2))
+    });
+    let e = ex.try_patch(&mut ex_opt).unwrap_err();
+    assert_eq!(e.to_string(), "invalid field `b`: patched key not found");
+    assert_eq!(ex, original);
+    assert!(ex_opt.is_modified());
+
+    // keys are present as modified by the preceding operations
+    let patch = |c| ExampleNamedOpt::default().c(c);
+    let ops = || {
+        MapPatch::new()
+            .insert(2, ExampleNamed::default())
+            .patch_at(2, patch(2))
+            .remove(1)
+    };
+    assert!(ops().check(&ex.b).is_ok());
+    assert!(ops().patch_at(1, patch(1)).check(&ex.b).is_err());
+    assert!(ops().clear().patch_at(2, patch(2)).check(&ex.b).is_err());
+
+    let mut ex_opt = ExampleMapNestedOpt::default().b(|b| {
+        b.insert(2, ExampleNamed::default())
+            .patch_at(2, ExampleNamedOpt::default().c(2))
+    });
+    ex.try_patch(&mut ex_opt).unwrap();
+    assert_eq!(
+        ex.b[&2],
+        ExampleNamed {
+            c: 2,
+            ..Default::default()
+        }
+    );
+}
+
+#[cfg(feature = "bytemuck")]
+mod wire {
+    use super::*;
+
+    #[test]
+    fn test_map_serialize() {
+        let ex = ExampleMap {
+            a: A,
+            b: HashMap::from([(1, -1)]),
+            c: BTreeSet::from([1, 256]),
+        };
+        let serialized = ex.serialize();
+        assert_eq!(
+            serialized[1..],
+            [A, 1, 0, 1, 255, 255, 255, 255, 2, 0, 1, 0, 0, 1]
+        );
+        assert_eq!(ExampleMap::deserialize(&serialized[1..]), ex);
+    }
+
+    #[test]
+    fn test_map_serialize_opt() {
+        let ex_opt = ExampleMapOpt::default()
+            .b(|b| b.insert(1, 2).remove(3))
+            .c(|c| c.clear().insert(4));
+        let serialized = ex_opt.serialize();
+        assert_eq!(
+            serialized[1..],
+            [
+                (ExampleMapFields::B | ExampleMapFields::C).bits(),
+                2,
+                0, // number of operations
+                0,
+                1,
+                2,
+                0,
+                0,
+                0, // insert
+                1,
+                3, // remove
+                2,
+                0, // number of operations
+                2, // clear
+                0,
+                4,
+                0, // insert
+            ]
+        );
+        assert_eq!(ExampleMapOpt::deserialize(&serialized[1..]), ex_opt);
+    }
+
+    #[test]
+    fn test_map_serialize_nested() {
+        let ex = ExampleMapNested {
+            a: A,
+            b: BTreeMap::from([(1, ExampleNamed { a: A, b: B, c: C })]),
+        };
+        assert_eq!(ExampleMapNested::deserialize(&ex.serialize()[1..]), ex);
+
+        let ex_opt = ExampleMapNestedOpt::default().b(|b| {
+            b.patch_at(1, ExampleNamedOpt::default().c(1))
+                .insert(2, ExampleNamed::default())
+                .clear()
+        });
+        let serialized = ex_opt.serialize();
+        assert_eq!(ExampleMapNestedOpt::deserialize(&serialized[1..]), ex_opt);
+    }
+}
@@ -0,0 +1,104 @@
+mod common;
+use common::{enums::*, flat::*, named::*, unnamed::*};
+use wopt::*;
+
+#[test]
+fn test_merge_named() {
+    let p1 = || ExampleNamedOpt {
+        a: Some(1),
+        b: Some(2.0),
+        c: None,
+    };
+    let p2 = || ExampleNamedOpt {
+        a: None,
+        b: Some(3.0),
+        c: Some(4),
+    };
+
+    let mut ex = ExampleNamed { a: A, b: B, c: C };
+    let mut sequential = ex;
+    sequential.patch(&mut p1());
+    sequential.patch(&mut p2());
+
+    let mut merged = p1() | p2();
+    assert_eq!(
+        merged,
+        ExampleNamedOpt {
+            a: Some(1),
+            b: Some(3.0),
+            c: Some(4),
+        }
+    );
+
+    ex.patch(&mut merged);
+    assert_eq!(ex, sequential);
+}
+
+#[test]
+fn test_merge_unnamed() {
+    let mut p = ExampleUnnamedReqOpt(Some(1), 2.0, None);
+    p |= ExampleUnnamedReqOpt(None, 3.0, Some(4));
+    assert_eq!(p, ExampleUnnamedReqOpt(Some(1), 3.0, Some(4)));
+}
+
+#[test]
+fn test_merge_nested() {
+    let mut p = ExampleNestedOpt {
+        a: Some(1),
+        b: ExampleNamedOpt {
+            a: Some(2),
+            ..Default::default()
+        },
+    };
+    Patch::merge(
+        &mut p,
+        ExampleNestedOpt {
+            a: None,
+            b: ExampleNamedOpt {
+                c: Some(3),
+                ..Default::default()
+            },
+        },
+    );
+    assert_eq!(
+        p,
+        ExampleNestedOpt {
+            a: Some(1),
+            b: ExampleNamedOpt {
+                a: Some(2),
+                b: None,
+                c: Some(3),
+            },
+        }
+    );
+
+    let mut ex = ExampleNested::default();
+    ex.patch(&mut p);
+    assert_eq!(ex.b, ExampleNamed { a: 2, b: 0.0, c: 3 });
+}
+
+#[test]
+fn test_merge_enum() {
+    let named = |a, b, c| ExampleEnumOpt::Named { a, b, c };
+
+    // same variant
+    let p = || named(Some(1), None, None) | named(None, None, Some(2));
+    assert_eq!(p(), named(Some(1), None, Some(2)));
+
+    // unmodified patches are replaced by, or ignore, the other
+    assert_eq!(ExampleEnumOpt::Unmodified | p(), p());
+    assert_eq!(p() | ExampleEnumOpt::Unmodified, p());
+
+    // another variant overrides when complete
+    let p = named(Some(1), None, None) | ExampleEnumOpt::Unnamed(Some(2), 3.0);
+    assert_eq!(p, ExampleEnumOpt::Unnamed(Some(2), 3.0));
+
+    let mut ex = ExampleEnum::Idle;
+    let mut p = ExampleEnumOpt::Idle | named(Some(1), Some(2.0), Some(3));
+    ex.patch(&mut p);
+    assert_eq!(ex, ExampleEnum::Named { a: 1, b: 2.0, c: 3 });
+
+    // and is ignored otherwise
+    let p = ExampleEnumOpt::Idle | ExampleEnumOpt::Unnamed(None, 3.0);
+    assert_eq!(p, ExampleEnumOpt::Idle);
+}
@@ -310,6 +310,7 @@ fn test_named_bytemuck_deserialize_vec() {
 
 #[test]
 #[cfg(feature = "bytemuck")]
+#[allow(deprecated)] // through the former name of `ExampleNamedVecFields`
 fn test_named_bytemuck_serialize_vec_opt() {
     let ex = ExampleNamedVecOpt {
         a: Some(A),
@@ -0,0 +1,211 @@
+mod common;
+use common::nested::*;
+use std::sync::Arc;
+use wopt::*;
+
+const NAMED: ExampleNamed = ExampleNamed { a: A, b: B, c: C };
+
+#[test]
+fn test_nested_wrappers() {
+    let mut ex = ExampleNested::new(ExampleNamed::default());
+
+    let mut ex_opt = ExampleNestedOpt::default()
+        .boxed(|boxed| boxed.a(A))
+        .local(|local| local.b(B))
+        .array(|[first, second]| [first, second.c(C)])
+        .tuple(|(first, second)| (first.a(A), second));
+    ex.patch(&mut ex_opt);
+    assert!(!ex_opt.is_modified());
+    assert_eq!(ex.boxed.a, A);
+    assert_eq!(ex.local.b, B);
+    assert_eq!(ex.array[0], ExampleNamed::default());
+    assert_eq!(ex.array[1].c, C);
+    assert_eq!(ex.tuple.0.a, A);
+    assert_eq!(
+        ExampleNestedOpt::default()
+            .array(|[first, _]| [first, ExampleNamedOpt::default().a(A)])
+            .modified_fields(),
+        ExampleNestedFields::Array
+    );
+}
+
+#[test]
+fn test_nested_shared() {
+    let mut ex = ExampleNested::new(NAMED);
+    let shared = Arc::clone(&ex.shared);
+
+    // unpatched values stay shared
+    ex.patch(&mut ExampleNestedOpt::default().boxed(|boxed| boxed.a(0)));
+    assert!(Arc::ptr_eq(&ex.shared, &shared));
+
+    // patched values are cloned first (i.e., copy-on-write)
+    ex.patch(&mut ExampleNestedOpt::default().shared(|shared| shared.a(0)));
+    assert!(!Arc::ptr_eq(&ex.shared, &shared));
+    assert_eq!(ex.shared.a, 0);
+    assert_eq!(shared.a, A);
+}
+
+#[test]
+fn test_nested_option() {
+    let mut ex = ExampleNested::new(NAMED);
+
+    let mut ex_opt =
+        ExampleNestedOpt::default().maybe(|_| OptionPatch::Patch(ExampleNamedOpt::default().a(0)));
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex.maybe, Some(ExampleNamed { a: 0, ..NAMED }));
+
+    ex.patch(&mut ExampleNestedOpt::default().maybe(|_| OptionPatch::Clear));
+    assert_eq!(ex.maybe, None);
+
+    // incomplete patches can't set a value, and are left in the patch
+    let mut ex_opt =
+        ExampleNestedOpt::default().maybe(|_| OptionPatch::Patch(ExampleNamedOpt::default().a(0)));
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex.maybe, None);
+    assert_eq!(
+        ex_opt.maybe,
+        OptionPatch::Patch(ExampleNamedOpt::default().a(0))
+    );
+
+    let mut ex_opt = ExampleNestedOpt::default().maybe(|_| OptionPatch::Set(NAMED.into_opt()));
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex.maybe, Some(NAMED));
+
+    // patching a cleared value sets it
+    let mut ex_opt = ExampleNestedOpt::default().maybe(|_| OptionPatch::Clear);
+    ex_opt.merge(ExampleNestedOpt::default().maybe(|_| OptionPatch::Patch(NAMED.into_opt())));
+    assert_eq!(ex_opt.maybe, OptionPatch::Set(NAMED.into_opt()));
+}
+
+#[test]
+fn test_nested_option_incomplete() {
+    let mut ex = ExampleNested::new(NAMED);
+
+    // nor replace one
+    let mut ex_opt =
+        ExampleNestedOpt::default().maybe(|_| OptionPatch::Set(ExampleNamedOpt::default().a(0)));
+    let undo = ex.patch_with_undo(&mut ex_opt);
+    assert_eq!(ex.maybe, Some(NAMED));
+    assert_eq!(
+        ex_opt.maybe,
+        OptionPatch::Set(ExampleNamedOpt::default().a(0))
+    );
+    assert_eq!(undo.maybe, OptionPatch::Unchanged);
+
+    // through wrappers too
+    let second = || OptionPatch::Set(ExampleNamedOpt::default().b(B));
+    let mut ex_opt = ExampleNestedOpt::default().tuple(|(first, _)| (first, second()));
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex.tuple, (NAMED, Some(Box::new(NAMED))));
+    assert_eq!(ex_opt.tuple.1, second());
+}
+
+#[test]
+fn test_nested_undo() {
+    let original = ExampleNested::new(NAMED);
+    let mut ex = original.clone();
+
+    let mut ex_opt = ExampleNestedOpt::default()
+        .shared(|shared| shared.b(0.0))
+        .maybe(|_| OptionPatch::Clear)
+        .array(|[first, second]| [first.c(0), second])
+        .tuple(|(first, _)| (first, OptionPatch::Clear));
+    let mut undo = ex.patch_with_undo(&mut ex_opt);
+    assert_eq!(ex.maybe, None);
+    assert_eq!(ex.tuple.1, None);
+
+    ex.patch(&mut undo);
+    assert_eq!(ex, original);
+}
+
+#[test]
+fn test_nested_diff() {
+    let ex = ExampleNested::new(NAMED);
+    let mut other = ExampleNested::new(NAMED);
+    other.boxed.a = 0;
+    other.maybe = None;
+    other.array[1].b = 0.0;
+
+    let mut diff = ex.diff(&other);
+    assert_eq!(
+        diff.modified_fields(),
+        ExampleNestedFields::Boxed | ExampleNestedFields::Maybe | ExampleNestedFields::Array
+    );
+
+    let mut ex = ex;
+    ex.patch(&mut diff);
+    assert_eq!(ex, other);
+}
+
+#[test]
+fn test_nested_track() {
+    let mut ex = ExampleNested::new(NAMED);
+
+    let mut ex_opt = ExampleNestedOpt::default()
+        .boxed(|boxed| boxed.a(A))
+        .maybe(|_| OptionPatch::Patch(ExampleNamedOpt::default().c(0)))
+        .array(|[first, second]| [first, second.b(0.0)]);
+    let changes = ex.patch_tracked(&mut ex_opt);
+
+    // equal values aren't changes
+    assert_eq!(
+        changes.fields,
+        ExampleNestedFields::Maybe | ExampleNestedFields::Array
+    );
+    assert!(changes.boxed.is_empty());
+    assert_eq!(
+        changes.maybe,
+        OptionChanges::Changed(ExampleNamedChanges {
+            fields: ExampleNamedFields::C,
+        })
+    );
+    assert!(changes.array[0].is_empty());
+    assert_eq!(changes.array[1].fields, ExampleNamedFields::B);
+
+    let changes = ex.patch_tracked(&mut ExampleNestedOpt::default().maybe(|_| OptionPatch::Clear));
+    assert_eq!(changes.maybe, OptionChanges::Replaced);
+}
+
+#[test]
+fn test_nested_full() {
+    let ex = ExampleNested::new(NAMED);
+    assert_eq!(ex.clone().into_opt().try_into_full(), Ok(ex));
+
+    let ex_opt = ExampleNestedOpt::default()
+        .boxed(|_| NAMED.into_opt())
+        .shared(|_| NAMED.into_opt())
+        .local(|_| NAMED.into_opt())
+        .maybe(|_| OptionPatch::Clear)
+        .array(|_| [NAMED.into_opt(), ExampleNamedOpt::default().a(A)])
+        .tuple(|_| (NAMED.into_opt(), OptionPatch::Unchanged));
+    let missing = ex_opt.try_into_full().unwrap_err();
+    assert_eq!(missing.fields(), ["array.1.b", "array.1.c", "tuple.1"]);
+}
+
+#[cfg(feature = "bytemuck")]
+mod wire {
+    use super::*;
+
+    // the length of the (hashed) identity
+    const ID_LEN: usize = IdWidth::Varint.len(ExampleNestedOpt::ID);
+
+    #[test]
+    fn test_nested_serialize() {
+        let ex_opt = ExampleNestedOpt::default()
+            .boxed(|boxed| boxed.a(A))
+            .shared(|shared| shared.b(B))
+            .maybe(|_| OptionPatch::Patch(ExampleNamedOpt::default().c(C)))
+            .array(|[first, second]| [first, second.c(C)])
+            .tuple(|(first, _)| (first.a(A), OptionPatch::Clear));
+        assert_eq!(
+            ExampleNestedOpt::deserialize(&ex_opt.serialize()[ID_LEN..]),
+            ex_opt
+        );
+
+        let ex_opt = ExampleNested::new(NAMED).into_opt();
+        assert_eq!(
+            ExampleNestedOpt::deserialize(&ex_opt.serialize()[ID_LEN..]),
+            ex_opt
+        );
+    }
+}
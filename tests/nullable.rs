@@ -0,0 +1,175 @@
+mod common;
+use common::nullable::*;
+use wopt::*;
+
+#[test]
+fn test_nullable_patch() {
+    let mut ex = ExampleNullable {
+        a: A,
+        b: Some(B),
+        c: Some(C),
+    };
+
+    let mut ex_opt = ExampleNullableOpt {
+        a: None,
+        b: Nullable::Clear,
+        c: Nullable::Unchanged,
+    };
+    assert!(ex_opt.is_modified());
+    ex.patch(&mut ex_opt);
+    assert!(!ex_opt.is_modified());
+    assert_eq!(
+        ex,
+        ExampleNullable {
+            a: A,
+            b: None,
+            c: Some(C),
+        }
+    );
+
+    ex.patch(&mut ExampleNullableOpt::default().b(Some(1.0)));
+    assert_eq!(ex.b, Some(1.0));
+}
+
+#[test]
+fn test_nullable_into_opt() {
+    let ex = ExampleNullable {
+        a: A,
+        b: None,
+        c: Some(C),
+    };
+    let ex_opt = ex.into_opt();
+    assert_eq!(
+        ex_opt,
+        ExampleNullableOpt {
+            a: Some(A),
+            b: Nullable::Clear,
+            c: Nullable::Set(C),
+        }
+    );
+    assert_eq!(ex_opt.try_into_full(), Ok(ex));
+
+    let missing = ExampleNullableOpt::default().a(A).try_into_full();
+    assert_eq!(missing.unwrap_err().fields(), ["b", "c"]);
+}
+
+#[test]
+fn test_nullable_diff_undo() {
+    let a = ExampleNullable {
+        a: A,
+        b: Some(B),
+        c: None,
+    };
+    let b = ExampleNullable {
+        a: A,
+        b: None,
+        c: None,
+    };
+    let mut diff = a.diff(&b);
+    assert_eq!(diff, ExampleNullableOpt::default().b(None));
+
+    let mut ex = a;
+    let mut undo = ex.patch_with_undo(&mut diff);
+    assert_eq!(ex, b);
+    assert_eq!(undo, ExampleNullableOpt::default().b(Some(B)));
+
+    ex.patch(&mut undo);
+    assert_eq!(ex, a);
+}
+
+#[test]
+fn test_nullable_merge() {
+    let p =
+        ExampleNullableOpt::default().b(Some(B)).c(None) | ExampleNullableOpt::default().b(None);
+    assert_eq!(p.get_b(), Nullable::Clear);
+    assert_eq!(p.get_c(), Nullable::Clear);
+
+    let mut p = p;
+    p.clear_b();
+    assert!(!p.is_b_set());
+}
+
+#[test]
+fn test_nullable_alias() {
+    let mut ex = ExampleNullableAlias(A, Some(1));
+    ex.patch(&mut ExampleNullableAliasOpt(None, Nullable::Clear));
+    assert_eq!(ex, ExampleNullableAlias(A, None));
+}
+
+#[test]
+fn test_nullable_enum() {
+    let mut ex = ExampleEnumNullable::Idle;
+
+    // clearing is a complete patch too
+    ex.patch(&mut ExampleEnumNullableOpt::Value {
+        a: Some(A),
+        b: Nullable::Clear,
+    });
+    assert_eq!(ex, ExampleEnumNullable::Value { a: A, b: None });
+
+    ex.patch(&mut ExampleEnumNullableOpt::Value {
+        a: None,
+        b: Nullable::Set(C),
+    });
+    assert_eq!(ex, ExampleEnumNullable::Value { a: A, b: Some(C) });
+
+    // an unchanged field is incomplete
+    let mut undo = ex.patch_with_undo(&mut ExampleEnumNullableOpt::Idle);
+    assert_eq!(ex, ExampleEnumNullable::Idle);
+    ex.patch(&mut undo);
+    assert_eq!(ex, ExampleEnumNullable::Value { a: A, b: Some(C) });
+
+    let mut ex = ExampleEnumNullable::Idle;
+    ex.patch(&mut ExampleEnumNullableOpt::Value {
+        a: Some(A),
+        b: Nullable::Unchanged,
+    });
+    assert_eq!(ex, ExampleEnumNullable::Idle);
+}
+
+#[cfg(feature = "bytemuck")]
+mod wire {
+    use super::*;
+
+    #[test]
+    fn test_nullable_serialize() {
+        let ex = ExampleNullable {
+            a: A,
+            b: None,
+            c: Some(C),
+        };
+        let serialized = ex.serialize();
+        assert_eq!(serialized[1..], [A, 0, 0, 0, 0, 0, 1, 0, 248, 255, 255]);
+        assert_eq!(ExampleNullable::deserialize(&serialized[1..]), ex);
+    }
+
+    #[test]
+    fn test_nullable_serialize_opt() {
+        let ex_opt = ExampleNullableOpt {
+            a: None,
+            b: Nullable::Clear,
+            c: Nullable::Set(C),
+        };
+        let serialized = ex_opt.serialize();
+
+        // the clear bits follow the bit of every field
+        let mask = ExampleNullableFields::C.bits() | 1 << ExampleNullableFields::FIELD_NAMES.len();
+        assert_eq!(mask, 0b01100);
+        assert_eq!(serialized[1..], [mask, 0, 248, 255, 255]);
+        assert_eq!(ExampleNullableOpt::deserialize(&serialized[1..]), ex_opt);
+
+        let ex_opt = ExampleEnumNullableOpt::Value {
+            a: None,
+            b: Nullable::Clear,
+        };
+        let serialized = ex_opt.serialize();
+        assert_eq!(serialized[1..], [1, 0b100]);
+        assert_eq!(
+            ExampleEnumNullableOpt::deserialize(&serialized[1..]),
+            ex_opt
+        );
+
+        let ex = ExampleEnumNullable::Value { a: A, b: Some(C) };
+        assert_eq!(ExampleEnumNullable::deserialize(&ex.serialize()[1..]), ex);
+    }
+}
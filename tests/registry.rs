@@ -0,0 +1,91 @@
+#![cfg(feature = "bytemuck")]
+
+mod common;
+use common::enums::*;
+use common::named::*;
+use wopt::*;
+
+registry! {
+    /// Every type exchanged by the tests.
+    #[derive(Debug, PartialEq)]
+    pub enum Message {
+        ExampleNamed,
+        ExampleNamedReq,
+        ExampleNamedVec,
+        ExampleEnum,
+    }
+}
+
+#[test]
+fn test_registry_decode() {
+    let ex = ExampleNamed { a: A, b: B, c: C };
+    let bytes = ex.serialize();
+    assert_eq!(Message::decode(&bytes), Ok(Message::ExampleNamed(ex)));
+
+    let ex_opt = ExampleNamedReqOpt {
+        a: Some(A),
+        b: B,
+        c: None,
+    };
+    let bytes = ex_opt.serialize();
+    let message = Message::from(ex_opt);
+    assert_eq!(message.id(), ExampleNamedReqOpt::ID as u32);
+    assert_eq!(message.encode(), bytes);
+    assert_eq!(Message::decode(&message.encode()), Ok(message));
+
+    let ex = ExampleEnum::Unnamed(A, B);
+    assert_eq!(
+        Message::decode(&ex.serialize()),
+        Ok(Message::ExampleEnum(ex))
+    );
+}
+
+#[test]
+fn test_registry_encode() {
+    let ex = ExampleNamedVec {
+        a: A,
+        b: vec![1, 2],
+        c: C,
+    };
+    let message = Message::from(ex.clone());
+    assert_eq!(message.try_encode(), Ok(ex.serialize()));
+
+    // lengths overflowing their prefix are rejected
+    let message = Message::from(ExampleNamedVec {
+        b: vec![0; 0x10000],
+        ..ex
+    });
+    let e = message.try_encode().unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "unencodable field `b`: 65536 overflows `u16`"
+    );
+}
+
+#[test]
+#[should_panic(expected = "unencodable field `b`: 65536 overflows `u16`")]
+fn test_registry_encode_panics() {
+    let ex = ExampleNamedVec {
+        b: vec![0; 0x10000],
+        ..Default::default()
+    };
+    Message::from(ex).encode();
+}
+
+#[test]
+fn test_registry_unknown() {
+    let e = Message::decode(&[]).unwrap_err();
+    assert_eq!(e.offset(), 0);
+    assert_eq!(e.to_string(), "invalid bytes at offset 0: missing ID");
+
+    // the identities of the other types
+    let ids = [
+        ExampleNamed::ID,
+        ExampleNamedReq::ID,
+        ExampleNamedVec::ID,
+        ExampleEnum::ID,
+    ];
+    let id = (0..0x80).find(|id| !ids.contains(id)).unwrap();
+    let e = Message::decode(&[id]).unwrap_err();
+    assert_eq!(e.reason(), format!("unknown ID `{id}`"));
+}
@@ -0,0 +1,121 @@
+mod common;
+use common::enums::*;
+use common::list::*;
+use common::map::*;
+use common::track::*;
+use std::collections::{BTreeSet, HashMap};
+use wopt::*;
+
+fn calls() -> Vec<String> {
+    CALLS.with_borrow_mut(std::mem::take)
+}
+
+#[test]
+fn test_track_fields() {
+    let mut ex = ExampleTrack {
+        a: A,
+        b: Some(C),
+        c: ExampleNamed { a: A, b: B, c: C },
+    };
+
+    // equal values aren't changes
+    let changes = ex.patch_tracked(&mut ExampleTrackOpt::default().a(A).b(Some(C)));
+    assert!(changes.is_empty());
+    assert!(calls().is_empty());
+
+    let mut ex_opt = ExampleTrackOpt::default().a(2).b(None);
+    let changes = ex.patch_tracked(&mut ex_opt);
+    assert!(!ex_opt.is_modified());
+    assert_eq!(
+        changes.fields,
+        ExampleTrackFields::A | ExampleTrackFields::B
+    );
+    assert!(changes.c.is_empty());
+    assert_eq!(ex.a, 2);
+    assert_eq!(ex.b, None);
+    assert_eq!(calls(), ["a = 2", "ExampleTrackFields(A | B) (None)"]);
+}
+
+#[test]
+fn test_track_nested() {
+    let mut ex = ExampleTrack::default();
+
+    let changes = ex.patch_tracked(&mut ExampleTrackOpt::default().c(|c| c.a(0).c(C)));
+    assert_eq!(changes.fields, ExampleTrackFields::C);
+    assert_eq!(
+        changes.c,
+        ExampleNamedChanges {
+            fields: ExampleNamedFields::C,
+        }
+    );
+    assert_eq!(calls(), ["ExampleTrackFields(C) (None)"]);
+
+    // through the trait
+    let changes = Track::patch_tracked(&mut ex, &mut ExampleTrackOpt::default().c(|c| c.c(C)));
+    assert!(changes.is_empty());
+}
+
+#[test]
+fn test_track_enum() {
+    let mut ex = ExampleEnum::Named { a: A, b: B, c: C };
+
+    let changes = ex.patch_tracked(&mut ExampleEnumOpt::Named {
+        a: Some(A),
+        b: None,
+        c: Some(0),
+    });
+    assert!(!changes.variant);
+    assert_eq!(changes.fields, ExampleEnumFields::NamedC);
+    assert_eq!(changes.fields.names().collect::<Vec<_>>(), ["Named.c"]);
+
+    let changes = ex.patch_tracked(&mut ExampleEnumOpt::Named {
+        a: Some(A),
+        b: None,
+        c: None,
+    });
+    assert!(changes.is_empty());
+
+    let changes = ex.patch_tracked(&mut ExampleEnumOpt::Idle);
+    assert!(changes.variant);
+    assert!(changes.fields.is_empty());
+
+    let changes = ex.patch_tracked(&mut ExampleEnumOpt::Idle);
+    assert!(changes.is_empty());
+}
+
+#[test]
+fn test_track_ops() {
+    let mut ex = ExampleList {
+        a: A,
+        b: vec![1, 2],
+    };
+
+    // operations leaving the collection equal aren't changes
+    let changes = ex.patch_tracked(&mut ExampleListOpt::default().b(|b| b.set(0, 1)));
+    assert!(changes.is_empty());
+    let changes = ex.patch_tracked(&mut ExampleListOpt::default().b(|b| b.push(3).pop()));
+    assert!(changes.is_empty());
+
+    let changes = ex.patch_tracked(&mut ExampleListOpt::default().b(|b| b.set(0, 3)));
+    assert_eq!(changes.fields, ExampleListFields::B);
+    assert_eq!(ex.b, [3, 2]);
+
+    let mut ex = ExampleMap {
+        a: A,
+        b: HashMap::from([(1, 10)]),
+        c: BTreeSet::from([1]),
+    };
+    let changes = ex.patch_tracked(
+        &mut ExampleMapOpt::default()
+            .b(|b| b.insert(1, 10).remove(2))
+            .c(|c| c.insert(1)),
+    );
+    assert!(changes.is_empty());
+
+    let changes = ex.patch_tracked(
+        &mut ExampleMapOpt::default()
+            .b(|b| b.remove(2))
+            .c(|c| c.insert(2)),
+    );
+    assert_eq!(changes.fields, ExampleMapFields::C);
+}
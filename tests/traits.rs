@@ -0,0 +1,76 @@
+mod common;
+use common::{enums::*, named::*, unnamed::*};
+use wopt::{Patch, WithOpt};
+
+fn apply<T: WithOpt>(t: &mut T, p: &mut T::Opt) -> bool {
+    let is_modified = p.is_modified();
+    t.patch(p);
+    is_modified
+}
+
+#[test]
+fn test_traits_patch() {
+    let mut ex = ExampleNamed { a: A, b: B, c: C };
+    let mut ex_opt = ExampleNamedOpt {
+        a: Some(1),
+        b: None,
+        c: None,
+    };
+    assert!(apply(&mut ex, &mut ex_opt));
+    assert!(!Patch::is_modified(&ex_opt));
+    assert_eq!(ex, ExampleNamed { a: 1, b: B, c: C });
+
+    let mut ex = ExampleUnnamed(A, B, C);
+    let mut ex_opt = WithOpt::into_opt(ExampleUnnamed(1, 2.0, 3));
+    assert!(apply(&mut ex, &mut ex_opt));
+    assert_eq!(ex, ExampleUnnamed(1, 2.0, 3));
+
+    let mut ex = ExampleEnum::Idle;
+    let mut ex_opt = ExampleEnumOpt::Unmodified;
+    assert!(!apply(&mut ex, &mut ex_opt));
+    assert_eq!(ex, ExampleEnum::Idle);
+}
+
+#[test]
+fn test_traits_take() {
+    let mut ex_opt = ExampleNamedOpt {
+        a: Some(1),
+        b: None,
+        c: Some(2),
+    };
+    let taken = Patch::take(&mut ex_opt);
+    assert!(!ex_opt.is_modified());
+    assert_eq!(taken.c, Some(2));
+}
+
+#[cfg(feature = "bytemuck")]
+mod wire {
+    use super::*;
+    use common::unit::*;
+    use wopt::WireEncode;
+
+    fn roundtrip<T: WireEncode>(t: &T) -> T {
+        let bytes = t.serialize();
+        assert_eq!(T::ID_WIDTH.decode(&bytes), Ok((T::ID, 1)));
+        T::deserialize_body(&bytes[1..])
+    }
+
+    #[test]
+    fn test_traits_wire() {
+        let ex = ExampleNamed { a: A, b: B, c: C };
+        assert_eq!(<ExampleNamed as WireEncode>::ID, ExampleNamed::ID as u32);
+        assert_eq!(roundtrip(&ex), ex);
+
+        let ex_opt = ExampleNamedOpt {
+            a: None,
+            b: Some(B),
+            c: None,
+        };
+        assert_eq!(roundtrip(&ex_opt), ex_opt);
+
+        let ex = ExampleEnum::Unnamed(A, B);
+        assert_eq!(roundtrip(&ex), ex);
+
+        assert_eq!(roundtrip(&ExampleUnit), ExampleUnit);
+    }
+}
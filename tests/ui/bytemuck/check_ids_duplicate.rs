@@ -0,0 +1,14 @@
+use wopt::*;
+
+#[derive(WithOpt)]
+#[wopt(id = 2)]
+struct Ping(u32);
+
+// serialized differently, but with the same `WireEncode::ID`
+#[derive(WithOpt)]
+#[wopt(id = 1, id_width = "varint")]
+struct Pong(u32);
+
+check_ids!(Ping, PingOpt, Pong, PongOpt);
+
+fn main() {}
@@ -0,0 +1,22 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+#[wopt(id = 1)]
+struct Ping(u32);
+
+#[derive(WithOpt)]
+#[wopt(id = 1)]
+struct Pong(u32);
+
+// hashed from the same name and schema
+mod a {
+    #[derive(wopt::WithOpt)]
+    pub struct Move(pub u32);
+}
+
+mod b {
+    #[derive(wopt::WithOpt)]
+    pub struct Move(pub u32);
+}
+
+fn main() {}
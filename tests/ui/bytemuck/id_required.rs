@@ -0,0 +1,11 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+#[wopt(id_width = "u8")]
+struct Ping(u32);
+
+#[derive(WithOpt)]
+#[wopt(id_width = "u16")]
+struct Pong(u32);
+
+fn main() {}
@@ -0,0 +1,31 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+#[wopt(id = 128)]
+struct Example {
+    a: u8,
+    b: f32,
+}
+
+#[derive(WithOpt)]
+#[wopt(id = "1")]
+struct ExampleStr {
+    a: u8,
+    b: f32,
+}
+
+#[derive(WithOpt)]
+#[wopt(id = 32768, id_width = "u16")]
+struct ExampleWide {
+    a: u8,
+    b: f32,
+}
+
+#[derive(WithOpt)]
+#[wopt(id_width = "u32")]
+struct ExampleWidth {
+    a: u8,
+    b: f32,
+}
+
+fn main() {}
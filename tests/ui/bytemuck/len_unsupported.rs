@@ -0,0 +1,17 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+struct Example {
+    a: u8,
+    #[wopt(len = "u32")]
+    b: f32,
+}
+
+#[derive(WithOpt)]
+#[wopt(len = "u64")]
+struct ExampleStruct {
+    #[wopt(list_ops, len = "usize")]
+    a: Vec<u8>,
+}
+
+fn main() {}
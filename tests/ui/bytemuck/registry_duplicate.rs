@@ -0,0 +1,14 @@
+use wopt::{WithOpt, registry};
+
+#[derive(WithOpt)]
+struct Ping(u32);
+
+registry! {
+    enum Message {
+        Ping,
+        Ping,
+        (u8, u8),
+    }
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use wopt::*;
+
+#[derive(WithOpt)]
+struct Ping(u32);
+
+#[derive(WithOpt)]
+#[wopt(id = 1)]
+struct Pong(u32);
+
+registry! {
+    enum Message {
+        Ping,
+        Pong,
+    }
+}
+
+fn main() {}
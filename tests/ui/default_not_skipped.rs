@@ -0,0 +1,10 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+struct Example {
+    a: u8,
+    #[wopt(default = 1)]
+    b: u8,
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use wopt::WithOpt;
+
+fn check(_: &Example) -> bool {
+    true
+}
+
+#[derive(WithOpt)]
+#[wopt(validate = "check")]
+#[wopt(derive(Debug), validate = "check")]
+struct Example {
+    /// Required, and later skipped.
+    #[wopt(required)]
+    #[wopt(skip)]
+    a: u8,
+    #[wopt(optional)]
+    #[wopt(non_const, optional)]
+    b: f32,
+}
+
+fn main() {}
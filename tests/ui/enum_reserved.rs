@@ -0,0 +1,9 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+enum Example {
+    Idle,
+    Unmodified,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+struct Example {
+    a: u8,
+    default: u8,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+struct Example {
+    a: u8,
+    #[wopt(list_ops)]
+    b: [u8; 4],
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use std::collections::HashSet;
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+struct Example {
+    #[wopt(map_ops)]
+    a: Vec<u8>,
+    #[wopt(map_ops, optional)]
+    b: HashSet<u8>,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+struct Example {}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+struct Example {
+    a: u8,
+    #[wopt(nullable, required)]
+    b: Option<u8>,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use wopt::WithOpt;
+
+fn on_b(_: &u8) {}
+
+#[derive(WithOpt)]
+struct Example {
+    a: u8,
+    #[wopt(required, on_change = "on_b")]
+    b: u8,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+struct Example {
+    a: u8,
+    #[wopt(optional)]
+    b: [fn(); 2],
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use wopt::WithOpt;
+
+#[derive(Clone, Copy, WithOpt)]
+#[wopt(no_serde)]
+struct Inner {
+    a: u8,
+}
+
+#[derive(WithOpt)]
+#[wopt(no_serde)]
+struct Example {
+    #[wopt(optional, serde)]
+    a: Box<Inner>,
+}
+
+fn main() {}
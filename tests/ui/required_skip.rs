@@ -0,0 +1,10 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+struct Example {
+    #[wopt(required, skip)]
+    a: u8,
+    b: f32,
+}
+
+fn main() {}
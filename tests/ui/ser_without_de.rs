@@ -0,0 +1,14 @@
+use wopt::WithOpt;
+
+fn ser(data: &Vec<u8>) -> &[u8] {
+    data.as_slice()
+}
+
+#[derive(WithOpt)]
+struct Example {
+    a: u8,
+    #[wopt(ser = "ser")]
+    b: Vec<u8>,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+#[wopt(no_such)]
+#[wopt(other(Debug))]
+#[wopt(derive(Debug))]
+struct Example {
+    #[wopt(skip, nope)]
+    a: u8,
+    b: f32,
+}
+
+fn main() {}
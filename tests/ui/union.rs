@@ -0,0 +1,9 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+union Example {
+    a: u8,
+    b: f32,
+}
+
+fn main() {}
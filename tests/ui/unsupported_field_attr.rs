@@ -0,0 +1,10 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+struct Example {
+    #[wopt(foo, required, bar = "baz")]
+    a: u8,
+    b: f32,
+}
+
+fn main() {}
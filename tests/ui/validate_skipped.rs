@@ -0,0 +1,10 @@
+use wopt::WithOpt;
+
+#[derive(WithOpt)]
+struct Example {
+    a: u8,
+    #[wopt(skip, range = "0..10")]
+    b: u8,
+}
+
+fn main() {}
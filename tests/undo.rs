@@ -0,0 +1,107 @@
+mod common;
+use common::{enums::*, flat::*, named::*, unnamed::*};
+use wopt::*;
+
+#[test]
+fn test_undo_named() {
+    let original = ExampleNamed { a: A, b: B, c: C };
+    let mut ex = original;
+
+    let mut undo = ex.patch_with_undo(&mut ExampleNamedOpt {
+        a: None,
+        b: Some(1.0),
+        c: Some(2),
+    });
+    assert_eq!(ex, ExampleNamed { a: A, b: 1.0, c: 2 });
+
+    // only the replaced fields are restored
+    assert_eq!(
+        undo,
+        ExampleNamedOpt {
+            a: None,
+            b: Some(B),
+            c: Some(C),
+        }
+    );
+
+    ex.patch(&mut undo);
+    assert_eq!(ex, original);
+}
+
+#[test]
+fn test_undo_unnamed() {
+    let mut ex = ExampleUnnamedReq(A, B, C);
+
+    // required fields are carried through
+    let undo = ex.patch_with_undo(&mut ExampleUnnamedReqOpt(Some(1), 2.0, None));
+    assert_eq!(undo, ExampleUnnamedReqOpt(Some(A), 2.0, None));
+}
+
+#[test]
+fn test_undo_nested() {
+    let original = ExampleNested {
+        a: A,
+        b: ExampleNamed { a: A, b: B, c: C },
+    };
+    let mut ex = original;
+
+    let mut undo = WithOpt::patch_with_undo(
+        &mut ex,
+        &mut ExampleNestedOpt {
+            a: None,
+            b: ExampleNamedOpt {
+                c: Some(1),
+                ..Default::default()
+            },
+        },
+    );
+    assert_eq!(
+        undo,
+        ExampleNestedOpt {
+            a: None,
+            b: ExampleNamedOpt {
+                c: Some(C),
+                ..Default::default()
+            },
+        }
+    );
+
+    ex.patch(&mut undo);
+    assert_eq!(ex, original);
+}
+
+#[test]
+fn test_undo_enum() {
+    let original = ExampleEnum::Named { a: A, b: B, c: C };
+    let mut ex = original;
+
+    // same variant
+    let mut undo = ex.patch_with_undo(&mut ExampleEnumOpt::Named {
+        a: Some(1),
+        b: None,
+        c: None,
+    });
+    assert_eq!(
+        undo,
+        ExampleEnumOpt::Named {
+            a: Some(A),
+            b: None,
+            c: None,
+        }
+    );
+    ex.patch(&mut undo);
+    assert_eq!(ex, original);
+
+    // another variant, restored with all of the previous fields
+    let mut undo = ex.patch_with_undo(&mut ExampleEnumOpt::Unnamed(Some(1), 2.0));
+    assert_eq!(ex, ExampleEnum::Unnamed(1, 2.0));
+    ex.patch(&mut undo);
+    assert_eq!(ex, original);
+
+    // unapplied patches have nothing to undo, and are left unconsumed
+    let mut ex_opt = ExampleEnumOpt::Unnamed(None, 2.0);
+    let undo = ex.patch_with_undo(&mut ex_opt);
+    assert_eq!(undo, ExampleEnumOpt::Unmodified);
+    assert_eq!(ex_opt, ExampleEnumOpt::Unnamed(None, 2.0));
+    assert_eq!(ex, original);
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::init_numbered_fields)]
+
 mod common;
 use common::unnamed::*;
 
@@ -0,0 +1,148 @@
+mod common;
+use common::validate::*;
+
+#[test]
+fn test_validate_field() {
+    let ex = ExampleValidate {
+        a: 100,
+        b: 2,
+        c: None,
+    };
+    assert_eq!(ex.validate(), Ok(()));
+
+    let ex = ExampleValidate { a: 101, ..ex };
+    let e = ex.validate().unwrap_err();
+    assert_eq!(e.field(), Some("a"));
+    assert_eq!(e.to_string(), "invalid field `a`: out of range (0..=100)");
+
+    let ex = ExampleValidate {
+        a: 1,
+        b: 3,
+        c: None,
+    };
+    let e = ex.validate().unwrap_err();
+    assert_eq!(e.field(), Some("b"));
+    assert_eq!(e.reason(), "not even");
+
+    // only set values are checked
+    let ex = ExampleValidate {
+        a: 1,
+        b: 2,
+        c: Some(10),
+    };
+    assert_eq!(ex.validate().unwrap_err().field(), Some("c"));
+}
+
+#[test]
+fn test_try_patch() {
+    let mut ex = ExampleValidate {
+        a: 1,
+        b: 2,
+        c: Some(3),
+    };
+
+    let mut ex_opt = ExampleValidateOpt::default().a(50).c(None);
+    assert_eq!(ex.try_patch(&mut ex_opt), Ok(()));
+    assert!(!ex_opt.is_modified());
+    assert_eq!(
+        ex,
+        ExampleValidate {
+            a: 50,
+            b: 2,
+            c: None,
+        }
+    );
+
+    // either every field is patched, or none of them
+    let mut ex_opt = ExampleValidateOpt::default().a(60).b(5).c(Some(4));
+    let e = ex.try_patch(&mut ex_opt).unwrap_err();
+    assert_eq!(e.field(), Some("b"));
+    assert_eq!(
+        ex,
+        ExampleValidate {
+            a: 50,
+            b: 2,
+            c: None,
+        }
+    );
+    // the rejected patch is left intact
+    assert_eq!(ex_opt, ExampleValidateOpt::default().a(60).b(5).c(Some(4)));
+
+    // fields left unchanged aren't checked
+    let mut ex = ExampleValidate {
+        a: 200,
+        b: 2,
+        c: None,
+    };
+    assert_eq!(
+        ex.try_patch(&mut ExampleValidateOpt::default().b(4)),
+        Ok(())
+    );
+    assert_eq!(ex.b, 4);
+}
+
+#[test]
+fn test_try_patch_struct() {
+    let mut ex = ExampleValidateBounds { min: 1, max: 5 };
+
+    let mut ex_opt = ExampleValidateBoundsOpt::default().min(6);
+    let e = ex.try_patch(&mut ex_opt).unwrap_err();
+    assert_eq!(e.field(), None);
+    assert_eq!(e.to_string(), "invalid value: 6 > 5");
+    assert_eq!(ex, ExampleValidateBounds { min: 1, max: 5 });
+
+    // the validator sees the would-be result
+    let mut ex_opt = ExampleValidateBoundsOpt::default().min(6).max(8);
+    assert_eq!(ex.try_patch(&mut ex_opt), Ok(()));
+    assert_eq!(ex, ExampleValidateBounds { min: 6, max: 8 });
+
+    assert_eq!(
+        ex.try_patch(&mut ExampleValidateBoundsOpt::default()),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_try_patch_nested() {
+    let mut ex = ExampleValidateNested {
+        a: 1,
+        b: ExampleValidate {
+            a: 1,
+            b: 2,
+            c: None,
+        },
+    };
+    let before = ex;
+
+    let mut ex_opt = ExampleValidateNestedOpt::default()
+        .a(2)
+        .b(|b| b.a(3).c(Some(-20)));
+    let e = ex.try_patch(&mut ex_opt).unwrap_err();
+    assert_eq!(e.field(), Some("b.c"));
+    assert_eq!(e.to_string(), "invalid field `b.c`: out of range (-10..10)");
+    assert_eq!(ex, before);
+
+    let mut ex_opt = ExampleValidateNestedOpt::default().a(0);
+    assert_eq!(ex.try_patch(&mut ex_opt).unwrap_err().field(), Some("a"));
+    assert_eq!(ex, before);
+}
+
+#[test]
+fn test_try_patch_enum() {
+    let mut ex = ExampleEnumValidate::Idle;
+
+    let e = ex
+        .try_patch(&mut ExampleEnumValidateOpt::Value(Some(3)))
+        .unwrap_err();
+    assert_eq!(e.field(), Some("0"));
+    assert_eq!(ex, ExampleEnumValidate::Idle);
+
+    assert_eq!(
+        ex.try_patch(&mut ExampleEnumValidateOpt::Value(Some(4))),
+        Ok(())
+    );
+    assert_eq!(ex, ExampleEnumValidate::Value(4));
+
+    assert_eq!(ex.try_patch(&mut ExampleEnumValidateOpt::Idle), Ok(()));
+    assert_eq!(ex.validate(), Ok(()));
+}
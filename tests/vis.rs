@@ -0,0 +1,28 @@
+mod common;
+use common::vis::*;
+
+#[test]
+fn test_vis_private() {
+    let mut ex = ExampleVis::default();
+
+    // private fields are only reachable through the accessors
+    let mut ex_opt = ExampleVisOpt::default().with_inner(B);
+    ex_opt.a = Some(A);
+    ex.patch(&mut ex_opt);
+    assert_eq!(ex, ExampleVis::new(A, B, 0));
+    assert_eq!(ExampleVisFields::FIELD_NAMES, ["a", "inner", "secret"]);
+}
+
+#[cfg(feature = "bytemuck")]
+mod wire {
+    use super::*;
+
+    #[test]
+    fn test_vis_serialize() {
+        let ex = ExampleVis::new(A, B, C);
+        assert_eq!(ExampleVis::deserialize(&ex.serialize()[1..]), ex);
+
+        let ex_opt = ExampleVisOpt::default().with_inner(B);
+        assert_eq!(ExampleVisOpt::deserialize(&ex_opt.serialize()[1..]), ex_opt);
+    }
+}
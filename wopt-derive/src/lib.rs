@@ -0,0 +1,4607 @@
+use proc_macro::{Span, TokenStream};
+use proc_macro2::{Group, TokenStream as TokenStream2};
+use quote::{ToTokens, format_ident, quote};
+use std::collections::HashSet;
+use syn::ext::IdentExt;
+use syn::{
+    Attribute, Data, DataEnum, DataStruct, DeriveInput, Expr, ExprLit, Field, Fields,
+    GenericArgument, Generics, Ident, Index, Lit, LitStr, Member, Meta, Path, PathArguments,
+    PathSegment, Token, Type, TypePath, Visibility, WhereClause, WherePredicate, parse_macro_input,
+    parse_quote,
+    punctuated::{Iter, Punctuated},
+    token::Paren,
+};
+
+#[cfg(feature = "bytemuck")]
+use syn::LitInt;
+
+#[cfg(all(not(feature = "bytemuck"), feature = "unchecked"))]
+compile_error!("Feature `unchecked` requires feature `bytemuck`.");
+
+/// Accumulates errors, to report all of them at once.
+#[derive(Default)]
+struct Errors(Option<syn::Error>);
+
+impl Errors {
+    fn push(&mut self, error: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(error),
+            None => self.0 = Some(error),
+        }
+    }
+
+    fn finish(self) -> syn::Result<()> {
+        self.0.map_or(Ok(()), Err)
+    }
+}
+
+/// Combines two results, keeping the errors of both.
+fn join<T, U>(a: syn::Result<T>, b: syn::Result<U>) -> syn::Result<(T, U)> {
+    match (a, b) {
+        (Ok(a), Ok(b)) => Ok((a, b)),
+        (Err(mut a), Err(b)) => {
+            a.combine(b);
+            Err(a)
+        }
+        (Err(e), _) | (_, Err(e)) => Err(e),
+    }
+}
+
+/// Whether `ty` wraps the nested type of an `optional` field (e.g., `Box<T>` or `[T; N]`).
+fn is_wrapper(ty: &Type) -> bool {
+    match ty {
+        Type::Paren(paren) => is_wrapper(&paren.elem),
+        Type::Group(group) => is_wrapper(&group.elem),
+        Type::Array(_) => true,
+        Type::Tuple(tuple) => !tuple.elems.is_empty(),
+        _ => matches!(
+            get_type_args(ty),
+            Some((ident, args)) if args.len() == 1
+                && ["Box", "Rc", "Arc", "Option"].iter().any(|wrapper| ident == wrapper)
+        ),
+    }
+}
+
+/// Maps the nested types of an `optional` field through its wrappers, given the mapping of each
+/// nested type (`leaf`) and that of `Option<T>` (given the mapped `T`).
+fn map_nested(
+    ty: &Type,
+    leaf: &dyn Fn(&Type) -> syn::Result<Type>,
+    option: &dyn Fn(Type) -> Type,
+) -> syn::Result<Type> {
+    if !is_wrapper(ty) {
+        return leaf(ty);
+    }
+    match ty {
+        Type::Paren(paren) => map_nested(&paren.elem, leaf, option),
+        Type::Group(group) => map_nested(&group.elem, leaf, option),
+        Type::Array(array) => {
+            let mut array = array.clone();
+            *array.elem = map_nested(&array.elem, leaf, option)?;
+            Ok(Type::Array(array))
+        }
+        Type::Tuple(tuple) => {
+            let mut tuple = tuple.clone();
+            for elem in &mut tuple.elems {
+                *elem = map_nested(elem, leaf, option)?;
+            }
+            Ok(Type::Tuple(tuple))
+        }
+        // `Box`, `Rc` and `Arc` are patched like the type they wrap
+        _ => {
+            let (ident, args) = get_type_args(ty).unwrap();
+            let nested = map_nested(args[0], leaf, option)?;
+            Ok(if ident == "Option" {
+                option(nested)
+            } else {
+                nested
+            })
+        }
+    }
+}
+
+/// The optional type of an `optional` field (e.g., `FooOpt<T>` of `Foo<T>`, or `[FooOpt; 2]` of
+/// `[Foo; 2]`), given the optional type of its nested type if named explicitly.
+fn get_opt_type(original: &Type, named: Option<&Type>) -> syn::Result<Type> {
+    map_nested(
+        original,
+        &|ty| match named {
+            Some(named) => Ok(named.clone()),
+            None => get_path_opt_type(ty),
+        },
+        &|opt| parse_quote! { ::wopt::OptionPatch<#opt> },
+    )
+}
+
+fn get_path_opt_type(original: &Type) -> syn::Result<Type> {
+    if let Type::Path(TypePath { path, .. }) = original
+        && let Some(last_segment) = path.segments.last()
+    {
+        let orig_ident = &last_segment.ident;
+        let new_ident = Ident::new(
+            format!("{orig_ident}Opt").as_str(),
+            Span::call_site().into(),
+        );
+
+        // Construct a new path with the modified ident and same arguments
+        let new_segment = PathSegment {
+            ident: new_ident,
+            arguments: last_segment.arguments.clone(),
+        };
+
+        let mut new_path = path.clone();
+        new_path.segments.pop();
+        new_path.segments.push(new_segment);
+
+        return Ok(Type::Path(TypePath {
+            qself: None,
+            path: new_path,
+        }));
+    }
+    Err(syn::Error::new_spanned(
+        original,
+        "`optional` is only supported on path types.",
+    ))
+}
+
+/// The type of the changes of an `optional` field (e.g., `FooChanges` of `Foo<T>`, or
+/// `[FooChanges; 2]` of `[Foo; 2]`).
+fn get_changes_type(original: &Type) -> syn::Result<Type> {
+    map_nested(original, &get_path_changes_type, &|changes| {
+        parse_quote! { ::wopt::OptionChanges<#changes> }
+    })
+}
+
+fn get_path_changes_type(original: &Type) -> syn::Result<Type> {
+    if let Type::Path(TypePath { path, .. }) = original
+        && let Some(last_segment) = path.segments.last()
+    {
+        // changes are never generic
+        let mut new_path = path.clone();
+        new_path.segments.pop();
+        new_path.segments.push(PathSegment::from(format_ident!(
+            "{}Changes",
+            last_segment.ident
+        )));
+        return Ok(Type::Path(TypePath {
+            qself: None,
+            path: new_path,
+        }));
+    }
+    Err(syn::Error::new_spanned(
+        original,
+        "`optional` is only supported on path types.",
+    ))
+}
+
+struct FieldAttr<'a> {
+    member: Member,
+    binding: Ident,
+    /// Visibility of the field (inherited by the optional field).
+    vis: &'a Visibility,
+    field_type: &'a Type,
+    field_type_opt: Type,
+    is_vec: bool,
+    is_optional: bool,
+    /// Whether the nested type of an `optional` field is wrapped (e.g., `Box<T>` or `[T; N]`), and
+    /// so patched through the traits.
+    is_wrapped: bool,
+    is_required: bool,
+    is_skipped: bool,
+    _is_serde: bool,
+    _serde_fn: Option<[Path; 2]>,
+    /// Value of the field when skipped (`Default::default()` if unspecified).
+    default: Option<Expr>,
+    /// Type of the value of a nullable field (i.e., `T` of `Option<T>`).
+    nullable: Option<Type>,
+    /// Collection of a `list_ops` or `map_ops` field.
+    ops: Option<OpsAttr>,
+    /// Function checking the value of the field.
+    validate: Option<Path>,
+    /// Range containing the value of the field (and its source, for error messages).
+    range: Option<(TokenStream2, String)>,
+    /// Function called with the value of the field whenever a tracked patch changes it.
+    on_change: Option<Path>,
+    /// How the lengths of the field are serialized (that of the type if unspecified).
+    #[cfg(feature = "bytemuck")]
+    len: Option<LenWidth>,
+    /// Attributes of the optional field (`#[cfg]`, doc comments and `#[wopt(attr(...))]`).
+    attrs: Vec<TokenStream2>,
+}
+
+/// The collection of a `list_ops` or `map_ops` field, patched by operations.
+#[allow(clippy::large_enum_variant)] // short-lived
+enum OpsAttr {
+    /// `Vec<T>`, with the optional type of its elements if patched per index (i.e., `optional`).
+    List { elem: Type, elem_opt: Option<Type> },
+    /// `HashMap<K, V>` or `BTreeMap<K, V>`, with the optional type of its values if patched
+    /// per key (i.e., `optional`).
+    Map {
+        key: Type,
+        value: Type,
+        value_opt: Option<Type>,
+    },
+    /// `HashSet<K>` or `BTreeSet<K>`.
+    Set { key: Type },
+}
+
+impl OpsAttr {
+    /// The type of the patch.
+    fn patch_type(&self) -> Type {
+        match self {
+            Self::List {
+                elem,
+                elem_opt: Some(elem_opt),
+            } => parse_quote! { ::wopt::ListPatch<#elem, #elem_opt> },
+            Self::List { elem, .. } => parse_quote! { ::wopt::ListPatch<#elem> },
+            Self::Map {
+                key,
+                value,
+                value_opt: Some(value_opt),
+            } => parse_quote! { ::wopt::MapPatch<#key, #value, #value_opt> },
+            Self::Map { key, value, .. } => parse_quote! { ::wopt::MapPatch<#key, #value> },
+            Self::Set { key } => parse_quote! { ::wopt::SetPatch<#key> },
+        }
+    }
+
+    /// The optional type of the elements, if patched per index (or key).
+    fn elem_opt(&self) -> Option<&Type> {
+        match self {
+            Self::List { elem_opt, .. } => elem_opt.as_ref(),
+            Self::Map { value_opt, .. } => value_opt.as_ref(),
+            Self::Set { .. } => None,
+        }
+    }
+
+    /// The patch setting every element of `value`.
+    fn full_patch(&self, value: TokenStream2) -> TokenStream2 {
+        match self {
+            Self::List { .. } => quote! { ::wopt::ListPatch::from(#value) },
+            Self::Map { .. } => quote! { ::wopt::MapPatch::from_map(#value) },
+            Self::Set { .. } => quote! { ::wopt::SetPatch::from_set(#value) },
+        }
+    }
+
+    /// Bounds of the element types required to compute differences.
+    fn diff_bounds(&self) -> Vec<WherePredicate> {
+        let value_bound = |value: &Type, value_opt: &Option<Type>| -> WherePredicate {
+            match value_opt {
+                Some(value_opt) => parse_quote! {
+                    #value: ::core::cmp::PartialEq + ::core::clone::Clone + ::wopt::Diff<Opt = #value_opt>
+                },
+                None => parse_quote! { #value: ::core::cmp::PartialEq + ::core::clone::Clone },
+            }
+        };
+        match self {
+            Self::List { elem, elem_opt } => vec![value_bound(elem, elem_opt)],
+            Self::Map {
+                key,
+                value,
+                value_opt,
+            } => vec![
+                parse_quote! { #key: ::core::clone::Clone },
+                value_bound(value, value_opt),
+            ],
+            Self::Set { key } => vec![parse_quote! { #key: ::core::clone::Clone }],
+        }
+    }
+
+    /// Name of the collection in the (de)serialization helpers (e.g., `ser_list`), and the types
+    /// of its keys and values, given whether they're (de)serialized through `WithOpt`.
+    #[cfg(feature = "bytemuck")]
+    fn wire_types(&self) -> (&str, Vec<(&Type, bool)>) {
+        match self {
+            Self::List { elem, elem_opt } => ("list", vec![(elem, elem_opt.is_some())]),
+            Self::Map {
+                key,
+                value,
+                value_opt,
+            } => ("map", vec![(key, false), (value, value_opt.is_some())]),
+            Self::Set { key } => ("set", vec![(key, false)]),
+        }
+    }
+}
+
+impl FieldAttr<'_> {
+    /// Whether the optional type is an array or a tuple (i.e., only patched through the traits).
+    fn is_composite(&self) -> bool {
+        self.is_wrapped && matches!(self.field_type_opt, Type::Array(_) | Type::Tuple(_))
+    }
+
+    /// The field as a place expression.
+    fn place(&self, access: Access) -> TokenStream2 {
+        let (member, binding) = (&self.member, &self.binding);
+        match access {
+            Access::Member => quote! { self.#member },
+            Access::Binding => quote! { (*#binding) },
+        }
+    }
+
+    /// The field, moved out of `self`.
+    fn value(&self, access: Access) -> TokenStream2 {
+        let (member, binding) = (&self.member, &self.binding);
+        match access {
+            Access::Member => quote! { self.#member },
+            Access::Binding => quote! { #binding },
+        }
+    }
+
+    /// A reference to the field.
+    fn by_ref(&self, access: Access) -> TokenStream2 {
+        let (member, binding) = (&self.member, &self.binding);
+        match access {
+            Access::Member => quote! { &self.#member },
+            Access::Binding => quote! { #binding },
+        }
+    }
+
+    /// The (taken) field of the optional struct being patched in.
+    fn rhs(&self, access: Access) -> TokenStream2 {
+        let member = &self.member;
+        match access {
+            Access::Member => quote! { rhs.#member },
+            Access::Binding => self.rhs_binding().into_token_stream(),
+        }
+    }
+
+    /// A reference to the field of the other value being compared against.
+    fn other(&self, access: Access) -> TokenStream2 {
+        let member = &self.member;
+        match access {
+            Access::Member => quote! { &other.#member },
+            Access::Binding => self.rhs_binding().into_token_stream(),
+        }
+    }
+
+    /// The optional field set to `value`.
+    fn wrap(&self, value: TokenStream2) -> TokenStream2 {
+        if self.nullable.is_some() {
+            // matched in place, to be usable in `const fn`s
+            quote! {
+                match #value {
+                    Some(val) => ::wopt::Nullable::Set(val),
+                    None => ::wopt::Nullable::Clear,
+                }
+            }
+        } else {
+            quote! { Some(#value) }
+        }
+    }
+
+    /// Pattern of the optional field when set, binding its value to `binding` (if any).
+    fn set_pattern(&self, binding: Option<&Ident>) -> TokenStream2 {
+        match (&self.nullable, binding) {
+            (Some(_), Some(binding)) => {
+                quote! { #binding @ (::wopt::Nullable::Set(_) | ::wopt::Nullable::Clear) }
+            }
+            (Some(_), None) => quote! { (::wopt::Nullable::Set(_) | ::wopt::Nullable::Clear) },
+            (None, Some(binding)) => quote! { Some(#binding) },
+            (None, None) => quote! { Some(_) },
+        }
+    }
+
+    /// The value of an optional field bound by [`Self::set_pattern`].
+    fn set_value(&self, binding: &Ident) -> TokenStream2 {
+        if self.nullable.is_some() {
+            quote! { #binding.into_value() }
+        } else {
+            quote! { #binding }
+        }
+    }
+
+    /// The value of a skipped field.
+    fn default(&self) -> TokenStream2 {
+        match &self.default {
+            Some(default) => default.to_token_stream(),
+            None => quote! { ::core::default::Default::default() },
+        }
+    }
+
+    /// Statements checking `value` (a reference to the value of the field) with its validators.
+    fn validators(&self, value: &TokenStream2) -> TokenStream2 {
+        let name = self.name();
+        let validate = self.validate.iter().map(|path| {
+            quote! {
+                #path(#value).map_err(|e| ::wopt::PatchError::new(e).nest(#name))?;
+            }
+        });
+        let range = self.range.iter().map(|(range, source)| {
+            let reason = format!("out of range ({source})");
+            quote! {
+                if !(#range).contains(#value) {
+                    return Err(::wopt::PatchError::new(#reason).nest(#name));
+                }
+            }
+        });
+        quote! {
+            #(#validate)*
+            #(#range)*
+        }
+    }
+
+    /// The name of the field, as reported when missing.
+    fn name(&self) -> String {
+        match &self.member {
+            Member::Named(ident) => ident.unraw().to_string(),
+            Member::Unnamed(index) => index.index.to_string(),
+        }
+    }
+
+    fn rhs_binding(&self) -> Ident {
+        format_ident!("rhs{}", self.binding)
+    }
+}
+
+/// How generated code reaches the fields.
+#[derive(Clone, Copy)]
+enum Access {
+    /// Through `self` (structs).
+    Member,
+    /// Through the bindings of a `match` arm (enum variants).
+    Binding,
+}
+
+struct FieldAttrs<'a> {
+    attrs: Vec<FieldAttr<'a>>,
+    is_const: bool,
+    /// How the lengths of the fields are serialized unless specified (`#[wopt(len = ...)]` of the
+    /// type).
+    #[cfg(feature = "bytemuck")]
+    len: LenWidth,
+}
+
+impl FieldAttrs<'_> {
+    /// The number of bits of the modified fields' bitmask (nullable fields having a clear bit).
+    fn mask_len(&self) -> usize {
+        let fields = self.attrs.iter().filter(|field| !field.is_skipped);
+        fields.clone().count() + fields.filter(|field| field.nullable.is_some()).count()
+    }
+}
+
+/// The name of a generic type and its type arguments (e.g., `Option` and `T` of `Option<T>`).
+fn get_type_args(ty: &Type) -> Option<(&Ident, Vec<&Type>)> {
+    if let Type::Path(TypePath { qself: None, path }) = ty
+        && let Some(last_segment) = path.segments.last()
+        && let PathArguments::AngleBracketed(args) = &last_segment.arguments
+    {
+        let args = args.args.iter().filter_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        });
+        return Some((&last_segment.ident, args.collect()));
+    }
+    None
+}
+
+/// The type argument of `outer<T>` (e.g., `T` of `Option<T>`).
+fn get_type_arg(ty: &Type, outer: &str) -> Option<Type> {
+    match get_type_args(ty)? {
+        (ident, args) if ident == outer && args.len() == 1 => Some(args[0].clone()),
+        _ => None,
+    }
+}
+
+/// The collection of a `list_ops` (`is_list`) or `map_ops` field, given whether its elements are
+/// patched per index (or key), and the optional type of their nested type if named explicitly.
+fn get_ops_attr(
+    ty: &Type,
+    is_list: bool,
+    is_optional: bool,
+    opt_type: Option<&Type>,
+) -> syn::Result<OpsAttr> {
+    let elem_opt = |elem: &Type| {
+        is_optional
+            .then(|| get_opt_type(elem, opt_type))
+            .transpose()
+    };
+    let args = get_type_args(ty).map(|(ident, args)| (ident.to_string(), args));
+    match args
+        .as_ref()
+        .map(|(ident, args)| (ident.as_str(), args.as_slice()))
+    {
+        Some(("Vec", [elem])) if is_list => Ok(OpsAttr::List {
+            elem: (*elem).clone(),
+            elem_opt: elem_opt(elem)?,
+        }),
+        _ if is_list => Err(syn::Error::new_spanned(
+            ty,
+            "`list_ops` is only supported on `Vec<T>` fields.",
+        )),
+        // maps and sets may also have a hasher
+        Some(("HashMap" | "BTreeMap", [key, value, ..])) => Ok(OpsAttr::Map {
+            key: (*key).clone(),
+            value: (*value).clone(),
+            value_opt: elem_opt(value)?,
+        }),
+        Some(("HashSet" | "BTreeSet", [key, ..])) if !is_optional => Ok(OpsAttr::Set {
+            key: (*key).clone(),
+        }),
+        _ => Err(syn::Error::new_spanned(
+            ty,
+            "`map_ops` is only supported on `HashMap`, `BTreeMap`, `HashSet` or `BTreeSet` fields \
+             (with `optional` on maps only).",
+        )),
+    }
+}
+
+/// The attributes of an original item carried over to its optional counterpart.
+fn get_forwarded_attrs(attrs: &[Attribute]) -> Vec<TokenStream2> {
+    attrs
+        .iter()
+        .filter(|attr| {
+            ["cfg", "cfg_attr", "doc"]
+                .iter()
+                .any(|name| attr.path().is_ident(name))
+        })
+        .map(ToTokens::to_token_stream)
+        .collect()
+}
+
+/// The attributes listed by `#[wopt(attr(...))]`.
+fn get_attr_metas(input: syn::parse::ParseStream) -> syn::Result<Vec<TokenStream2>> {
+    let metas = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+    Ok(metas.iter().map(|meta| quote! { #[#meta] }).collect())
+}
+
+/// The `#[wopt(...)]` attributes among `attrs`.
+fn get_wopt_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("wopt"))
+}
+
+fn get_field_kvs(fields: Iter<Field>) -> syn::Result<FieldAttrs> {
+    let mut is_const = true;
+    let mut errors = Errors::default();
+    let mut attrs = Vec::new();
+
+    for (i, field) in fields.enumerate() {
+        let (mut is_vec, mut is_optional, mut is_required, mut is_skipped, mut _is_serde) =
+            Default::default();
+        let (mut ser, mut de): (Option<Path>, Option<Path>) = Default::default();
+        let mut default = None;
+        let mut is_nullable = false;
+        let (mut is_list, mut is_map) = (false, false);
+        let (mut validate, mut range) = (None, None);
+        let mut on_change = None;
+        let mut opt_type: Option<Type> = None;
+        #[cfg(feature = "bytemuck")]
+        let mut len = None;
+
+        // disabled fields are stripped before expansion, while the enabled ones keep their `#[cfg]`
+        let mut forwarded = get_forwarded_attrs(&field.attrs);
+
+        // check for non-constant field (e.g., 'Vec')
+        if let Type::Path(path) = &field.ty
+            && path.path.segments.last().unwrap().ident == "Vec"
+        {
+            is_vec = true;
+            is_const = false;
+        }
+
+        // every `#[wopt(...)]` attribute is merged, wherever it is among the field's attributes
+        let wopt_attrs = get_wopt_attrs(&field.attrs).collect::<Vec<_>>();
+        let mut specified = HashSet::new();
+        for wopt_attr in &wopt_attrs {
+            let result = wopt_attr.parse_nested_meta(|a| {
+                let Some(ident) = a.path.get_ident() else {
+                    return Err(a.error("Unsupported attribute."));
+                };
+                // `attr(...)` lists are merged, while everything else is specified once
+                let key = ident.to_string();
+                if key != "attr" && !specified.insert(key.clone()) {
+                    errors.push(a.error(format!("`{key}` is specified more than once.")));
+                }
+                match key.as_str() {
+                    "non_const" => is_const = false,
+                    "optional" => {
+                        // the optional type may be named explicitly (`{Ident}Opt` otherwise)
+                        if a.input.peek(Token![=]) {
+                            let s: LitStr = a.value()?.parse()?;
+                            opt_type = Some(s.parse()?)
+                        }
+                        is_optional = true
+                    }
+                    "required" => is_required = true,
+                    "skip" => is_skipped = true,
+                    "nullable" => is_nullable = true,
+                    "list_ops" => is_list = true,
+                    "map_ops" => is_map = true,
+                    "serde" => _is_serde = true,
+                    "ser" => {
+                        let s: LitStr = a.value()?.parse()?;
+                        ser = Some(s.parse()?)
+                    }
+                    "de" => {
+                        let s: LitStr = a.value()?.parse()?;
+                        de = Some(s.parse()?)
+                    }
+                    "default" => default = Some(a.value()?.parse()?),
+                    "validate" => {
+                        let s: LitStr = a.value()?.parse()?;
+                        validate = Some(s.parse()?)
+                    }
+                    "range" => {
+                        let s: LitStr = a.value()?.parse()?;
+                        range = Some((s.parse()?, s.value()))
+                    }
+                    "on_change" => {
+                        let s: LitStr = a.value()?.parse()?;
+                        on_change = Some(s.parse()?)
+                    }
+                    "attr" => {
+                        let content;
+                        syn::parenthesized!(content in a.input);
+                        forwarded.extend(get_attr_metas(&content)?);
+                    }
+                    "len" => {
+                        let s: LitStr = a.value()?.parse()?;
+
+                        #[cfg(not(feature = "bytemuck"))]
+                        errors.push(syn::Error::new_spanned(
+                            s,
+                            "Enable the `bytemuck` feature to use the `len` attribute.",
+                        ));
+
+                        #[cfg(feature = "bytemuck")]
+                        match LenWidth::from_name(&s.value()) {
+                            Some(width) => len = Some(width),
+                            None => errors.push(syn::Error::new_spanned(
+                                s,
+                                "Expected `u8`, `u16`, `u32` or `varint`.",
+                            )),
+                        }
+                    }
+                    attr => {
+                        // keep going to report the remaining attributes too
+                        errors.push(a.error(format!("Unsupported attribute ({attr}).")));
+                        if a.input.peek(Token![=]) {
+                            a.value()?.parse::<Expr>()?;
+                        } else if a.input.peek(Paren) {
+                            a.input.parse::<Group>()?;
+                        }
+                    }
+                }
+                Ok(())
+            });
+            if let Err(e) = result {
+                errors.push(e);
+            }
+        }
+
+        // nullable fields are patched as a whole, unlike these
+        let is_patched_otherwise =
+            is_optional || is_required || is_skipped || _is_serde || ser.is_some();
+
+        // conflicts are reported on the span of every `#[wopt(...)]` attribute
+        let span = (!wopt_attrs.is_empty()).then(|| quote! { #(#wopt_attrs)* });
+        if let Some(attr) = &span {
+            if is_required && is_skipped {
+                errors.push(syn::Error::new_spanned(
+                    attr,
+                    "`required` and `skip` can't be specified together.",
+                ));
+            }
+
+            if is_nullable && is_patched_otherwise {
+                errors.push(syn::Error::new_spanned(
+                    attr,
+                    "`nullable` can't be specified with `optional`, `required`, `skip` or ser/de.",
+                ));
+            }
+
+            if (is_list || is_map)
+                && (is_list && is_map
+                    || is_required
+                    || is_skipped
+                    || is_nullable
+                    || _is_serde
+                    || ser.is_some())
+            {
+                errors.push(syn::Error::new_spanned(
+                    attr,
+                    "`list_ops`/`map_ops` can't be specified together, nor with `required`, `skip`, \
+                     `nullable` or ser/de.",
+                ));
+            }
+
+            if (validate.is_some() || range.is_some()) && is_skipped {
+                errors.push(syn::Error::new_spanned(
+                    attr,
+                    "`validate`/`range` aren't supported on skipped fields.",
+                ));
+            }
+
+            // such fields are never patched
+            if on_change.is_some() && (is_required || is_skipped) {
+                errors.push(syn::Error::new_spanned(
+                    attr,
+                    "`on_change` isn't supported on required or skipped fields.",
+                ));
+            }
+
+            if default.is_some() && !is_skipped {
+                errors.push(syn::Error::new_spanned(
+                    attr,
+                    "`default` is only supported on skipped fields.",
+                ));
+            }
+
+            // override if any user-provided definitions
+            if ser.is_some() != de.is_some() {
+                errors.push(syn::Error::new_spanned(
+                    attr,
+                    "Both ser/de need to be implemented.",
+                ));
+            }
+
+            // only these fields are prefixed by their length
+            #[cfg(feature = "bytemuck")]
+            if len.is_some() && !(is_vec && ser.is_some() || is_list || is_map) {
+                errors.push(syn::Error::new_spanned(
+                    attr,
+                    "`len` is only supported on `Vec` fields with ser/de, or `list_ops`/`map_ops` \
+                     fields.",
+                ));
+            }
+        }
+
+        // the elements of a collection are patched per index (or key) with `optional`, rather
+        // than the collection itself
+        let field_type = &field.ty;
+        let ops = if is_list || is_map {
+            match get_ops_attr(field_type, is_list, is_optional, opt_type.as_ref()) {
+                Ok(ops) => {
+                    // collections are serialized with their length
+                    is_const = false;
+                    is_optional = false;
+                    Some(ops)
+                }
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            }
+        } else {
+            None
+        };
+
+        // wrappers are patched through the traits, which aren't `const`
+        let is_wrapped = is_optional && is_wrapper(field_type);
+        if is_wrapped {
+            is_const = false;
+            if _is_serde {
+                errors.push(syn::Error::new_spanned(
+                    field_type,
+                    "`serde` isn't supported on wrapped `optional` fields.",
+                ));
+            }
+        }
+
+        // determine if optional struct provided
+        let field_type_opt = if is_optional {
+            match get_opt_type(field_type, opt_type.as_ref()) {
+                Ok(ty) => ty,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            }
+        } else {
+            field_type.clone()
+        };
+
+        // `Option<T>` fields are nullable, unless patched differently
+        let nullable = if is_nullable {
+            Some(get_type_arg(field_type, "Option").unwrap_or_else(
+                || parse_quote! { <#field_type as ::wopt::__private::OptionType>::Inner },
+            ))
+        } else if is_patched_otherwise || is_list || is_map {
+            None
+        } else {
+            get_type_arg(field_type, "Option")
+        };
+        let field_type_opt = match (&nullable, &ops) {
+            (Some(inner), _) => parse_quote! { ::wopt::Nullable<#inner> },
+            (_, Some(ops)) => ops.patch_type(),
+            (None, None) => field_type_opt,
+        };
+
+        let _serde_fn = ser.zip(de).map(|(ser, de)| [ser, de]);
+
+        // bindings are prefixed to never shadow any generated locals
+        let (member, binding) = match &field.ident {
+            Some(ident) => (
+                Member::Named(ident.clone()),
+                format_ident!("_{}", ident.unraw()),
+            ),
+            None => (Member::Unnamed(Index::from(i)), format_ident!("_{i}")),
+        };
+
+        attrs.push(FieldAttr {
+            member,
+            binding,
+            vis: &field.vis,
+            field_type,
+            field_type_opt,
+            is_vec,
+            is_optional,
+            is_wrapped,
+            is_required,
+            is_skipped,
+            _is_serde,
+            _serde_fn,
+            default,
+            nullable,
+            ops,
+            validate,
+            range,
+            on_change,
+            #[cfg(feature = "bytemuck")]
+            len,
+            attrs: forwarded,
+        });
+    }
+    errors.finish()?;
+    Ok(FieldAttrs {
+        attrs,
+        is_const,
+        #[cfg(feature = "bytemuck")]
+        len: LenWidth::default(),
+    })
+}
+
+/// Code generated for every field of a struct (or of an enum variant).
+#[derive(Default)]
+struct FieldsCode {
+    /// Declarations of the optional fields.
+    fields: Vec<TokenStream2>,
+    /// Statements patching the original fields.
+    upts: Vec<TokenStream2>,
+    /// Expressions checking for modified optional fields.
+    mods: Vec<TokenStream2>,
+    /// Initializers taking the optional fields.
+    take: Vec<TokenStream2>,
+    /// Initializers of the optional fields from the original fields.
+    into: Vec<TokenStream2>,
+    /// Initializers of the original fields from their bindings.
+    new: Vec<TokenStream2>,
+    /// Initializers of the optional fields from their bindings.
+    new_opt: Vec<TokenStream2>,
+    /// Statements binding the original fields from the optional fields, if set.
+    full: Vec<TokenStream2>,
+    /// Expressions checking that the optional fields are set (i.e., that `full` binds them).
+    is_full: Vec<TokenStream2>,
+    /// Initializers of the original fields from their bindings, skipped ones included.
+    new_full: Vec<TokenStream2>,
+    /// Statements merging the optional fields of a later patch.
+    merge: Vec<TokenStream2>,
+    /// Initializers of the optional fields from the values replaced by the patch.
+    undo: Vec<TokenStream2>,
+    /// Initializers of the optional fields from the differences with `other`.
+    diff: Vec<TokenStream2>,
+    /// Expressions checking for patched fields equal to those of `other`.
+    eqs: Vec<TokenStream2>,
+    /// Bounds of the field types required to compute differences.
+    diff_bounds: Vec<WherePredicate>,
+    /// Statements checking the values of the fields.
+    checks: Vec<TokenStream2>,
+    /// Statements checking the values of the fields patched by `undo` (i.e., their undo patch).
+    checks_patched: Vec<TokenStream2>,
+    /// Statements checking that `rhs` applies to the fields (i.e., that list operations are within
+    /// bounds, and that map operations patch present keys).
+    checks_ops: Vec<TokenStream2>,
+    /// Statements putting the modifications left unapplied back into the `unapplied` patch (i.e.,
+    /// list operations out of bounds, or incomplete values of nested options).
+    unapplied: Vec<TokenStream2>,
+    /// Patterns binding the checked fields.
+    checked: Vec<TokenStream2>,
+    /// Statements recording the fields changed by the patch returning `undo` (i.e., their undo patch).
+    changes: Vec<TokenStream2>,
+    /// Declarations of the changes of the `optional` fields.
+    changes_fields: Vec<TokenStream2>,
+    /// Bounds of the field types required to track changes.
+    track_bounds: Vec<WherePredicate>,
+    has_optional: bool,
+    /// Whether an `optional` field is an array or a tuple, whose optional type has no `const`
+    /// methods.
+    has_composite: bool,
+
+    /// Unpadded sizes of the original fields.
+    #[cfg(feature = "bytemuck")]
+    size: Vec<TokenStream2>,
+    #[cfg(feature = "bytemuck")]
+    ser: Vec<TokenStream2>,
+    #[cfg(feature = "bytemuck")]
+    de: Vec<TokenStream2>,
+    #[cfg(feature = "bytemuck")]
+    ser_opt: Vec<TokenStream2>,
+    #[cfg(feature = "bytemuck")]
+    de_opt: Vec<TokenStream2>,
+    /// Field types (de)serialized directly through `bytemuck`.
+    #[cfg(feature = "bytemuck")]
+    pod_types: Vec<Type>,
+}
+
+/// Generates the code of each field, where `flag` is the mask bit of the `n`-th non-skipped field
+/// (or, given its index, the clear bit of a nullable field), and `changed` records it as changed.
+#[cfg_attr(not(feature = "bytemuck"), allow(unused_variables))] // used by serialization
+fn get_fields_code(
+    info: &FieldAttrs,
+    access: Access,
+    is_const: bool,
+    flag: impl Fn(&FieldAttr, usize, Option<usize>) -> TokenStream2,
+    changed: impl Fn(&FieldAttr, usize) -> TokenStream2,
+) -> FieldsCode {
+    let mut code = FieldsCode::default();
+
+    // the clear bits of nullable fields follow the bit of every field
+    #[cfg(feature = "bytemuck")]
+    let (count, mut k) = (
+        info.attrs.iter().filter(|field| !field.is_skipped).count(),
+        0,
+    );
+
+    let mut n = 0;
+    for field in &info.attrs {
+        let FieldAttr {
+            member,
+            binding,
+            vis,
+            field_type,
+            field_type_opt,
+            is_vec,
+            is_optional,
+            is_wrapped,
+            is_required,
+            is_skipped,
+            _is_serde,
+            _serde_fn,
+            ..
+        } = field;
+
+        let place = field.place(access);
+        let value = field.value(access);
+        let rhs = field.rhs(access);
+        let (r, other) = (field.by_ref(access), field.other(access));
+
+        code.new.push(quote! { #member: #binding });
+
+        #[cfg(feature = "bytemuck")]
+        {
+            // nullable fields are serialized with a leading tag
+            let size_of = if *_is_serde {
+                quote! { <#field_type>::UNPADDED_SIZE }
+            } else if let Some(inner) = &field.nullable {
+                quote! { 1 + ::core::mem::size_of::<#inner>() }
+            } else {
+                quote! { ::core::mem::size_of::<#field_type>() }
+            };
+
+            if let Some(inner) = &field.nullable {
+                code.pod_types.push(inner.clone());
+            } else if let Some(ops) = &field.ops {
+                let (_, types) = ops.wire_types();
+                code.pod_types.extend(
+                    types
+                        .into_iter()
+                        .filter(|(_, is_serde)| !is_serde)
+                        .map(|(ty, _)| ty.clone()),
+                );
+            } else if !_is_serde && _serde_fn.is_none() {
+                code.pod_types.push((*field_type).clone());
+            }
+
+            let method_ser = if is_const {
+                quote! {
+                    // the field follows the previous one (or the identity)
+                    h = t;
+                    t += #size_of;
+                    data[h..t].copy_from_slice(field_data)
+                }
+            } else {
+                quote! {
+                    data.extend_from_slice(field_data)
+                }
+            };
+            let method_ser_opt = quote! {
+                data.extend_from_slice(field_data)
+            };
+
+            // errors are reported for the field
+            let name = field.name();
+            let nest = quote! { .map_err(|e| e.nest(#name))? };
+
+            // the lengths of lists (and the indices of their operations)
+            let len = field.len.unwrap_or(info.len).path();
+
+            // the bytes of fields (de)serialized by functions, prefixed by their length if lists
+            let read = if *is_vec {
+                quote! { ::wopt::__private::read_prefixed(bytes, &mut t, #len) }
+            } else {
+                quote! { ::wopt::__private::read(bytes, &mut t, #size_of) }
+            };
+            let write = |method_ser: &TokenStream2| {
+                if *is_vec {
+                    quote! { ::wopt::__private::write_prefixed(field_data, #len, &mut data) #nest }
+                } else {
+                    method_ser.clone()
+                }
+            };
+            let (write_ser, write_ser_opt) = (write(&method_ser), write(&method_ser_opt));
+
+            // elements are (de)serialized like `serde` fields when patched per index (or key)
+            let ops_codec = field.ops.as_ref().map(|ops| {
+                let (kind, types) = ops.wire_types();
+                let (sers, des): (Vec<_>, Vec<_>) = types
+                    .into_iter()
+                    .map(|(ty, is_serde)| get_codec(ty, is_serde))
+                    .unzip();
+                let [ser_patch, de_patch] = match ops {
+                    OpsAttr::Set { .. } => Default::default(),
+                    _ => get_codec_opt(ops.elem_opt()),
+                };
+                let [ser_fn, de_fn, ser_patch_fn, de_patch_fn] = [
+                    format!("ser_{kind}"),
+                    format!("de_{kind}"),
+                    format!("ser_{kind}_patch"),
+                    format!("de_{kind}_patch"),
+                ]
+                .map(|name| Ident::new(&name, Span::call_site().into()));
+                (
+                    [
+                        quote! { #ser_fn(#r, #len, &mut data, #(#sers),*) },
+                        quote! { #de_fn(bytes, &mut t, #len, #(#des),*) },
+                    ],
+                    [
+                        quote! { #ser_patch_fn(#r, #len, &mut data, #(#sers,)* #ser_patch) },
+                        quote! { #de_patch_fn(bytes, &mut t, #len, #(#des,)* #de_patch) },
+                    ],
+                )
+            });
+
+            if let Some(([ser, de], _)) = &ops_codec {
+                code.ser.push(quote! {
+                    ::wopt::__private::#ser #nest;
+                });
+                code.de.push(quote! {
+                    h = t;
+                    let #binding = ::wopt::__private::#de #nest;
+                });
+            } else if let Some([ser, de]) = _serde_fn {
+                code.ser.push(quote! {
+                    let field_data = #ser(#r).as_ref();
+                    #write_ser;
+                });
+                code.de.push(quote! {
+                    h = t;
+                    let #binding = #read.map(|bytes| #de(bytes)) #nest;
+                });
+            } else if *_is_serde {
+                // the fixed-size serialization (i.e., of `const` types) doesn't return errors
+                let field_data = if is_const {
+                    quote! { ::wopt::__private::encoded(::wopt::__private::ser_fields(#r)) }
+                } else {
+                    quote! { ::wopt::__private::ser_fields(#r) #nest }
+                };
+                code.ser.push(quote! {
+                    let field_data = &#field_data;
+                    #method_ser;
+                });
+                code.de.push(quote! {
+                    h = t;
+                    let #binding = <#field_type>::deserialize_from(bytes, &mut t) #nest;
+                });
+            } else if field.nullable.is_some() {
+                code.ser.push(quote! {
+                    let field_data = ::wopt::__private::ser_nullable(#r);
+                    let field_data = field_data.as_slice();
+                    #method_ser;
+                });
+                code.de.push(quote! {
+                    h = t;
+                    let #binding = ::wopt::__private::de_nullable(bytes, &mut t) #nest;
+                });
+            } else {
+                code.ser.push(quote! {
+                    let field_data = ::wopt::__private::bytemuck::bytes_of(#r);
+                    #method_ser;
+                });
+                code.de.push(quote! {
+                    h = t;
+                    let #binding = ::wopt::__private::read_pod(bytes, &mut t) #nest;
+                });
+            }
+
+            if *is_required {
+                if let Some([ser, de]) = _serde_fn {
+                    code.ser_opt.push(quote! {
+                        let field_data = #ser(#r).as_ref();
+                        #write_ser_opt;
+                    });
+                    code.de_opt.push(quote! {
+                        h = t;
+                        let #binding = #read.map(|bytes| #de(bytes)) #nest;
+                    });
+                } else if *_is_serde {
+                    code.ser_opt.push(quote! {
+                        let field_data = &::wopt::__private::ser_fields(#r) #nest;
+                        #method_ser_opt;
+                    });
+                    code.de_opt.push(quote! {
+                        h = t;
+                        let #binding = <#field_type_opt>::deserialize_from(bytes, &mut t) #nest;
+                    });
+                } else {
+                    code.ser_opt.push(quote! {
+                        data.extend_from_slice(::wopt::__private::bytemuck::bytes_of(#r));
+                    });
+                    code.de_opt.push(quote! {
+                        h = t;
+                        let #binding = ::wopt::__private::read_pod(bytes, &mut t) #nest;
+                    });
+                }
+            } else if !is_skipped {
+                let clear = field
+                    .nullable
+                    .as_ref()
+                    .map(|_| flag(field, n, Some(count + k)));
+                let flag = flag(field, n, None);
+
+                if let Some((_, [ser, de])) = &ops_codec {
+                    code.ser_opt.push(quote! {
+                        if (#r).is_modified() {
+                            mask |= #flag;
+                            ::wopt::__private::#ser #nest;
+                        }
+                    });
+                    code.de_opt.push(quote! {
+                        let mut #binding = ::core::default::Default::default();
+                        if mask & #flag == #flag {
+                            h = t;
+                            #binding = ::wopt::__private::#de #nest;
+                        }
+                    });
+                } else if let Some([ser, de]) = _serde_fn {
+                    code.ser_opt.push(quote! {
+                        if let Some(val) = (#r).as_ref() {
+                            let field_data = #ser(val).as_ref();
+                            mask |= #flag;
+                            #write_ser_opt;
+                        }
+                    });
+                    code.de_opt.push(quote! {
+                        let mut #binding = None;
+                        if mask & #flag == #flag {
+                            h = t;
+                            #binding = Some(#read.map(|bytes| #de(bytes)) #nest);
+                        }
+                    });
+                } else if let (Some(inner), Some(clear)) = (&field.nullable, clear) {
+                    k += 1;
+                    code.ser_opt.push(quote! {
+                        match #r {
+                            ::wopt::Nullable::Set(val) => {
+                                mask |= #flag;
+                                data.extend_from_slice(::wopt::__private::bytemuck::bytes_of(val));
+                            }
+                            ::wopt::Nullable::Clear => mask |= #clear,
+                            ::wopt::Nullable::Unchanged => {}
+                        }
+                    });
+                    code.de_opt.push(quote! {
+                        let mut #binding = ::wopt::Nullable::Unchanged;
+                        if mask & #flag == #flag {
+                            h = t;
+                            #binding = ::wopt::Nullable::Set(::wopt::__private::read_pod::<#inner>(bytes, &mut t) #nest);
+                        } else if mask & #clear == #clear {
+                            #binding = ::wopt::Nullable::Clear;
+                        }
+                    });
+                } else if *is_wrapped {
+                    code.ser_opt.push(quote! {
+                        if ::wopt::Patch::is_modified(#r) {
+                            mask |= #flag;
+                            <#field_type as ::wopt::__private::NestedWire>::ser_opt(#r, &mut data) #nest;
+                        }
+                    });
+                    code.de_opt.push(quote! {
+                        let mut #binding = ::core::default::Default::default();
+                        if mask & #flag == #flag {
+                            h = t;
+                            #binding = <#field_type as ::wopt::__private::NestedWire>::de_opt(bytes, &mut t) #nest;
+                        }
+                    });
+                } else if *is_optional {
+                    code.ser_opt.push(quote! {
+                        if (#r).is_modified() {
+                            mask |= #flag;
+                            data.extend_from_slice(&::wopt::__private::ser_fields(#r) #nest);
+                        }
+                    });
+                    code.de_opt.push(quote! {
+                        let mut #binding = ::core::default::Default::default();
+                        if mask & #flag == #flag {
+                            h = t;
+                            #binding = <#field_type_opt>::deserialize_from(bytes, &mut t) #nest;
+                        }
+                    });
+                } else {
+                    code.ser_opt.push(quote! {
+                        if let Some(val) = (#r).as_ref() {
+                            let field_data = ::wopt::__private::bytemuck::bytes_of(val);
+                            mask |= #flag;
+                            #method_ser_opt;
+                        }
+                    });
+                    code.de_opt.push(quote! {
+                        let mut #binding = None;
+                        if mask & #flag == #flag {
+                            h = t;
+                            #binding = Some(::wopt::__private::read_pod(bytes, &mut t) #nest);
+                        }
+                    });
+                }
+            }
+            code.size.push(size_of);
+        }
+
+        if *is_skipped {
+            let default = field.default();
+            code.new_full.push(quote! { #member: #default });
+            continue;
+        }
+        code.new_full.push(quote! { #member: #binding });
+
+        let (name, attrs) = (field.name(), &field.attrs);
+        let field_name = match member {
+            Member::Named(ident) => quote! { #ident: },
+            Member::Unnamed(_) => quote! {},
+        };
+
+        code.new_opt.push(quote! { #member: #binding });
+
+        // `Option` fields are only checked when set, and nested fields by their own validators
+        let validators = field.validators(&quote! { value });
+        let has_validators = field.validate.is_some() || field.range.is_some();
+        if has_validators || *is_optional {
+            let is_patched = if *is_wrapped {
+                quote! { ::wopt::Patch::is_modified(&undo.#member) }
+            } else if field.nullable.is_some() || *is_optional || field.ops.is_some() {
+                quote! { undo.#member.is_modified() }
+            } else {
+                quote! { undo.#member.is_some() }
+            };
+            let [check, check_patched] = if !has_validators {
+                Default::default()
+            } else if field.nullable.is_some() {
+                let check = quote! {
+                    if let Some(value) = #r {
+                        #validators
+                    }
+                };
+                [check.clone(), quote! { if #is_patched { #check } }]
+            } else {
+                let check = quote! {
+                    let value = #r;
+                    #validators
+                };
+                [quote! { { #check } }, quote! { if #is_patched { #check } }]
+            };
+            let (nested, nested_patched) = if *is_wrapped {
+                let nested = quote! { <#field_type as ::wopt::__private::Nested> };
+                (
+                    quote! { #nested::validate(#r).map_err(|e| e.nest(#name))?; },
+                    quote! {
+                        #nested::validate_patched(#r, &undo.#member).map_err(|e| e.nest(#name))?;
+                    },
+                )
+            } else if *is_optional {
+                (
+                    quote! { (#r).validate().map_err(|e| e.nest(#name))?; },
+                    quote! { (#r).validate_patched(&undo.#member).map_err(|e| e.nest(#name))?; },
+                )
+            } else {
+                Default::default()
+            };
+            code.checks.push(quote! {
+                #nested
+                #check
+            });
+            // required fields are never patched
+            if !*is_required {
+                code.checks_patched.push(quote! {
+                    #nested_patched
+                    #check_patched
+                });
+            }
+            code.checked.push(quote! { #member: #binding });
+        }
+
+        // compared with the replaced values (required fields are never patched)
+        if !*is_required {
+            let old = match access {
+                Access::Member => quote! { &#rhs },
+                Access::Binding => quote! { #rhs },
+            };
+            let mark = changed(field, n);
+            let on_change = field.on_change.iter().map(|path| quote! { #path(#r); });
+            let is_changed = if *is_optional {
+                let changes_member = match member {
+                    Member::Named(ident) => ident.clone(),
+                    Member::Unnamed(_) => format_ident!("_{name}"),
+                };
+                let changes_type = get_changes_type(field_type).unwrap();
+                code.changes_fields
+                    .push(quote! { pub #changes_member: #changes_type });
+                code.changes.push(quote! {
+                    changes.#changes_member = ::wopt::Track::changes(#r, #old);
+                });
+                code.track_bounds.push(parse_quote! {
+                    #field_type: ::wopt::Track<Opt = #field_type_opt, Changes = #changes_type>
+                });
+                quote! { !<#field_type as ::wopt::Track>::is_unchanged(&changes.#changes_member) }
+            } else if let Some(ops) = &field.ops {
+                // the replaced elements are restored onto a copy, compared with the collection
+                let patch = if ops.elem_opt().is_some() {
+                    "patch_nested"
+                } else {
+                    "patch"
+                };
+                let patch = Ident::new(patch, Span::call_site().into());
+                code.track_bounds.push(parse_quote! {
+                    #field_type: ::core::clone::Clone + ::core::cmp::PartialEq
+                });
+                code.track_bounds
+                    .push(parse_quote! { #field_type_opt: ::core::clone::Clone });
+                quote! {
+                    (#old).is_modified() && {
+                        let mut old = ::core::clone::Clone::clone(#r);
+                        ::core::clone::Clone::clone(#old).#patch(&mut old);
+                        old != *#r
+                    }
+                }
+            } else if let Some(inner) = &field.nullable {
+                code.track_bounds
+                    .push(parse_quote! { #inner: ::core::cmp::PartialEq });
+                quote! {
+                    match #old {
+                        ::wopt::Nullable::Set(old) => (#r).as_ref() != Some(old),
+                        ::wopt::Nullable::Clear => (#r).is_some(),
+                        ::wopt::Nullable::Unchanged => false,
+                    }
+                }
+            } else {
+                code.track_bounds
+                    .push(parse_quote! { #field_type: ::core::cmp::PartialEq });
+                quote! { matches!(#old, Some(old) if old != #r) }
+            };
+            code.changes.push(quote! {
+                if #is_changed {
+                    #mark
+                    #(#on_change)*
+                }
+            });
+        }
+
+        if *is_required {
+            code.fields
+                .push(quote! { #(#attrs)* #vis #field_name #field_type_opt });
+            code.take.push(quote! { #member: #place });
+            code.into.push(quote! { #member: #value });
+            code.undo.push(quote! { #member: #rhs });
+            code.full.push(quote! { let #binding = Some(#value); });
+            code.merge.push(quote! { #place = #rhs; });
+            code.diff
+                .push(quote! { #member: ::core::clone::Clone::clone(#other) });
+            code.diff_bounds
+                .push(parse_quote! { #field_type: ::core::clone::Clone });
+        } else if let Some(ops) = &field.ops {
+            // elements deriving `WithOpt` are patched per index (or key)
+            let [patch, patch_with_undo, into_full, diff] = if ops.elem_opt().is_some() {
+                [
+                    "patch_nested",
+                    "patch_nested_with_undo",
+                    "into_full_nested",
+                    "diff_nested",
+                ]
+            } else {
+                ["patch", "patch_with_undo", "into_full", "diff"]
+            }
+            .map(|method| Ident::new(method, Span::call_site().into()));
+
+            code.fields
+                .push(quote! { #(#attrs)* #vis #field_name #field_type_opt });
+            code.upts.push(quote! { #rhs.#patch(&mut #place); });
+            code.mods.push(quote! { #place.is_modified() });
+            code.take.push(quote! { #member: #place.take() });
+            let full_patch = ops.full_patch(value.clone());
+            code.into.push(quote! { #member: #full_patch });
+            code.full.push(quote! {
+                let #binding = #value.#into_full().or_else(|| {
+                    missing.push(#name);
+                    None
+                });
+            });
+            code.is_full.push(quote! { (#r).is_full() });
+            code.merge.push(quote! { #place.merge(#rhs); });
+            code.undo
+                .push(quote! { #member: #rhs.#patch_with_undo(&mut #place) });
+            if let OpsAttr::List { .. } = ops {
+                code.checks_ops.push(quote! {
+                    #rhs.check(#place.len()).map_err(|e| e.nest(#name))?;
+                });
+                code.unapplied.push(quote! { unapplied.#member = #rhs; });
+            } else if let OpsAttr::Map { .. } = ops {
+                code.checks_ops.push(quote! {
+                    #rhs.check(&#place).map_err(|e| e.nest(#name))?;
+                });
+            }
+            code.diff
+                .push(quote! { #member: <#field_type_opt>::#diff(#r, #other) });
+            code.eqs.push(quote! { #r == #other });
+            code.diff_bounds.extend(ops.diff_bounds());
+        } else if *is_wrapped {
+            code.has_optional = true;
+            code.has_composite |= field.is_composite();
+            code.fields
+                .push(quote! { #(#attrs)* #vis #field_name #field_type_opt });
+            code.upts
+                .push(quote! { if ::wopt::Patch::is_modified(&#rhs) {
+                    ::wopt::WithOpt::patch(&mut #place, &mut #rhs)
+                } });
+            if field.is_composite() {
+                code.mods
+                    .push(quote! { ::wopt::Patch::is_modified(&#place) });
+                code.take
+                    .push(quote! { #member: ::wopt::Patch::take(&mut #place) });
+            } else {
+                code.mods.push(quote! { #place.is_modified() });
+                code.take.push(quote! { #member: #place.take() });
+            }
+            code.into
+                .push(quote! { #member: ::wopt::WithOpt::into_opt(#value) });
+            code.full.push(quote! {
+                let #binding = <#field_type as ::wopt::__private::Nested>::try_from_opt(#value)
+                    .map_err(|fields| missing.nest(#name, fields))
+                    .ok();
+            });
+            code.is_full
+                .push(quote! { <#field_type as ::wopt::__private::Nested>::is_full(#r) });
+            // incomplete values of options are left unapplied
+            code.unapplied.push(quote! { unapplied.#member = #rhs; });
+            code.merge
+                .push(quote! { ::wopt::Patch::merge(&mut #place, #rhs); });
+            code.undo.push(quote! {
+                #member: ::wopt::WithOpt::patch_with_undo(&mut #place, &mut #rhs)
+            });
+            code.checks_ops.push(quote! {
+                <#field_type as ::wopt::__private::Nested>::check_patch(&#place, &#rhs)
+                    .map_err(|e| e.nest(#name))?;
+            });
+            code.diff
+                .push(quote! { #member: ::wopt::Diff::diff(#r, #other) });
+            code.diff_bounds
+                .push(parse_quote! { #field_type: ::wopt::Diff<Opt = #field_type_opt> });
+        } else if *is_optional {
+            code.has_optional = true;
+            code.fields
+                .push(quote! { #(#attrs)* #vis #field_name #field_type_opt });
+            code.upts.push(quote! { if #rhs.is_modified() {
+                #place.patch(&mut #rhs)
+            } });
+            code.mods.push(quote! { #place.is_modified() });
+            code.take.push(quote! { #member: #place.take() });
+            code.into.push(quote! { #member: #value.into_opt() });
+            code.full.push(quote! {
+                let #binding = #value
+                    .try_into_full()
+                    .map_err(|fields| missing.nest(#name, fields))
+                    .ok();
+            });
+            code.is_full.push(quote! { (#r).is_full() });
+            code.merge.push(quote! { #place.merge(#rhs); });
+            code.undo
+                .push(quote! { #member: #place.patch_with_undo(&mut #rhs) });
+            code.checks_ops.push(quote! {
+                #place.check_patch(&#rhs).map_err(|e| e.nest(#name))?;
+            });
+            code.diff
+                .push(quote! { #member: ::wopt::Diff::diff(#r, #other) });
+            code.diff_bounds
+                .push(parse_quote! { #field_type: ::wopt::Diff<Opt = #field_type_opt> });
+        } else {
+            let is_nullable = field.nullable.is_some();
+            let (wrap_value, wrap_other) = (
+                field.wrap(value.clone()),
+                field.wrap(quote! { ::core::clone::Clone::clone(#other) }),
+            );
+
+            if is_nullable {
+                code.fields
+                    .push(quote! { #(#attrs)* #vis #field_name #field_type_opt });
+                code.upts.push(quote! { #rhs.patch(&mut #place); });
+                code.mods.push(quote! { #place.is_modified() });
+                code.full.push(quote! {
+                    let #binding = #value.into_option().or_else(|| {
+                        missing.push(#name);
+                        None
+                    });
+                });
+                code.is_full.push(quote! { (#r).is_modified() });
+                code.merge.push(quote! { #place.merge(#rhs); });
+                code.undo
+                    .push(quote! { #member: #rhs.patch_with_undo(&mut #place) });
+            } else {
+                code.fields
+                    .push(quote! { #(#attrs)* #vis #field_name Option<#field_type_opt> });
+                code.upts.push(quote! { if let Some(val) = #rhs {
+                    #place = val
+                } });
+                code.mods.push(quote! { #place.is_some() });
+                code.full.push(quote! {
+                    let #binding = #value.or_else(|| {
+                        missing.push(#name);
+                        None
+                    });
+                });
+                code.is_full.push(quote! { (#r).is_some() });
+                code.merge.push(quote! { if let Some(val) = #rhs {
+                    #place = Some(val)
+                } });
+                code.undo.push(
+                    quote! { #member: #rhs.map(|val| ::core::mem::replace(&mut #place, val)) },
+                );
+            }
+            code.take.push(quote! { #member: #place.take() });
+            code.into.push(quote! { #member: #wrap_value });
+
+            let unchanged = if is_nullable {
+                quote! { ::wopt::Nullable::Unchanged }
+            } else {
+                quote! { None }
+            };
+            code.diff.push(quote! {
+                #member: if #r != #other {
+                    #wrap_other
+                } else {
+                    #unchanged
+                }
+            });
+            code.eqs.push(quote! { #r == #other });
+            code.diff_bounds
+                .push(parse_quote! { #field_type: ::core::cmp::PartialEq + ::core::clone::Clone });
+        }
+
+        n += 1;
+    }
+    code
+}
+
+/// Struct-level `#[wopt(...)]` options.
+struct Options {
+    derives: Vec<TokenStream2>,
+    /// Attributes of the optional type (`#[wopt(attr(...))]`).
+    attrs: Vec<TokenStream2>,
+    _no_serde: bool,
+    /// Function checking the value resulting from a patch.
+    validate: Option<Path>,
+    /// Function called with the value (and its changes) whenever a tracked patch changes it.
+    on_change: Option<Path>,
+    /// Visibility of the generated types (that of the original type if unspecified).
+    vis: Visibility,
+    /// Name of the optional type (`{Ident}Opt` if unspecified).
+    name: Option<Ident>,
+    #[cfg(feature = "bytemuck")]
+    id: Option<u32>,
+    /// How the identities are serialized (per crate with `WOPT_ID_WIDTH` if unspecified, `varint`
+    /// for hashed ones otherwise).
+    #[cfg(feature = "bytemuck")]
+    id_width: IdWidth,
+    /// Whether `id_width` was read from `WOPT_ID_WIDTH` (see `get_id_width_tracking`).
+    #[cfg(feature = "bytemuck")]
+    track_id_width: bool,
+    /// How the lengths of the fields are serialized, unless specified per field.
+    #[cfg(feature = "bytemuck")]
+    len: LenWidth,
+}
+
+fn get_options(input: &DeriveInput) -> syn::Result<Options> {
+    let (mut derives, mut attrs) = (Vec::new(), Vec::new());
+    let mut _no_serde = false;
+    let (mut validate, mut on_change) = (None, None);
+    let (mut vis, mut name) = (input.vis.clone(), None);
+    let mut specified = HashSet::new();
+    let mut errors = Errors::default();
+
+    // identity of this optional struct
+    #[cfg(feature = "bytemuck")]
+    let (mut id, mut id_width) = (None, None);
+
+    #[cfg(feature = "bytemuck")]
+    let mut len = LenWidth::default();
+
+    // process every `#[wopt(...)]` attribute, each with any number of metas
+    for attr in get_wopt_attrs(&input.attrs) {
+        let metas = match attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) {
+            Ok(metas) => metas,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        for meta in &metas {
+            // name-values are specified once, while derives and attrs are merged
+            if let Meta::NameValue(nv) = meta
+                && let Some(ident) = nv.path.get_ident()
+                && !specified.insert(ident.to_string())
+            {
+                errors.push(syn::Error::new_spanned(
+                    &nv.path,
+                    format!("`{ident}` is specified more than once."),
+                ));
+                continue;
+            }
+
+            match meta {
+                Meta::Path(path) => {
+                    if !path.is_ident("no_serde") {
+                        errors.push(syn::Error::new_spanned(
+                            path,
+                            "Only 'no_serde' path meta is supported.",
+                        ));
+                        continue;
+                    }
+                    _no_serde = true
+                }
+
+                Meta::List(list) if list.path.is_ident("attr") => {
+                    match list.parse_args_with(get_attr_metas) {
+                        Ok(metas) => attrs.extend(metas),
+                        Err(e) => errors.push(e),
+                    }
+                }
+
+                Meta::List(list) => {
+                    if !list.path.is_ident("derive") {
+                        errors.push(syn::Error::new_spanned(
+                            &list.path,
+                            "Only 'derive' and 'attr' list meta are supported.",
+                        ));
+                        continue;
+                    }
+
+                    let result = list.parse_nested_meta(|a| {
+                        if let Some(ident) = a.path.get_ident() {
+                            derives.push(quote! { #ident });
+                        }
+                        Ok(())
+                    });
+                    if let Err(e) = result {
+                        errors.push(e);
+                    }
+                }
+                Meta::NameValue(nv) => {
+                    if nv.path.is_ident("id") {
+                        #[cfg(not(feature = "bytemuck"))]
+                        errors.push(syn::Error::new_spanned(
+                            &nv.path,
+                            "Enable the `bytemuck` feature to use the `id` attribute.",
+                        ));
+
+                        #[cfg(feature = "bytemuck")]
+                        match &nv.value {
+                            Expr::Lit(ExprLit {
+                                lit: Lit::Int(v), ..
+                            }) => id = Some(v.clone()),
+                            value => errors
+                                .push(syn::Error::new_spanned(value, "Expected integer literal.")),
+                        }
+                        continue;
+                    }
+                    if nv.path.is_ident("id_width") {
+                        #[cfg(not(feature = "bytemuck"))]
+                        errors.push(syn::Error::new_spanned(
+                            &nv.path,
+                            "Enable the `bytemuck` feature to use the `id_width` attribute.",
+                        ));
+
+                        #[cfg(feature = "bytemuck")]
+                        match &nv.value {
+                            Expr::Lit(ExprLit {
+                                lit: Lit::Str(s), ..
+                            }) => match IdWidth::from_name(&s.value()) {
+                                Some(width) => id_width = Some(width),
+                                None => errors.push(syn::Error::new_spanned(
+                                    s,
+                                    "Expected `u8`, `u16` or `varint`.",
+                                )),
+                            },
+                            value => errors
+                                .push(syn::Error::new_spanned(value, "Expected string literal.")),
+                        }
+                        continue;
+                    }
+                    if nv.path.is_ident("len") {
+                        #[cfg(not(feature = "bytemuck"))]
+                        errors.push(syn::Error::new_spanned(
+                            &nv.path,
+                            "Enable the `bytemuck` feature to use the `len` attribute.",
+                        ));
+
+                        #[cfg(feature = "bytemuck")]
+                        match &nv.value {
+                            Expr::Lit(ExprLit {
+                                lit: Lit::Str(s), ..
+                            }) => match LenWidth::from_name(&s.value()) {
+                                Some(width) => len = width,
+                                None => errors.push(syn::Error::new_spanned(
+                                    s,
+                                    "Expected `u8`, `u16`, `u32` or `varint`.",
+                                )),
+                            },
+                            value => errors
+                                .push(syn::Error::new_spanned(value, "Expected string literal.")),
+                        }
+                        continue;
+                    }
+                    if nv.path.is_ident("validate") || nv.path.is_ident("on_change") {
+                        let hook = if nv.path.is_ident("validate") {
+                            &mut validate
+                        } else {
+                            &mut on_change
+                        };
+                        match &nv.value {
+                            Expr::Lit(ExprLit {
+                                lit: Lit::Str(s), ..
+                            }) => match s.parse() {
+                                Ok(path) => *hook = Some(path),
+                                Err(e) => errors.push(e),
+                            },
+                            value => errors
+                                .push(syn::Error::new_spanned(value, "Expected string literal.")),
+                        }
+                        continue;
+                    }
+                    if nv.path.is_ident("vis") || nv.path.is_ident("name") {
+                        match &nv.value {
+                            Expr::Lit(ExprLit {
+                                lit: Lit::Str(s), ..
+                            }) => {
+                                let result = if nv.path.is_ident("vis") {
+                                    s.parse().map(|value| vis = value)
+                                } else {
+                                    s.parse().map(|value| name = Some(value))
+                                };
+                                if let Err(e) = result {
+                                    errors.push(e);
+                                }
+                            }
+                            value => errors
+                                .push(syn::Error::new_spanned(value, "Expected string literal.")),
+                        }
+                        continue;
+                    }
+                    if nv.path.is_ident("bf") {
+                        #[cfg(not(feature = "bf"))]
+                        errors.push(syn::Error::new_spanned(
+                            &nv.path,
+                            "Enable the `bf` feature to use brainfuck.",
+                        ));
+
+                        #[cfg(feature = "bf")]
+                        match &nv.value {
+                            Expr::Lit(ExprLit {
+                                lit: Lit::Str(s), ..
+                            }) => {
+                                let s = bf2s::bf_to_str(&s.value());
+                                derives.extend(s.split_whitespace().map(|p| {
+                                    let p = Ident::new(p, Span::call_site().into());
+                                    quote! { #p }
+                                }));
+                            }
+                            value => errors
+                                .push(syn::Error::new_spanned(value, "Expected string literal.")),
+                        }
+                        continue;
+                    }
+                    errors.push(syn::Error::new_spanned(&nv.path, "Unsupported attribute."));
+                }
+            }
+        }
+    }
+    // the width of the crate applies unless specified
+    #[cfg(feature = "bytemuck")]
+    let track_id_width = id_width.is_none();
+
+    // hashed identities are only spread widely enough among varints
+    #[cfg(feature = "bytemuck")]
+    let id_width = match id_width {
+        Some(width) => width,
+        None => match std::env::var("WOPT_ID_WIDTH") {
+            Ok(name) => IdWidth::from_name(&name).unwrap_or_else(|| {
+                errors.push(syn::Error::new_spanned(
+                    &input.ident,
+                    format!("Unknown `WOPT_ID_WIDTH` `{name}` (expected `u8`, `u16` or `varint`)."),
+                ));
+                IdWidth::default()
+            }),
+            Err(_) if id.is_none() => IdWidth::Varint,
+            Err(_) => IdWidth::default(),
+        },
+    };
+
+    #[cfg(feature = "bytemuck")]
+    if id.is_none() && !matches!(id_width, IdWidth::Varint) {
+        errors.push(syn::Error::new_spanned(
+            &input.ident,
+            format!(
+                "`{}` needs an explicit `#[wopt(id = ...)]` with `id_width = \"{}\"` (only `varint` identities are hashed).",
+                input.ident,
+                id_width.name(),
+            ),
+        ));
+    }
+
+    #[cfg(feature = "bytemuck")]
+    let id = id.and_then(|v| match get_id(&v, id_width) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            errors.push(e);
+            None
+        }
+    });
+
+    errors.finish()?;
+    Ok(Options {
+        derives,
+        attrs,
+        _no_serde,
+        validate,
+        on_change,
+        vis,
+        name,
+        #[cfg(feature = "bytemuck")]
+        id,
+        #[cfg(feature = "bytemuck")]
+        id_width,
+        #[cfg(feature = "bytemuck")]
+        track_id_width,
+        #[cfg(feature = "bytemuck")]
+        len,
+    })
+}
+
+/// Has Cargo track `WOPT_ID_WIDTH` if the width of the identities was read from it, so that
+/// changing it rebuilds the crate (deriving again) rather than leaving stale widths.
+#[cfg(feature = "bytemuck")]
+fn get_id_width_tracking(tracked: bool) -> TokenStream2 {
+    if !tracked {
+        return quote! {};
+    }
+    quote! {
+        const _: Option<&str> = option_env!("WOPT_ID_WIDTH");
+    }
+}
+
+/// Parses the value of `#[wopt(id = ...)]`, leaving room for the identity of the optional type.
+#[cfg(feature = "bytemuck")]
+fn get_id(v: &LitInt, width: IdWidth) -> syn::Result<u32> {
+    let max = width.max();
+    match v.base10_parse::<u32>() {
+        Ok(value) if value <= max => Ok(value),
+        _ => Err(syn::Error::new_spanned(
+            v,
+            format!(
+                "Value too large (max: {max} with `id_width = \"{}\"`).",
+                width.name()
+            ),
+        )),
+    }
+}
+
+/// How the identity of a type is serialized (see `::wopt::IdWidth`).
+#[cfg(feature = "bytemuck")]
+#[derive(Clone, Copy, Default)]
+enum IdWidth {
+    #[default]
+    U8,
+    U16,
+    Varint,
+}
+
+#[cfg(feature = "bytemuck")]
+impl IdWidth {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "u8" => Some(Self::U8),
+            "u16" => Some(Self::U16),
+            "varint" => Some(Self::Varint),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::Varint => "varint",
+        }
+    }
+
+    /// The largest specified identity, leaving room for that of the optional type.
+    fn max(self) -> u32 {
+        match self {
+            Self::U8 => 0x7f,
+            Self::U16 => 0x7fff,
+            Self::Varint => 0x7fff_ffff,
+        }
+    }
+
+    /// The identities of the original and optional types, given `id`: the high bit is set for the
+    /// latter, or, for varints, the low bit (so that small identities stay a single byte).
+    fn ids(self, id: u32) -> [Id; 2] {
+        let [og, opt] = match self {
+            Self::U8 => [id, id | 0x80],
+            Self::U16 => [id, id | 0x8000],
+            Self::Varint => [id << 1, id << 1 | 1],
+        };
+        [og, opt].map(|value| Id { value, width: self })
+    }
+
+    fn path(self) -> TokenStream2 {
+        match self {
+            Self::U8 => quote! { ::wopt::IdWidth::U8 },
+            Self::U16 => quote! { ::wopt::IdWidth::U16 },
+            Self::Varint => quote! { ::wopt::IdWidth::Varint },
+        }
+    }
+}
+
+/// How the lengths of variable-size fields are serialized (see `::wopt::LenWidth`).
+#[cfg(feature = "bytemuck")]
+#[derive(Clone, Copy, Default)]
+enum LenWidth {
+    U8,
+    #[default]
+    U16,
+    U32,
+    Varint,
+}
+
+#[cfg(feature = "bytemuck")]
+impl LenWidth {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "u8" => Some(Self::U8),
+            "u16" => Some(Self::U16),
+            "u32" => Some(Self::U32),
+            "varint" => Some(Self::Varint),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::Varint => "varint",
+        }
+    }
+
+    fn path(self) -> TokenStream2 {
+        match self {
+            Self::U8 => quote! { ::wopt::LenWidth::U8 },
+            Self::U16 => quote! { ::wopt::LenWidth::U16 },
+            Self::U32 => quote! { ::wopt::LenWidth::U32 },
+            Self::Varint => quote! { ::wopt::LenWidth::Varint },
+        }
+    }
+}
+
+/// The identity of a type, serialized with `width`.
+#[cfg(feature = "bytemuck")]
+#[derive(Clone, Copy)]
+struct Id {
+    value: u32,
+    width: IdWidth,
+}
+
+#[cfg(feature = "bytemuck")]
+impl Id {
+    /// The identity as a literal of the type of its width (`u32` for varints).
+    fn lit(self) -> LitInt {
+        let ty = match self.width {
+            IdWidth::U8 => "u8",
+            IdWidth::U16 => "u16",
+            IdWidth::Varint => "u32",
+        };
+        LitInt::new(&format!("{}{ty}", self.value), Span::call_site().into())
+    }
+
+    /// The type of [`Self::lit`].
+    fn ty(self) -> Ident {
+        Ident::new(self.lit().suffix(), Span::call_site().into())
+    }
+
+    /// The identity as a `u32` literal (e.g., for `WireEncode::ID`).
+    fn lit_u32(self) -> LitInt {
+        LitInt::new(&format!("{}u32", self.value), Span::call_site().into())
+    }
+
+    /// A macro exported at the root of the crate (hidden from its docs) and named after the
+    /// identity, so that deriving another type with the same one fails to compile ("defined
+    /// multiple times"), pointing at both types (`ident`).
+    fn marker(self, ident: &Ident) -> TokenStream2 {
+        let span = ident.span();
+        let name = format_ident!(
+            "__wopt_id_{}_{}",
+            self.width.name(),
+            self.value,
+            span = span
+        );
+        quote::quote_spanned! {span=>
+            #[doc(hidden)]
+            #[allow(non_local_definitions)] // types may be derived in functions
+            #[macro_export]
+            macro_rules! #name {
+                () => {};
+            }
+        }
+    }
+
+    /// The serialized identity.
+    fn bytes(self) -> Vec<u8> {
+        match self.width {
+            IdWidth::U8 => vec![self.value as u8],
+            IdWidth::U16 => (self.value as u16).to_le_bytes().to_vec(),
+            IdWidth::Varint => {
+                let (mut value, mut bytes) = (self.value, Vec::new());
+                while value >= 0x80 {
+                    bytes.push(value as u8 | 0x80);
+                    value >>= 7;
+                }
+                bytes.push(value as u8);
+                bytes
+            }
+        }
+    }
+}
+
+#[proc_macro_derive(WithOpt, attributes(id, wopt))]
+pub fn wopt_derive(input: TokenStream) -> TokenStream {
+    // parse the input tokens into a syntax tree
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let options = get_options(&input);
+
+    match &input.data {
+        Data::Struct(data) => expand_struct(&input, data, options),
+        Data::Enum(data) => expand_enum(&input, data, options),
+        Data::Union(data) => Err(syn::Error::new_spanned(
+            data.union_token,
+            "Unions are not supported.",
+        )),
+    }
+    .unwrap_or_else(syn::Error::into_compile_error)
+    .into()
+}
+
+/// The enum of a `registry!`, with a variant for each registered type (named after it).
+#[cfg(feature = "bytemuck")]
+struct Registry {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    ident: Ident,
+    types: Punctuated<Type, Token![,]>,
+}
+
+#[cfg(feature = "bytemuck")]
+impl syn::parse::Parse for Registry {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![enum]>()?;
+        let ident = input.parse()?;
+        let content;
+        syn::braced!(content in input);
+        let types = content.parse_terminated(<Type as syn::parse::Parse>::parse, Token![,])?;
+        Ok(Self {
+            attrs,
+            vis,
+            ident,
+            types,
+        })
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+#[proc_macro]
+pub fn registry(input: TokenStream) -> TokenStream {
+    let registry = parse_macro_input!(input as Registry);
+    expand_registry(&registry)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[cfg(feature = "bytemuck")]
+fn expand_registry(registry: &Registry) -> syn::Result<TokenStream2> {
+    let Registry {
+        attrs,
+        vis,
+        ident,
+        types,
+    } = registry;
+
+    // each type is registered along with its optional type
+    let mut variants = Vec::new();
+    let mut names = HashSet::new();
+    let mut errors = Errors::default();
+    for ty in types {
+        let Type::Path(TypePath { path, .. }) = ty else {
+            errors.push(syn::Error::new_spanned(
+                ty,
+                "Only path types can be registered.",
+            ));
+            continue;
+        };
+        let name = &path.segments.last().unwrap().ident;
+        if !names.insert(name.to_string()) {
+            errors.push(syn::Error::new_spanned(
+                ty,
+                format!("`{name}` is registered more than once."),
+            ));
+            continue;
+        }
+        let ty_opt: Type = parse_quote! { <#ty as ::wopt::WithOpt>::Opt };
+        variants.push((name.clone(), ty.clone()));
+        variants.push((format_ident!("{name}Opt"), ty_opt));
+    }
+    if types.is_empty() {
+        errors.push(syn::Error::new_spanned(
+            ident,
+            "At least one type must be registered.",
+        ));
+    }
+    errors.finish()?;
+
+    let (names, types): (Vec<_>, Vec<_>) = variants.into_iter().unzip();
+    let width_messages = types.iter().map(|ty| {
+        format!(
+            "`{}` has a different ID width than the first registered type (see `#[wopt(id_width = ...)]`).",
+            ty.to_token_stream()
+        )
+    });
+    let first = &types[0];
+    let docs = types
+        .iter()
+        .map(|ty| format!("A serialized `{}`.", ty.to_token_stream()));
+    Ok(quote! {
+        #(#attrs)*
+        #vis enum #ident {
+            #(
+                #[doc = #docs]
+                #names(#types),
+            )*
+        }
+
+        ::wopt::check_ids!(#(#types),*);
+
+        // the leading identity is read with the same width for every type
+        const _: () = ::wopt::__private::check_id_widths(
+            &[#(<#types as ::wopt::WireEncode>::ID_WIDTH),*],
+            &[#(#width_messages),*],
+        );
+
+        impl #ident {
+            /// Decodes the value of the type identified by the leading identity, rejecting invalid
+            /// bytes (see `WireEncode::try_deserialize`).
+            pub fn decode(bytes: &[u8]) -> Result<Self, ::wopt::DecodeError> {
+                if bytes.is_empty() {
+                    return Err(::wopt::DecodeError::new(0, "missing ID"));
+                }
+                let (id, _) = <#first as ::wopt::WireEncode>::ID_WIDTH.decode(bytes)?;
+                match id {
+                    #(
+                        id if id == <#types as ::wopt::WireEncode>::ID => {
+                            <#types as ::wopt::WireEncode>::try_deserialize(bytes).map(Self::#names)
+                        }
+                    )*
+                    id => Err(::wopt::DecodeError::new(0, format!("unknown ID `{id}`"))),
+                }
+            }
+
+            /// Encodes the value, starting with the identity of its type, panicking if a length
+            /// overflows its prefix (see `try_encode`).
+            pub fn encode(&self) -> Vec<u8> {
+                match self {
+                    #(Self::#names(value) => ::wopt::WireEncode::serialize(value),)*
+                }
+            }
+
+            /// Encodes the value like `encode`, rejecting any length overflowing its prefix (see
+            /// `WireEncode::try_serialize`).
+            pub fn try_encode(&self) -> Result<Vec<u8>, ::wopt::EncodeError> {
+                match self {
+                    #(Self::#names(value) => ::wopt::WireEncode::try_serialize(value),)*
+                }
+            }
+
+            /// The identity of the type of the value.
+            pub const fn id(&self) -> u32 {
+                match self {
+                    #(Self::#names(_) => <#types as ::wopt::WireEncode>::ID,)*
+                }
+            }
+        }
+
+        #(
+            impl ::core::convert::From<#types> for #ident {
+                fn from(value: #types) -> Self {
+                    Self::#names(value)
+                }
+            }
+        )*
+    })
+}
+
+/// Extends the where-clause of `generics` with the bounds required for (de)serialization.
+#[cfg(feature = "bytemuck")]
+fn get_serde_where(
+    generics: &Generics,
+    pod_types: &[Type],
+    opt_types: &[&Type],
+) -> [WhereClause; 2] {
+    let mut serde_generics = generics.clone();
+    let serde_where = serde_generics.make_where_clause();
+
+    // generic parameters only need to be `Pod` for (de)serialization
+    if !generics.params.is_empty() {
+        serde_where
+            .predicates
+            .extend(pod_types.iter().map(|ty| -> WherePredicate {
+                parse_quote! { #ty: ::wopt::__private::bytemuck::Pod }
+            }));
+    }
+    let mut serde_where_opt = serde_where.clone();
+    if !generics.params.is_empty() {
+        serde_where_opt
+            .predicates
+            .extend(opt_types.iter().map(|ty| -> WherePredicate {
+                parse_quote! { #ty: ::core::default::Default }
+            }));
+    }
+    [serde_where.clone(), serde_where_opt]
+}
+
+/// The smallest unsigned integer with a bit for each of the `count` fields.
+fn get_bits(count: usize, name: &Ident) -> syn::Result<Ident> {
+    let bits = match count {
+        0..=8 => "u8",
+        9..=16 => "u16",
+        17..=32 => "u32",
+        33..=64 => "u64",
+        65..=128 => "u128",
+        _ => {
+            return Err(syn::Error::new_spanned(name, "Too many fields (max: 128)."));
+        }
+    };
+    Ok(Ident::new(bits, Span::call_site().into()))
+}
+
+/// Closures (de)serializing a value of `ty` (through its own methods if `is_serde`), from and to
+/// the `tail` of the bytes.
+#[cfg(feature = "bytemuck")]
+fn get_codec(ty: &Type, is_serde: bool) -> (TokenStream2, TokenStream2) {
+    if is_serde {
+        (
+            quote! { |val, data| {
+                data.extend_from_slice(&::wopt::__private::ser_fields(val)?);
+                Ok(())
+            } },
+            quote! { |bytes, t| <#ty>::deserialize_from(bytes, t) },
+        )
+    } else {
+        (
+            quote! { |val, data| {
+                data.extend_from_slice(::wopt::__private::bytemuck::bytes_of(val));
+                Ok(())
+            } },
+            quote! { |bytes, t| ::wopt::__private::read_pod(bytes, t) },
+        )
+    }
+}
+
+/// Closures (de)serializing the optional type of the elements, if patched per index (or key).
+#[cfg(feature = "bytemuck")]
+fn get_codec_opt(ty: Option<&Type>) -> [TokenStream2; 2] {
+    match ty {
+        Some(ty) => [
+            quote! { |val, data| {
+                data.extend_from_slice(&::wopt::__private::ser_fields(val)?);
+                Ok(())
+            } },
+            quote! { |bytes, t| <#ty>::deserialize_from(bytes, t) },
+        ],
+        None => [
+            quote! { |val, _| match *val {} },
+            quote! { |_, t: &mut usize| Err(::wopt::DecodeError::new(*t, "unpatchable elements")) },
+        ],
+    }
+}
+
+/// Name of the bitmask flag of the `n`-th (non-skipped) field.
+fn get_unit_name(field: &FieldAttr, n: usize) -> Ident {
+    match &field.member {
+        Member::Named(ident) => Ident::new(
+            &convert_case::Casing::to_case(&ident.unraw().to_string(), convert_case::Case::Pascal),
+            Span::call_site().into(),
+        ),
+        Member::Unnamed(_) => Ident::new(&format!("F{n}"), Span::call_site().into()),
+    }
+}
+
+/// Implements `WithOpt` (and `Nested`) for the original type and `Patch` for the optional type.
+fn get_trait_impls(input: &DeriveInput, opt_name: &Ident) -> TokenStream2 {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::wopt::WithOpt for #name #ty_generics #where_clause {
+            type Opt = #opt_name #ty_generics;
+
+            fn patch(&mut self, rhs: &mut Self::Opt) {
+                Self::patch(self, rhs)
+            }
+
+            fn patch_with_undo(&mut self, rhs: &mut Self::Opt) -> Self::Opt {
+                Self::patch_with_undo(self, rhs)
+            }
+
+            fn into_opt(self) -> Self::Opt {
+                Self::into_opt(self)
+            }
+        }
+
+        impl #impl_generics ::wopt::Patch for #opt_name #ty_generics #where_clause {
+            fn is_modified(&self) -> bool {
+                Self::is_modified(self)
+            }
+
+            fn take(&mut self) -> Self {
+                Self::take(self)
+            }
+
+            fn merge(&mut self, rhs: Self) {
+                Self::merge(self, rhs)
+            }
+        }
+
+        impl #impl_generics ::wopt::__private::Nested for #name #ty_generics #where_clause {
+            fn try_from_opt(opt: Self::Opt) -> Result<Self, ::wopt::MissingFields> {
+                opt.try_into_full()
+            }
+
+            fn is_full(opt: &Self::Opt) -> bool {
+                opt.is_full()
+            }
+
+            fn validate(&self) -> Result<(), ::wopt::PatchError> {
+                Self::validate(self)
+            }
+
+            fn validate_patched(&self, undo: &Self::Opt) -> Result<(), ::wopt::PatchError> {
+                Self::validate_patched(self, undo)
+            }
+
+            fn check_patch(&self, rhs: &Self::Opt) -> Result<(), ::wopt::PatchError> {
+                Self::check_patch(self, rhs)
+            }
+        }
+
+        impl #impl_generics ::core::convert::TryFrom<#opt_name #ty_generics> for #name #ty_generics #where_clause {
+            type Error = ::wopt::MissingFields;
+
+            fn try_from(opt: #opt_name #ty_generics) -> Result<Self, Self::Error> {
+                opt.try_into_full()
+            }
+        }
+
+        impl #impl_generics ::core::ops::BitOr for #opt_name #ty_generics #where_clause {
+            type Output = Self;
+
+            fn bitor(mut self, rhs: Self) -> Self {
+                self.merge(rhs);
+                self
+            }
+        }
+
+        impl #impl_generics ::core::ops::BitOrAssign for #opt_name #ty_generics #where_clause {
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.merge(rhs)
+            }
+        }
+    }
+}
+
+/// The `fields_name` bitflags of the type `name`, with a flag for each of `units` (named after the
+/// field), sized to fit `len` bits (e.g., the clear bits of nullable fields following them in the
+/// serialized bitmask).
+fn get_fields_impl(
+    units: &[(Ident, String)],
+    len: usize,
+    name: &Ident,
+    fields_name: &Ident,
+    vis: &Visibility,
+) -> syn::Result<TokenStream2> {
+    let flags = units.iter().enumerate().map(|(n, (unit_name, _))| {
+        quote! { const #unit_name = 1 << #n; }
+    });
+    let names = units.iter().map(|(_, name)| name);
+    let (bits, count) = (get_bits(len, name)?, units.len());
+    let doc = format!("Fields of [`{name}`], e.g., those modified by a patch.");
+    Ok(quote! {
+        ::wopt::__private::bitflags::bitflags! {
+            #[doc = #doc]
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            #vis struct #fields_name: #bits {
+                #(#flags)*
+            }
+        }
+
+        impl #fields_name {
+            /// Names of the fields, in the order of their flags.
+            pub const FIELD_NAMES: [&'static str; #count] = [#(#names),*];
+
+            /// Names of the set fields.
+            pub fn names(self) -> impl Iterator<Item = &'static str> {
+                Self::FIELD_NAMES
+                    .into_iter()
+                    .enumerate()
+                    .filter(move |(n, _)| self.bits() & (1 << n) != 0)
+                    .map(|(_, name)| name)
+            }
+        }
+    })
+}
+
+/// Methods of the optional struct, which fluent setters can't be named after.
+const OPT_METHODS: [&str; 13] = [
+    "is_modified",
+    "take",
+    "merge",
+    "modified_fields",
+    "retain",
+    "remove",
+    "try_into_full",
+    "serialize",
+    "deserialize",
+    "deserialize_with",
+    "deserialize_from",
+    "deserialize_spanned",
+    "try_deserialize",
+];
+
+/// Methods of the traits implemented by the optional struct, which fluent setters would shadow
+/// (e.g., `ExampleOpt::default()`), along with their traits.
+const TRAIT_METHODS: [(&str, &str); 9] = [
+    ("default", "Default"),
+    ("clone", "Clone"),
+    ("clone_from", "Clone"),
+    ("eq", "PartialEq"),
+    ("ne", "PartialEq"),
+    ("fmt", "Debug"),
+    ("hash", "Hash"),
+    ("bitor", "BitOr"),
+    ("bitor_assign", "BitOrAssign"),
+];
+
+/// Fluent setters and accessors of the optional struct's fields (e.g., `a`, `set_a`, `clear_a`,
+/// `is_a_set` and `get_a`, or `_0`, `set_0`, ... for tuple structs).
+fn get_accessors(info: &FieldAttrs) -> syn::Result<Vec<TokenStream2>> {
+    let mut accessors = Vec::new();
+    for field in info.attrs.iter().filter(|field| !field.is_skipped) {
+        let FieldAttr {
+            member,
+            field_type,
+            field_type_opt,
+            ..
+        } = field;
+        let name = field.name();
+        if let (Member::Named(ident), Some((_, trait_name))) = (
+            member,
+            TRAIT_METHODS.iter().find(|(method, _)| *method == name),
+        ) {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("`{name}` is reserved for `{trait_name}` on the optional struct."),
+            ));
+        }
+        let [set, clear, is_set, get] = ["set_{}", "clear_{}", "is_{}_set", "get_{}"]
+            .map(|method| Ident::new(&method.replace("{}", &name), Span::call_site().into()));
+
+        if !OPT_METHODS.contains(&name.as_str()) {
+            let fluent = match member {
+                Member::Named(ident) => ident.clone(),
+                Member::Unnamed(_) => format_ident!("_{name}"),
+            };
+            accessors.push(if field.is_optional || field.ops.is_some() {
+                quote! {
+                    pub fn #fluent(mut self, f: impl FnOnce(#field_type_opt) -> #field_type_opt) -> Self {
+                        self.#member = f(self.#member);
+                        self
+                    }
+                }
+            } else if field.is_required {
+                quote! {
+                    pub fn #fluent(mut self, value: #field_type) -> Self {
+                        self.#member = value;
+                        self
+                    }
+                }
+            } else {
+                let value = field.wrap(quote! { value });
+                quote! {
+                    pub fn #fluent(mut self, value: #field_type) -> Self {
+                        self.#member = #value;
+                        self
+                    }
+                }
+            });
+        }
+
+        accessors.push(if field.is_optional || field.ops.is_some() {
+            quote! {
+                pub fn #set(&mut self, value: #field_type_opt) -> &mut Self {
+                    self.#member = value;
+                    self
+                }
+
+                pub fn #clear(&mut self) -> &mut Self {
+                    ::wopt::Patch::take(&mut self.#member);
+                    self
+                }
+
+                pub fn #is_set(&self) -> bool {
+                    ::wopt::Patch::is_modified(&self.#member)
+                }
+
+                pub const fn #get(&self) -> &#field_type_opt {
+                    &self.#member
+                }
+            }
+        } else if field.is_required {
+            quote! {
+                pub fn #set(&mut self, value: #field_type) -> &mut Self {
+                    self.#member = value;
+                    self
+                }
+
+                pub const fn #get(&self) -> &#field_type {
+                    &self.#member
+                }
+            }
+        } else if let Some(inner) = &field.nullable {
+            quote! {
+                pub fn #set(&mut self, value: #field_type) -> &mut Self {
+                    self.#member = ::wopt::Nullable::from(value);
+                    self
+                }
+
+                pub fn #clear(&mut self) -> &mut Self {
+                    self.#member = ::wopt::Nullable::Unchanged;
+                    self
+                }
+
+                pub const fn #is_set(&self) -> bool {
+                    self.#member.is_modified()
+                }
+
+                pub const fn #get(&self) -> ::wopt::Nullable<&#inner> {
+                    self.#member.as_ref()
+                }
+            }
+        } else {
+            quote! {
+                pub fn #set(&mut self, value: #field_type) -> &mut Self {
+                    self.#member = Some(value);
+                    self
+                }
+
+                pub fn #clear(&mut self) -> &mut Self {
+                    self.#member = None;
+                    self
+                }
+
+                pub const fn #is_set(&self) -> bool {
+                    self.#member.is_some()
+                }
+
+                pub const fn #get(&self) -> Option<&#field_type> {
+                    self.#member.as_ref()
+                }
+            }
+        });
+    }
+    Ok(accessors)
+}
+
+/// The body of `try_into_full`, constructing `path` once every field of `info` is set.
+fn get_full_body(
+    info: &FieldAttrs,
+    full: &[TokenStream2],
+    new_full: &[TokenStream2],
+    path: TokenStream2,
+) -> TokenStream2 {
+    let bindings = info
+        .attrs
+        .iter()
+        .filter(|field| !field.is_skipped)
+        .map(|field| &field.binding);
+    let bindings_set = bindings.clone();
+    quote! {
+        #(#full)*
+        match (#(#bindings,)*) {
+            (#(Some(#bindings_set),)*) => Ok(#path { #(#new_full),* }),
+            _ => Err(missing),
+        }
+    }
+}
+
+/// Declares the `missing` fields of `try_into_full`, given whether any of them can be.
+fn get_missing(is_refutable: bool) -> TokenStream2 {
+    if is_refutable {
+        quote! { let mut missing = ::wopt::MissingFields::new(); }
+    } else {
+        quote! { let missing = ::wopt::MissingFields::new(); }
+    }
+}
+
+/// Extends the where-clause of `generics` with the bounds required to compute differences.
+fn get_diff_where(generics: &Generics, diff_bounds: &[WherePredicate]) -> WhereClause {
+    let mut diff_generics = generics.clone();
+    let diff_where = diff_generics.make_where_clause();
+
+    // higher-ranked bounds are only checked once used, so field types lacking
+    // them (e.g., `PartialEq`) just don't get a `diff` rather than an error
+    diff_where
+        .predicates
+        .extend(diff_bounds.iter().map(|bound| -> WherePredicate {
+            parse_quote! { for<'__wopt> #bound }
+        }));
+    diff_where.clone()
+}
+
+/// The validation methods of the original type, given the statements checking its fields, those
+/// checking the fields patched by `undo` (otherwise, the whole value is checked if patched), and
+/// those checking that `rhs` applies to its fields.
+fn get_validate(
+    checks: TokenStream2,
+    checks_patched: Option<TokenStream2>,
+    checks_ops: TokenStream2,
+    validate: Option<&Path>,
+    opt_ty: &TokenStream2,
+) -> TokenStream2 {
+    let validate = validate.map(|path| {
+        quote! { #path(self).map_err(::wopt::PatchError::new)?; }
+    });
+    let validate_patched = match checks_patched {
+        Some(checks_patched) => {
+            let validate = validate.as_ref().map(|validate| {
+                quote! {
+                    if undo.is_modified() {
+                        #validate
+                    }
+                }
+            });
+            quote! {
+                #checks_patched
+                #validate
+                Ok(())
+            }
+        }
+        None => quote! {
+            if undo.is_modified() {
+                self.validate()
+            } else {
+                Ok(())
+            }
+        },
+    };
+    quote! {
+        /// Checks every field, then the whole value, with their validators.
+        pub fn validate(&self) -> Result<(), ::wopt::PatchError> {
+            #checks
+            #validate
+            Ok(())
+        }
+
+        /// Checks the fields patched by the patch returning `undo`, then the whole value (if patched).
+        #[doc(hidden)]
+        #[allow(unused_variables)]
+        pub fn validate_patched(&self, undo: &#opt_ty) -> Result<(), ::wopt::PatchError> {
+            #validate_patched
+        }
+
+        /// Checks that `rhs` applies to `self` (i.e., that its list operations are within bounds,
+        /// and that its map operations patch present keys).
+        #[doc(hidden)]
+        #[allow(unused_variables)]
+        pub fn check_patch(&self, rhs: &#opt_ty) -> Result<(), ::wopt::PatchError> {
+            #checks_ops
+            Ok(())
+        }
+
+        /// Patches `self` like `patch`, given every patched field (and the whole value) is valid,
+        /// and every list (or map) operation applies.
+        ///
+        /// Otherwise, `self` is left unchanged, as is `rhs` (or a patch equivalent to it).
+        pub fn try_patch(&mut self, rhs: &mut #opt_ty) -> Result<(), ::wopt::PatchError> {
+            self.check_patch(rhs)?;
+            let mut undo = self.patch_with_undo(rhs);
+            if let Err(e) = self.validate_patched(&undo) {
+                // reverting the patch restores it
+                *rhs = self.patch_with_undo(&mut undo);
+                return Err(e);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Implements `diff` for the original type (and `Diff` through it), given its `body`.
+fn get_diff_impl(
+    input: &DeriveInput,
+    opt_name: &Ident,
+    diff_where: &WhereClause,
+    body: TokenStream2,
+) -> TokenStream2 {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics #name #ty_generics #diff_where {
+            pub fn diff(&self, other: &Self) -> #opt_name #ty_generics {
+                #body
+            }
+        }
+
+        impl #impl_generics ::wopt::Diff for #name #ty_generics #diff_where {
+            fn diff(&self, other: &Self) -> Self::Opt {
+                Self::diff(self, other)
+            }
+        }
+    }
+}
+
+/// Implements `patch_tracked` for the original type (and `Track` through it), given the body of
+/// `changes` (declaring `changes` from `undo`) and the callback of the whole value.
+fn get_track_impl(
+    input: &DeriveInput,
+    opt_name: &Ident,
+    changes_name: &Ident,
+    track_where: &WhereClause,
+    body: TokenStream2,
+    on_change: Option<&Path>,
+) -> TokenStream2 {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let on_change = on_change.map(|path| {
+        quote! {
+            if !changes.is_empty() {
+                #path(self, &changes);
+            }
+        }
+    });
+    quote! {
+        impl #impl_generics #name #ty_generics #track_where {
+            /// Patches `self` like `patch`, returning the fields whose values changed (and running
+            /// the `on_change` callbacks).
+            pub fn patch_tracked(&mut self, rhs: &mut #opt_name #ty_generics) -> #changes_name {
+                let undo = self.patch_with_undo(rhs);
+                self.changes(&undo)
+            }
+
+            #[doc(hidden)]
+            #[allow(unused_mut, unused_variables)]
+            pub fn changes(&self, undo: &#opt_name #ty_generics) -> #changes_name {
+                #body
+                #on_change
+                changes
+            }
+        }
+
+        impl #impl_generics ::wopt::Track for #name #ty_generics #track_where {
+            type Changes = #changes_name;
+
+            fn patch_tracked(&mut self, rhs: &mut Self::Opt) -> Self::Changes {
+                Self::patch_tracked(self, rhs)
+            }
+
+            fn changes(&self, undo: &Self::Opt) -> Self::Changes {
+                Self::changes(self, undo)
+            }
+
+            fn is_unchanged(changes: &Self::Changes) -> bool {
+                changes.is_empty()
+            }
+        }
+    }
+}
+
+/// Implements `WireEncode` for `ty` (either the original or optional type).
+#[cfg(feature = "bytemuck")]
+fn get_wire_impl(
+    input: &DeriveInput,
+    ty: &Ident,
+    serde_where: &WhereClause,
+    id: Id,
+) -> TokenStream2 {
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let (id, id_width) = (id.lit_u32(), id.width.path());
+    quote! {
+        impl #impl_generics ::wopt::WireEncode for #ty #ty_generics #serde_where {
+            const ID: u32 = #id;
+            const ID_WIDTH: ::wopt::IdWidth = #id_width;
+            const UNPADDED_SIZE: usize = Self::UNPADDED_SIZE;
+
+            fn serialize(&self) -> Vec<u8> {
+                Self::serialize(self).into()
+            }
+
+            fn try_serialize(&self) -> Result<Vec<u8>, ::wopt::EncodeError> {
+                Self::try_serialize(self)
+            }
+
+            fn deserialize_body(bytes: &[u8]) -> Self {
+                Self::deserialize(bytes)
+            }
+
+            fn try_deserialize(bytes: &[u8]) -> Result<Self, ::wopt::DecodeError> {
+                Self::try_deserialize(bytes)
+            }
+        }
+    }
+}
+
+/// The serialization methods of variable-size types, given the `body` serializing `self` (and
+/// returning any length overflowing its prefix).
+#[cfg(feature = "bytemuck")]
+fn get_ser(body: TokenStream2) -> TokenStream2 {
+    quote! {
+        pub fn serialize(&self) -> Vec<u8> {
+            ::wopt::__private::encoded(self.try_serialize())
+        }
+
+        pub fn try_serialize(&self) -> Result<Vec<u8>, ::wopt::EncodeError> {
+            #body
+        }
+    }
+}
+
+/// The deserialization methods, given the `body` deserializing `Self` from the `tail` of the bytes
+/// (leaving the `head` at the start of the last field read).
+#[cfg(feature = "bytemuck")]
+fn get_de(body: TokenStream2, id: Id) -> TokenStream2 {
+    let (id, id_width) = (id.lit_u32(), id.width.path());
+    quote! {
+        #[doc(hidden)]
+        #[allow(unused_mut, unused_assignments)]
+        pub fn deserialize_spanned(
+            bytes: &[u8],
+            head: &mut usize,
+            tail: &mut usize,
+        ) -> Result<Self, ::wopt::DecodeError> {
+            #body
+        }
+
+        #[doc(hidden)]
+        pub fn deserialize_from(bytes: &[u8], tail: &mut usize) -> Result<Self, ::wopt::DecodeError> {
+            Self::deserialize_spanned(bytes, &mut tail.clone(), tail)
+        }
+
+        pub fn deserialize_with(bytes: &[u8], head: &mut usize, tail: &mut usize) -> Self {
+            *tail = *head;
+            ::wopt::__private::decoded(Self::deserialize_spanned(bytes, head, tail))
+        }
+
+        pub fn deserialize(bytes: &[u8]) -> Self {
+            ::wopt::__private::decoded(Self::deserialize_from(bytes, &mut 0))
+        }
+
+        pub fn try_deserialize(bytes: &[u8]) -> Result<Self, ::wopt::DecodeError> {
+            let mut t = ::wopt::__private::check_id(bytes, #id, #id_width)?;
+            let new = Self::deserialize_from(bytes, &mut t)?;
+            ::wopt::__private::check_end(bytes, t).map(|_| new)
+        }
+    }
+}
+
+/// Implements `NestedWire` for the original type, (de)serializing its optional type `opt_name`
+/// within that of another type.
+#[cfg(feature = "bytemuck")]
+fn get_nested_wire_impl(
+    input: &DeriveInput,
+    opt_name: &Ident,
+    serde_where_opt: &WhereClause,
+) -> TokenStream2 {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::wopt::__private::NestedWire for #name #ty_generics #serde_where_opt {
+            fn ser_opt(
+                opt: &#opt_name #ty_generics,
+                data: &mut Vec<u8>,
+            ) -> Result<(), ::wopt::EncodeError> {
+                data.extend_from_slice(&::wopt::__private::ser_fields(opt)?);
+                Ok(())
+            }
+
+            fn de_opt(
+                bytes: &[u8],
+                tail: &mut usize,
+            ) -> Result<#opt_name #ty_generics, ::wopt::DecodeError> {
+                <#opt_name #ty_generics>::deserialize_from(bytes, tail)
+            }
+        }
+    }
+}
+
+/// Identities of the original and optional types, hashed (among every value of the width) from the
+/// crate name, the type name, its schema and the width of its lengths unless specified (see
+/// `IdWidth::ids`). Neither the file nor the module of the type are hashed, so that moving it
+/// within its crate keeps its identity (types with the same name and schema clashing instead).
+#[cfg(feature = "bytemuck")]
+fn get_ids(input: &DeriveInput, id: Option<u32>, width: IdWidth, len: LenWidth) -> [Id; 2] {
+    let id = id.unwrap_or_else(|| {
+        let krate = std::env::var("CARGO_CRATE_NAME").unwrap_or_default();
+        let schema = format!(
+            "{krate}::{}{}/len={}",
+            input.ident,
+            get_schema(&input.data),
+            len.name(),
+        );
+        (fnv1a(schema.as_bytes()) % (width.max() as u64 + 1)) as u32
+    });
+    width.ids(id)
+}
+
+/// The names, types and serialization attributes of the fields (of every variant, in order),
+/// identifying the serialized layout.
+#[cfg(feature = "bytemuck")]
+fn get_schema(data: &Data) -> String {
+    let fields = |fields: &Fields| {
+        let fields = fields.iter().map(|field| {
+            let ty = field.ty.to_token_stream();
+            let attrs = get_layout_attrs(&field.attrs);
+            match &field.ident {
+                Some(ident) => format!("{ident}:{ty}{attrs}"),
+                None => format!("{ty}{attrs}"),
+            }
+        });
+        format!("({})", fields.collect::<Vec<_>>().join(","))
+    };
+    match data {
+        Data::Struct(data) => fields(&data.fields),
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|variant| format!("|{}{}", variant.ident, fields(&variant.fields)))
+            .collect(),
+        Data::Union(_) => String::new(),
+    }
+}
+
+/// The `#[wopt(...)]` attributes of a field changing how it's serialized (e.g., `required` or
+/// `len = "u8"`), sorted so that their order doesn't matter.
+#[cfg(feature = "bytemuck")]
+fn get_layout_attrs(attrs: &[Attribute]) -> String {
+    const LAYOUT: [&str; 10] = [
+        "required", "skip", "nullable", "len", "list_ops", "map_ops", "optional", "serde", "ser",
+        "de",
+    ];
+    let mut layout = Vec::new();
+    for attr in get_wopt_attrs(attrs) {
+        // invalid attributes are reported along with the fields
+        let _ = attr.parse_nested_meta(|a| {
+            let key = a
+                .path
+                .get_ident()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            let value = if a.input.peek(Token![=]) {
+                Some(a.value()?.parse::<Expr>()?.to_token_stream().to_string())
+            } else {
+                if a.input.peek(Paren) {
+                    a.input.parse::<Group>()?;
+                }
+                None
+            };
+            if LAYOUT.contains(&key.as_str()) {
+                match value {
+                    Some(value) if key == "len" => layout.push(format!("{key}={value}")),
+                    _ => layout.push(key),
+                }
+            }
+            Ok(())
+        });
+    }
+    layout.sort();
+    layout.iter().map(|attr| format!("#{attr}")).collect()
+}
+
+/// 64-bit FNV-1a hash, stable across builds and platforms (unlike `DefaultHasher`).
+#[cfg(feature = "bytemuck")]
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg_attr(not(feature = "bytemuck"), allow(unused_variables))] // used by serialization
+fn expand_struct(
+    input: &DeriveInput,
+    data: &DataStruct,
+    options: syn::Result<Options>,
+) -> syn::Result<TokenStream2> {
+    // get the struct name
+    let name = &input.ident;
+
+    // generics are carried over as-is onto the optional struct and every impl
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // generic sizes can't be used in const operations (e.g., array lengths)
+    let is_generic = !generics.params.is_empty();
+
+    #[allow(unused_mut)]
+    let mut is_unit = false;
+
+    // the type of struct
+    let mut is_named = false;
+
+    // match on the fields of the struct
+    let info = match &data.fields {
+        Fields::Named(fields) => {
+            is_named = true;
+            get_field_kvs(fields.named.iter())
+        }
+        Fields::Unnamed(fields) => get_field_kvs(fields.unnamed.iter()),
+        _ => {
+            #[cfg(not(feature = "bytemuck"))]
+            {
+                Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "Unit structs are only supported with the `bytemuck` feature.",
+                ))
+            }
+
+            #[cfg(feature = "bytemuck")]
+            {
+                is_unit = true;
+                Ok(FieldAttrs {
+                    attrs: Vec::new(),
+                    is_const: true,
+                    len: LenWidth::default(),
+                })
+            }
+        }
+    };
+
+    #[allow(unused_mut)]
+    let (options, mut info) = join(options, info)?;
+
+    if info.attrs.is_empty() && !is_unit {
+        return Err(syn::Error::new_spanned(
+            &data.fields,
+            "Must have at least 1 field.",
+        ));
+    }
+
+    let Options {
+        derives,
+        attrs,
+        _no_serde,
+        validate,
+        on_change,
+        vis,
+        name: opt_ident,
+        #[cfg(feature = "bytemuck")]
+        id,
+        #[cfg(feature = "bytemuck")]
+        id_width,
+        #[cfg(feature = "bytemuck")]
+        track_id_width,
+        #[cfg(feature = "bytemuck")]
+        len,
+    } = options;
+
+    // the lengths of the fields are serialized like those of the type unless specified
+    #[cfg(feature = "bytemuck")]
+    {
+        info.len = len;
+    }
+
+    let opt_name = if is_unit {
+        name.clone()
+    } else {
+        opt_ident.unwrap_or_else(|| Ident::new(&format!("{name}Opt"), name.span()))
+    };
+
+    let fields_name = Ident::new(&format!("{name}Fields"), Span::call_site().into());
+    let changes_name = Ident::new(&format!("{name}Changes"), Span::call_site().into());
+
+    #[cfg(feature = "bytemuck")]
+    let bits = get_bits(info.mask_len(), &input.ident)?;
+
+    let is_const = info.is_const && !is_generic;
+
+    let FieldsCode {
+        fields,
+        upts,
+        mods,
+        take,
+        into,
+        new,
+        new_opt,
+        full,
+        is_full,
+        new_full,
+        merge,
+        undo,
+        diff,
+        eqs: _,
+        diff_bounds,
+        checks,
+        checks_patched,
+        checks_ops,
+        unapplied,
+        checked: _,
+        changes,
+        changes_fields,
+        track_bounds,
+        has_optional,
+        has_composite,
+        #[cfg(feature = "bytemuck")]
+        size,
+        #[cfg(feature = "bytemuck")]
+            ser: field_serialization,
+        #[cfg(feature = "bytemuck")]
+            de: field_deserialization,
+        #[cfg(feature = "bytemuck")]
+            ser_opt: field_serialization_opt,
+        #[cfg(feature = "bytemuck")]
+            de_opt: field_deserialization_opt,
+        #[cfg(feature = "bytemuck")]
+        pod_types,
+    } = get_fields_code(
+        &info,
+        Access::Member,
+        is_const,
+        |field, n, clear| {
+            // the bits of the fields are their flags, followed by the clear bits
+            #[cfg(feature = "bytemuck")]
+            match clear {
+                Some(bit) => {
+                    let flag =
+                        LitInt::new(&format!("{}{bits}", 1u128 << bit), Span::call_site().into());
+                    quote! { #flag }
+                }
+                None => {
+                    let unit_name = get_unit_name(field, n);
+                    quote! { #fields_name::#unit_name.bits() }
+                }
+            }
+
+            #[cfg(not(feature = "bytemuck"))]
+            quote! {}
+        },
+        |field, n| {
+            let unit_name = get_unit_name(field, n);
+            quote! { changes.fields |= #fields_name::#unit_name; }
+        },
+    );
+
+    #[cfg(feature = "bytemuck")]
+    let ids @ [id_og, id_opt] = get_ids(input, id, id_width, len);
+
+    #[cfg(feature = "bytemuck")]
+    let [
+        (id_ty, id_lit, id_bytes, id_len),
+        (_, id_lit_opt, id_bytes_opt, id_len_opt),
+    ] = ids.map(|id| (id.ty(), id.lit(), id.bytes(), id.bytes().len()));
+
+    #[cfg(feature = "bytemuck")]
+    let (serde_og, serde_opt) = if is_unit {
+        let serde = quote! {
+            pub const fn serialize() -> [u8; #id_len] {
+                [#(#id_bytes),*]
+            }
+        };
+        (serde, quote! {})
+    } else {
+        let serde_og = if _no_serde {
+            quote! {}
+        } else {
+            let ser = if is_const {
+                quote! {
+                    pub fn serialize(&self) -> [u8; #id_len + Self::UNPADDED_SIZE] {
+                        let mut data = [0; #id_len + Self::UNPADDED_SIZE];
+                        let [mut h, mut t] = [0, #id_len];
+                        data[..#id_len].copy_from_slice(&[#(#id_bytes),*]);
+                        #(#field_serialization)*
+                        data
+                    }
+
+                    pub fn try_serialize(&self) -> Result<Vec<u8>, ::wopt::EncodeError> {
+                        Ok(self.serialize().into())
+                    }
+                }
+            } else {
+                get_ser(quote! {
+                    let mut data = Vec::with_capacity(#id_len + Self::UNPADDED_SIZE);
+                    data.extend_from_slice(&[#(#id_bytes),*]);
+                    #(#field_serialization)*
+                    Ok(data)
+                })
+            };
+            let de = get_de(
+                quote! {
+                    let [mut h, mut t] = [*tail; 2];
+                    #(#field_deserialization)*
+                    *head = h;
+                    *tail = t;
+                    Ok(Self { #(#new),* })
+                },
+                id_og,
+            );
+            quote! {
+                pub const ID: #id_ty = #id_lit;
+
+                #ser
+                #de
+            }
+        };
+
+        // the bits past those of the fields are unknown
+        let width = bits.to_string()[1..].parse::<usize>().unwrap();
+        let unknown = (info.mask_len() < width).then(|| {
+            let unknown = LitInt::new(
+                &format!(
+                    "{}{bits}",
+                    u128::MAX >> (128 - width) & !((1 << info.mask_len()) - 1)
+                ),
+                Span::call_site().into(),
+            );
+            quote! {
+                if mask & #unknown != 0 {
+                    return Err(::wopt::DecodeError::new(*tail, "unknown mask bits"));
+                }
+            }
+        });
+        let de_opt = get_de(
+            quote! {
+                let [mut h, mut t] = [*tail; 2];
+                let mask = #bits::from_le(::wopt::__private::read_pod(bytes, &mut t)?);
+                #unknown
+                #(#field_deserialization_opt)*
+                *head = h;
+                *tail = t;
+                Ok(Self { #(#new_opt),* })
+            },
+            id_opt,
+        );
+        let ser_opt = get_ser(quote! {
+            let mut data = Vec::with_capacity(
+                #id_len_opt                     +   // identity bytes
+                ::core::mem::size_of::<#bits>() +   // bitmask data
+                Self::UNPADDED_SIZE                 // field(s) data
+            );
+            data.extend_from_slice(&[#(#id_bytes_opt),*]);
+            let mut mask: #bits = 0;
+            #(#field_serialization_opt)*
+            data.splice(#id_len_opt..#id_len_opt, mask.to_le_bytes());
+            Ok(data)
+        });
+        let serde_opt = quote! {
+            pub const ID: #id_ty = #id_lit_opt;
+
+            #ser_opt
+            #de_opt
+        };
+        (serde_og, serde_opt)
+    };
+
+    // this is just filthy
+    if is_unit {
+        #[cfg(not(feature = "bytemuck"))]
+        return Ok(quote! {});
+
+        #[cfg(feature = "bytemuck")]
+        let (marker, id_u32, id_width) = (id_og.marker(name), id_og.lit_u32(), id_width.path());
+
+        #[cfg(feature = "bytemuck")]
+        let tracking = get_id_width_tracking(track_id_width);
+
+        #[cfg(feature = "bytemuck")]
+        return Ok(quote! {
+            #tracking
+            #marker
+
+            impl #impl_generics #name #ty_generics #where_clause {
+                pub const ID: #id_ty = #id_lit;
+                #serde_og
+            }
+
+            impl #impl_generics ::wopt::WireEncode for #name #ty_generics #where_clause {
+                const ID: u32 = #id_u32;
+                const ID_WIDTH: ::wopt::IdWidth = #id_width;
+                const UNPADDED_SIZE: usize = 0;
+
+                fn serialize(&self) -> Vec<u8> {
+                    Self::serialize().into()
+                }
+
+                fn try_serialize(&self) -> Result<Vec<u8>, ::wopt::EncodeError> {
+                    Ok(Self::serialize().into())
+                }
+
+                fn deserialize_body(_: &[u8]) -> Self {
+                    Self
+                }
+
+                fn try_deserialize(bytes: &[u8]) -> Result<Self, ::wopt::DecodeError> {
+                    let t = ::wopt::__private::check_id(bytes, #id_u32, #id_width)?;
+                    ::wopt::__private::check_end(bytes, t).map(|_| Self)
+                }
+            }
+        });
+    }
+
+    // generate the new struct
+    let structure = if is_named {
+        quote! {
+            #[derive(#(#derives),*)]
+            #(#attrs)*
+            #vis struct #opt_name #generics #where_clause {
+                #(#fields),*
+            }
+        }
+    } else {
+        quote! {
+            #[derive(#(#derives),*)]
+            #(#attrs)*
+            #vis struct #opt_name #generics (#(#fields),*) #where_clause;
+        }
+    };
+
+    let (impl_name, impl_name_opt) = if upts.is_empty() {
+        Default::default()
+    } else {
+        // modifications left unapplied are put back into the patch
+        let let_stmt = if !unapplied.is_empty() {
+            quote! { let (mut rhs, unapplied) = (rhs.take(), rhs); }
+        } else if has_optional {
+            quote! { let mut rhs = rhs.take(); }
+        } else {
+            quote! { let rhs = rhs.take(); }
+        };
+        let patch = quote! {
+            pub fn patch(&mut self, rhs: &mut #opt_name #ty_generics) {
+                #let_stmt
+                #(#upts)*
+                #(#unapplied)*
+            }
+
+            pub fn patch_with_undo(&mut self, rhs: &mut #opt_name #ty_generics) -> #opt_name #ty_generics {
+                #let_stmt
+                let undo = #opt_name { #(#undo),* };
+                #(#unapplied)*
+                undo
+            }
+        };
+        let validate = get_validate(
+            quote! { #(#checks)* },
+            Some(quote! { #(#checks_patched)* }),
+            quote! { #(#checks_ops)* },
+            validate.as_ref(),
+            &quote! { #opt_name #ty_generics },
+        );
+        let into_opt = if is_const {
+            quote! {
+                pub const fn into_opt(self) -> #opt_name #ty_generics {
+                    #opt_name { #(#into),* }
+                }
+            }
+        } else {
+            quote! {
+                pub fn into_opt(self) -> #opt_name #ty_generics {
+                    #opt_name { #(#into),* }
+                }
+            }
+        };
+        // the traits of composite optional types can't be called in `const fn`s
+        let constness = (!has_composite).then(|| quote! { const });
+        let is_modified = quote! {
+            pub #constness fn is_modified(&self) -> bool {
+                #(#mods)||*
+            }
+        };
+        let take = quote! {
+            pub #constness fn take(&mut self) -> Self {
+                Self { #(#take),* }
+            }
+
+            pub fn merge(&mut self, rhs: Self) {
+                #(#merge)*
+            }
+        };
+        let missing = get_missing(
+            info.attrs
+                .iter()
+                .any(|field| !field.is_required && !field.is_skipped),
+        );
+        let full_body = get_full_body(&info, &full, &new_full, quote! { #name });
+        let try_into_full = quote! {
+            pub fn try_into_full(self) -> Result<#name #ty_generics, ::wopt::MissingFields> {
+                #missing
+                #full_body
+            }
+
+            /// Whether every field is set (i.e., whether `try_into_full` succeeds).
+            pub fn is_full(&self) -> bool {
+                #(#is_full &&)* true
+            }
+        };
+        let accessors = get_accessors(&info)?;
+
+        // required fields are never modified
+        let (units, takes): (Vec<_>, Vec<_>) = info
+            .attrs
+            .iter()
+            .filter(|field| !field.is_skipped)
+            .enumerate()
+            .filter(|(_, field)| !field.is_required)
+            .map(|(n, field)| {
+                let member = &field.member;
+                let take = if field.is_composite() {
+                    quote! { ::wopt::Patch::take(&mut self.#member) }
+                } else {
+                    quote! { self.#member.take() }
+                };
+                (get_unit_name(field, n), take)
+            })
+            .unzip();
+        let modified = info
+            .attrs
+            .iter()
+            .filter(|field| !field.is_skipped && !field.is_required)
+            .map(|field| {
+                let member = &field.member;
+                if field.is_composite() {
+                    quote! { ::wopt::Patch::is_modified(&self.#member) }
+                } else if field.nullable.is_some() || field.is_optional || field.ops.is_some() {
+                    quote! { self.#member.is_modified() }
+                } else {
+                    quote! { self.#member.is_some() }
+                }
+            });
+        let fields_opt = quote! {
+            /// The modified fields.
+            pub #constness fn modified_fields(&self) -> #fields_name {
+                let mut fields = #fields_name::empty();
+                #(
+                    if #modified {
+                        fields = fields.union(#fields_name::#units);
+                    }
+                )*
+                fields
+            }
+
+            /// Takes the modifications of every field but `fields`.
+            pub fn retain(&mut self, fields: #fields_name) {
+                self.remove(fields.complement())
+            }
+
+            /// Takes the modifications of `fields`.
+            pub fn remove(&mut self, fields: #fields_name) {
+                #(
+                    if fields.contains(#fields_name::#units) {
+                        #takes;
+                    }
+                )*
+            }
+        };
+        let to_opt_masked = quote! {
+            /// Converts a clone of `self` into its optional counterpart, with only `fields` set.
+            pub fn to_opt_masked(&self, fields: #fields_name) -> #opt_name #ty_generics
+            where
+                for<'__wopt> Self: ::core::clone::Clone,
+            {
+                let mut opt = ::core::clone::Clone::clone(self).into_opt();
+                opt.retain(fields);
+                opt
+            }
+        };
+        (
+            quote! {
+                #patch
+                #validate
+                #into_opt
+                #to_opt_masked
+            },
+            quote! {
+                #is_modified
+                #take
+                #fields_opt
+                #try_into_full
+                #(#accessors)*
+            },
+        )
+    };
+
+    let impl_name = quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #impl_name
+        }
+    };
+
+    let impl_name_opt = quote! {
+        impl #impl_generics #opt_name #ty_generics #where_clause {
+            #impl_name_opt
+        }
+    };
+
+    #[cfg(feature = "bytemuck")]
+    let [serde_where, serde_where_opt] = get_serde_where(
+        generics,
+        &pod_types,
+        &info
+            .attrs
+            .iter()
+            .filter(|field| field.is_optional && !field.is_skipped)
+            .map(|field| &field.field_type_opt)
+            .collect::<Vec<_>>(),
+    );
+
+    // the traits are implemented through the inherent items
+    let impl_traits = if upts.is_empty() {
+        quote! {}
+    } else {
+        let impl_diff = get_diff_impl(
+            input,
+            &opt_name,
+            &get_diff_where(generics, &diff_bounds),
+            quote! { #opt_name { #(#diff),* } },
+        );
+        let impl_track = get_track_impl(
+            input,
+            &opt_name,
+            &changes_name,
+            &get_diff_where(generics, &track_bounds),
+            quote! {
+                let rhs = undo;
+                let mut changes = #changes_name::default();
+                #(#changes)*
+            },
+            on_change.as_ref(),
+        );
+        let units = info
+            .attrs
+            .iter()
+            .filter(|field| !field.is_skipped)
+            .enumerate()
+            .map(|(n, field)| (get_unit_name(field, n), field.name()))
+            .collect::<Vec<_>>();
+        let impl_fields = get_fields_impl(&units, info.mask_len(), name, &fields_name, &vis)?;
+        let changes_doc = format!("Changes of a [`{name}`] patch (see `patch_tracked`).");
+        let impl_traits = get_trait_impls(input, &opt_name);
+        quote! {
+            #impl_traits
+            #impl_diff
+            #impl_track
+            #impl_fields
+
+            #[doc = #changes_doc]
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+            #vis struct #changes_name {
+                /// The changed fields.
+                pub fields: #fields_name,
+                #(#changes_fields),*
+            }
+
+            impl #changes_name {
+                /// Whether no field changed.
+                pub const fn is_empty(&self) -> bool {
+                    self.fields.is_empty()
+                }
+            }
+        }
+    };
+
+    #[cfg(feature = "bytemuck")]
+    let impl_name = {
+        // the identities of the optional types differ as long as those of the original ones do
+        let impl_wire = if _no_serde {
+            quote! {}
+        } else {
+            let marker = id_og.marker(name);
+            let impl_wire = get_wire_impl(input, name, &serde_where, id_og);
+            quote! {
+                #marker
+                #impl_wire
+            }
+        };
+        quote! {
+            #impl_name
+
+            impl #impl_generics #name #ty_generics #serde_where {
+                pub const UNPADDED_SIZE: usize = #(#size)+*;
+
+                #serde_og
+            }
+
+            #impl_wire
+        }
+    };
+
+    #[cfg(feature = "bytemuck")]
+    let impl_name_opt = {
+        let impl_wire = get_wire_impl(input, &opt_name, &serde_where_opt, id_opt);
+        let impl_nested_wire = get_nested_wire_impl(input, &opt_name, &serde_where_opt);
+        let tracking = get_id_width_tracking(track_id_width);
+
+        // the bitmask used to be named after the optional struct
+        let unit_alias = (!upts.is_empty()).then(|| {
+            let unit = Ident::new(&format!("{opt_name}Unit"), Span::call_site().into());
+            let note = format!("renamed to `{fields_name}`");
+            quote! {
+                #[doc(hidden)]
+                #[deprecated(note = #note)]
+                #[allow(dead_code)]
+                #vis type #unit = #fields_name;
+            }
+        });
+        quote! {
+            #impl_name_opt
+            #tracking
+            #unit_alias
+
+            impl #impl_generics #opt_name #ty_generics #serde_where_opt {
+                pub const UNPADDED_SIZE: usize = #(#size)+*;
+
+                #serde_opt
+            }
+
+            #impl_wire
+            #impl_nested_wire
+        }
+    };
+
+    Ok(quote! {
+        #structure
+        #impl_name
+        #impl_name_opt
+        #impl_traits
+    })
+}
+
+#[cfg_attr(not(feature = "bytemuck"), allow(unused_variables))] // used by serialization
+fn expand_enum(
+    input: &DeriveInput,
+    data: &DataEnum,
+    options: syn::Result<Options>,
+) -> syn::Result<TokenStream2> {
+    // get the enum name
+    let name = &input.ident;
+
+    // generics are carried over as-is onto the optional enum and every impl
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // generic sizes can't be used in const operations (e.g., array lengths)
+    let is_generic = !generics.params.is_empty();
+
+    let mut errors = Errors::default();
+
+    if data.variants.is_empty() {
+        errors.push(syn::Error::new_spanned(
+            &input.ident,
+            "Must have at least 1 variant.",
+        ));
+    }
+
+    // the variant index `u8::MAX` is reserved for `Unmodified`
+    if data.variants.len() > u8::MAX as usize {
+        errors.push(syn::Error::new_spanned(
+            &input.ident,
+            "Too many variants (max: 255).",
+        ));
+    }
+
+    let unmodified = Ident::new("Unmodified", Span::call_site().into());
+
+    let mut infos = Vec::new();
+    for variant in &data.variants {
+        if variant.ident == unmodified {
+            errors.push(syn::Error::new_spanned(
+                &variant.ident,
+                "`Unmodified` is reserved for the optional enum.",
+            ));
+        }
+        match get_field_kvs(variant.fields.iter()) {
+            Ok(info) => {
+                for field in info.attrs.iter().filter(|field| field.is_optional) {
+                    errors.push(syn::Error::new_spanned(
+                        field.field_type,
+                        "`optional` fields aren't supported in enum variants.",
+                    ));
+                }
+                for field in info.attrs.iter().filter(|field| field.ops.is_some()) {
+                    errors.push(syn::Error::new_spanned(
+                        field.field_type,
+                        "`list_ops`/`map_ops` fields aren't supported in enum variants.",
+                    ));
+                }
+                infos.push((variant, info))
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    let (options, ()) = join(options, errors.finish())?;
+
+    let Options {
+        derives,
+        attrs,
+        _no_serde,
+        validate,
+        on_change,
+        vis,
+        name: opt_ident,
+        #[cfg(feature = "bytemuck")]
+        id,
+        #[cfg(feature = "bytemuck")]
+        id_width,
+        #[cfg(feature = "bytemuck")]
+        track_id_width,
+        #[cfg(feature = "bytemuck")]
+        len,
+    } = options;
+
+    // the lengths of the fields are serialized like those of the type unless specified
+    #[cfg(feature = "bytemuck")]
+    for (_, info) in &mut infos {
+        info.len = len;
+    }
+
+    let opt_name = opt_ident.unwrap_or_else(|| Ident::new(&format!("{name}Opt"), name.span()));
+
+    let is_const = infos.iter().all(|(_, info)| info.is_const) && !is_generic;
+
+    #[cfg(feature = "bytemuck")]
+    let ids @ [id_og, id_opt] = get_ids(input, id, id_width, len);
+
+    #[cfg(feature = "bytemuck")]
+    let [
+        (id_ty, id_lit, id_bytes, id_len),
+        (_, id_lit_opt, id_bytes_opt, id_len_opt),
+    ] = ids.map(|id| (id.ty(), id.lit(), id.bytes(), id.bytes().len()));
+
+    // the bitmask is sized to fit the largest variant
+    #[cfg(feature = "bytemuck")]
+    let bits = get_bits(
+        infos
+            .iter()
+            .map(|(_, info)| info.mask_len())
+            .max()
+            .unwrap_or_default(),
+        &input.ident,
+    )?;
+
+    let mut variants_opt = Vec::new();
+    let mut upts = Vec::new();
+    let mut undos = Vec::new();
+    let mut checks = Vec::new();
+    let mut changes = Vec::new();
+    let mut track_bounds = Vec::new();
+    let mut merges = Vec::new();
+    let mut fulls = Vec::new();
+    let mut is_full = Vec::new();
+    let mut switches = Vec::new();
+    let mut into = Vec::new();
+    let mut diffs = Vec::new();
+    let mut diffs_switch = Vec::new();
+    let mut diff_bounds = Vec::new();
+    let fields_name = Ident::new(&format!("{name}Fields"), Span::call_site().into());
+    let mut units = Vec::new();
+    let mut modified = Vec::new();
+    let mut removes = Vec::new();
+
+    // whether some variant can't be switched to (i.e., has an incomplete patch)
+    let mut is_refutable = false;
+
+    #[cfg(feature = "bytemuck")]
+    let mut ser = Vec::new();
+    #[cfg(feature = "bytemuck")]
+    let mut de = Vec::new();
+    #[cfg(feature = "bytemuck")]
+    let mut ser_opt = Vec::new();
+    #[cfg(feature = "bytemuck")]
+    let mut de_opt = Vec::new();
+    #[cfg(feature = "bytemuck")]
+    let mut sizes = Vec::new();
+    #[cfg(feature = "bytemuck")]
+    let mut pod_types = Vec::new();
+
+    for (index, (variant, info)) in infos.iter().enumerate() {
+        let variant_name = &variant.ident;
+        let index = index as u8;
+
+        let code = get_fields_code(
+            info,
+            Access::Binding,
+            is_const,
+            |_, n, clear| {
+                #[cfg(feature = "bytemuck")]
+                {
+                    let bit = clear.unwrap_or(n);
+                    let flag =
+                        LitInt::new(&format!("{}{bits}", 1u128 << bit), Span::call_site().into());
+                    quote! { #flag }
+                }
+
+                #[cfg(not(feature = "bytemuck"))]
+                quote! {}
+            },
+            |field, n| {
+                let unit_name = format_ident!("{variant_name}{}", get_unit_name(field, n));
+                quote! { changes.fields |= #fields_name::#unit_name; }
+            },
+        );
+        let (new, new_opt) = (&code.new, &code.new_opt);
+
+        let full_body = get_full_body(
+            info,
+            &code.full,
+            &code.new_full,
+            quote! { #name::#variant_name },
+        );
+        fulls.push(quote! {
+            Self::#variant_name { #(#new_opt),* } => {
+                #full_body
+            }
+        });
+        let is_full_variant = &code.is_full;
+        is_full.push(quote! {
+            Self::#variant_name { #(#new_opt),* } => #(#is_full_variant &&)* true,
+        });
+
+        let fields = &code.fields;
+        variants_opt.push(match &variant.fields {
+            Fields::Named(_) => quote! { #variant_name { #(#fields),* } },
+            Fields::Unnamed(_) => quote! { #variant_name(#(#fields),*) },
+            Fields::Unit => quote! { #variant_name },
+        });
+
+        // patch the fields in-place when the variant is unchanged
+        let patched = info
+            .attrs
+            .iter()
+            .filter(|field| !field.is_required && !field.is_skipped)
+            .collect::<Vec<_>>();
+        if !info.attrs.is_empty() {
+            let members = patched.iter().map(|field| &field.member);
+            let bindings = patched.iter().map(|field| &field.binding);
+            let members_rhs = patched.iter().map(|field| &field.member);
+            let bindings_rhs = patched.iter().map(|field| field.rhs_binding());
+            let upts_variant = &code.upts;
+            upts.push(quote! {
+                (
+                    Self::#variant_name { #(#members: #bindings,)* .. },
+                    #opt_name::#variant_name { #(#members_rhs: #bindings_rhs,)* .. },
+                ) => {
+                    #(#upts_variant)*
+                }
+            });
+
+            // the undo patch also carries the required fields through
+            let members = patched.iter().map(|field| &field.member);
+            let bindings = patched.iter().map(|field| &field.binding);
+            let unskipped = info.attrs.iter().filter(|field| !field.is_skipped);
+            let members_rhs = unskipped.clone().map(|field| &field.member);
+            let bindings_rhs = unskipped.map(|field| field.rhs_binding());
+            let undo_variant = &code.undo;
+            undos.push(quote! {
+                (
+                    Self::#variant_name { #(#members: #bindings,)* .. },
+                    #opt_name::#variant_name { #(#members_rhs: #bindings_rhs,)* .. },
+                ) => #opt_name::#variant_name { #(#undo_variant),* }
+            });
+        }
+
+        // the flags of the variant's fields follow those of the previous variants
+        let units_variant = info
+            .attrs
+            .iter()
+            .filter(|field| !field.is_skipped)
+            .enumerate()
+            .map(|(n, field)| {
+                let unit_name = format_ident!("{variant_name}{}", get_unit_name(field, n));
+                (field, unit_name, format!("{variant_name}.{}", field.name()))
+            })
+            .collect::<Vec<_>>();
+        let (modified_units, modified_bindings): (Vec<_>, Vec<_>) = units_variant
+            .iter()
+            .filter(|(field, ..)| !field.is_required)
+            .map(|(field, unit_name, _)| (unit_name, &field.binding))
+            .unzip();
+        if !patched.is_empty() {
+            let members = patched.iter().map(|field| &field.member);
+            let bindings = patched.iter().map(|field| &field.binding);
+            let is_set = patched.iter().map(|field| {
+                let binding = &field.binding;
+                if field.nullable.is_some() {
+                    quote! { #binding.is_modified() }
+                } else {
+                    quote! { #binding.is_some() }
+                }
+            });
+            modified.push(quote! {
+                Self::#variant_name { #(#members: #bindings,)* .. } => {
+                    let mut fields = #fields_name::empty();
+                    #(
+                        if #is_set {
+                            fields = fields.union(#fields_name::#modified_units);
+                        }
+                    )*
+                    fields
+                }
+            });
+            let members = patched.iter().map(|field| &field.member);
+            let bindings = patched.iter().map(|field| &field.binding);
+            removes.push(quote! {
+                Self::#variant_name { #(#members: #bindings,)* .. } => {
+                    #(
+                        if fields.contains(#fields_name::#modified_units) {
+                            #modified_bindings.take();
+                        }
+                    )*
+                }
+            });
+        }
+        units.extend(
+            units_variant
+                .into_iter()
+                .map(|(_, unit_name, field_name)| (unit_name, field_name)),
+        );
+
+        if !code.checks.is_empty() {
+            let checked = &code.checked;
+            let checks_variant = &code.checks;
+            checks.push(quote! {
+                Self::#variant_name { #(#checked,)* .. } => {
+                    #(#checks_variant)*
+                }
+            });
+        }
+
+        // the fields of an unchanged variant are compared with the replaced values
+        let members = patched.iter().map(|field| &field.member);
+        let bindings = patched.iter().map(|field| &field.binding);
+        let members_rhs = patched.iter().map(|field| &field.member);
+        let bindings_rhs = patched.iter().map(|field| field.rhs_binding());
+        let changes_variant = &code.changes;
+        changes.push(quote! {
+            (
+                Self::#variant_name { #(#members: #bindings,)* .. },
+                #opt_name::#variant_name { #(#members_rhs: #bindings_rhs,)* .. },
+            ) => {
+                #(#changes_variant)*
+            }
+        });
+        track_bounds.extend(code.track_bounds.iter().cloned());
+
+        // otherwise, switch to the variant if its patch is complete
+        let patterns = info
+            .attrs
+            .iter()
+            .filter(|field| !field.is_skipped)
+            .map(|field| {
+                let FieldAttr {
+                    member, binding, ..
+                } = field;
+                if field.is_required {
+                    quote! { #member: #binding }
+                } else {
+                    is_refutable = true;
+                    let pattern = field.set_pattern(Some(binding));
+                    quote! { #member: #pattern }
+                }
+            });
+        let inits = info.attrs.iter().map(|field| {
+            let FieldAttr {
+                member, binding, ..
+            } = field;
+            if field.is_skipped {
+                let default = field.default();
+                quote! { #member: #default }
+            } else if field.is_required {
+                quote! { #member: #binding }
+            } else {
+                let value = field.set_value(binding);
+                quote! { #member: #value }
+            }
+        });
+        // a later patch of the same variant merges its fields, otherwise it overrides if complete
+        let unskipped = info.attrs.iter().filter(|field| !field.is_skipped);
+        let members = unskipped.clone().map(|field| &field.member);
+        let bindings = unskipped.clone().map(|field| &field.binding);
+        let members_rhs = unskipped.clone().map(|field| &field.member);
+        let bindings_rhs = unskipped.clone().map(|field| field.rhs_binding());
+        let merge_variant = &code.merge;
+        let wildcards = unskipped.map(|field| {
+            let member = &field.member;
+            if field.is_required {
+                quote! { #member: _ }
+            } else {
+                let pattern = field.set_pattern(None);
+                quote! { #member: #pattern }
+            }
+        });
+        merges.push(quote! {
+            (
+                Self::#variant_name { #(#members: #bindings,)* .. },
+                Self::#variant_name { #(#members_rhs: #bindings_rhs,)* .. },
+            ) => {
+                #(#merge_variant)*
+            }
+            (this, rhs @ Self::#variant_name { #(#wildcards,)* .. }) => *this = rhs,
+        });
+
+        switches.push((
+            quote! { (this, #opt_name::#variant_name { #(#patterns),* }) },
+            quote! { Self::#variant_name { #(#inits),* } },
+        ));
+
+        let into_variant = &code.into;
+        into.push(quote! {
+            Self::#variant_name { #(#new),* } => #opt_name::#variant_name { #(#into_variant),* }
+        });
+
+        // the same variant only differs by its patched fields
+        let compared = info.attrs.iter().filter(|field| !field.is_skipped);
+        let members = compared.clone().map(|field| &field.member);
+        let bindings = compared.clone().map(|field| &field.binding);
+        let members_rhs = compared.clone().map(|field| &field.member);
+        let bindings_rhs = compared.map(|field| field.rhs_binding());
+        let (diff_variant, eqs) = (&code.diff, &code.eqs);
+        let diff_variant = if eqs.is_empty() {
+            quote! { #opt_name::#unmodified }
+        } else {
+            quote! {
+                if #(#eqs)&&* {
+                    #opt_name::#unmodified
+                } else {
+                    #opt_name::#variant_name { #(#diff_variant),* }
+                }
+            }
+        };
+        diffs.push(quote! {
+            (
+                Self::#variant_name { #(#members: #bindings,)* .. },
+                Self::#variant_name { #(#members_rhs: #bindings_rhs,)* .. },
+            ) => #diff_variant
+        });
+
+        // while another variant is switched to with all of its fields
+        let compared = info.attrs.iter().filter(|field| !field.is_skipped);
+        let members = compared.clone().map(|field| &field.member);
+        let bindings = compared.clone().map(|field| field.rhs_binding());
+        let inits = compared.map(|field| {
+            let member = &field.member;
+            let rhs = field.rhs_binding();
+            let value = quote! { ::core::clone::Clone::clone(#rhs) };
+            if field.is_required {
+                quote! { #member: #value }
+            } else {
+                let value = field.wrap(value);
+                quote! { #member: #value }
+            }
+        });
+        diffs_switch.push(quote! {
+            (_, Self::#variant_name { #(#members: #bindings,)* .. }) => {
+                #opt_name::#variant_name { #(#inits),* }
+            }
+        });
+        diff_bounds.extend(code.diff_bounds.iter().cloned());
+
+        #[cfg(feature = "bytemuck")]
+        {
+            let has_mask = !patched.is_empty();
+            let (ser_variant, de_variant) = (&code.ser, &code.de);
+            let (ser_opt_variant, de_opt_variant) = (&code.ser_opt, &code.de_opt);
+
+            ser.push(if is_const {
+                quote! {
+                    Self::#variant_name { #(#new),* } => {
+                        data[#id_len] = #index;
+                        #(#ser_variant)*
+                    }
+                }
+            } else {
+                quote! {
+                    Self::#variant_name { #(#new),* } => {
+                        data.push(#index);
+                        #(#ser_variant)*
+                    }
+                }
+            });
+            de.push(quote! {
+                #index => {
+                    #(#de_variant)*
+                    Self::#variant_name { #(#new),* }
+                }
+            });
+
+            let mask = if has_mask {
+                quote! { let mut mask: #bits = 0; }
+            } else {
+                quote! { let mask: #bits = 0; }
+            };
+            ser_opt.push(quote! {
+                Self::#variant_name { #(#new_opt),* } => {
+                    data.push(#index);
+                    #mask
+                    #(#ser_opt_variant)*
+                    data.splice(#id_len_opt + 1..#id_len_opt + 1, mask.to_le_bytes());
+                }
+            });
+            // the bits past those of the variant's fields are unknown
+            let width = bits.to_string()[1..].parse::<usize>().unwrap();
+            let unknown = (info.mask_len() < width).then(|| {
+                let unknown = LitInt::new(
+                    &format!(
+                        "{}{bits}",
+                        u128::MAX >> (128 - width) & !((1 << info.mask_len()) - 1)
+                    ),
+                    Span::call_site().into(),
+                );
+                quote! {
+                    if mask & #unknown != 0 {
+                        return Err(::wopt::DecodeError::new(offset, "unknown mask bits"));
+                    }
+                }
+            });
+            let mask = if has_mask || unknown.is_some() {
+                quote! { let mask = #bits::from_le(::wopt::__private::read_pod(bytes, &mut t)?); }
+            } else {
+                quote! { ::wopt::__private::read_pod::<#bits>(bytes, &mut t)?; }
+            };
+            de_opt.push(quote! {
+                #index => {
+                    let offset = t;
+                    #mask
+                    #unknown
+                    #(#de_opt_variant)*
+                    Self::#variant_name { #(#new_opt),* }
+                }
+            });
+
+            let size = &code.size;
+            sizes.push(quote! { 0 #(+ #size)* });
+            pod_types.extend(code.pod_types);
+        }
+    }
+
+    let missing = get_missing(is_refutable);
+
+    // incomplete patches of other variants are left unconsumed (and ignored by `merge`)
+    let [fallback, fallback_patch, fallback_undo] = if is_refutable {
+        [
+            quote! { _ => {} },
+            quote! { (_, unconsumed) => *rhs = unconsumed, },
+            quote! {
+                (_, unconsumed) => {
+                    *rhs = unconsumed;
+                    #opt_name::#unmodified
+                }
+            },
+        ]
+    } else {
+        Default::default()
+    };
+    let (switches, switches_undo): (Vec<_>, Vec<_>) = switches
+        .iter()
+        .map(|(pattern, new)| {
+            (
+                quote! { #pattern => *this = #new, },
+                quote! { #pattern => ::core::mem::replace(this, #new).into_opt(), },
+            )
+        })
+        .unzip();
+
+    // `#[default]` is only recognized by `#[derive(Default)]`
+    let default = if derives.iter().any(|derive| derive.to_string() == "Default") {
+        quote! { #[default] }
+    } else {
+        quote! {}
+    };
+
+    // generate the new enum
+    let structure = quote! {
+        #[derive(#(#derives),*)]
+        #(#attrs)*
+        #vis enum #opt_name #generics #where_clause {
+            #default
+            #unmodified,
+            #(#variants_opt),*
+        }
+    };
+
+    // variants without validators are always valid
+    let checks = if checks.is_empty() {
+        None
+    } else {
+        let fallback = (checks.len() < infos.len()).then(|| quote! { _ => {} });
+        Some(quote! {
+            match self {
+                #(#checks)*
+                #fallback
+            }
+        })
+    };
+    // variants have neither `list_ops` nor `optional` fields
+    let validate = get_validate(
+        quote! { #checks },
+        None,
+        quote! {},
+        validate.as_ref(),
+        &quote! { #opt_name #ty_generics },
+    );
+
+    let into_opt = if is_const {
+        quote! {
+            pub const fn into_opt(self) -> #opt_name #ty_generics {
+                match self {
+                    #(#into),*
+                }
+            }
+        }
+    } else {
+        quote! {
+            pub fn into_opt(self) -> #opt_name #ty_generics {
+                match self {
+                    #(#into),*
+                }
+            }
+        }
+    };
+
+    let impl_name = quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            pub fn patch(&mut self, rhs: &mut #opt_name #ty_generics) {
+                match (self, rhs.take()) {
+                    (_, #opt_name::#unmodified) => {}
+                    #(#upts)*
+                    #(#switches)*
+                    #fallback_patch
+                }
+            }
+
+            pub fn patch_with_undo(&mut self, rhs: &mut #opt_name #ty_generics) -> #opt_name #ty_generics {
+                match (self, rhs.take()) {
+                    (_, #opt_name::#unmodified) => #opt_name::#unmodified,
+                    #(#undos,)*
+                    #(#switches_undo)*
+                    #fallback_undo
+                }
+            }
+
+            #validate
+            #into_opt
+
+            /// Converts a clone of `self` into its optional counterpart, with only `fields` set.
+            pub fn to_opt_masked(&self, fields: #fields_name) -> #opt_name #ty_generics
+            where
+                for<'__wopt> Self: ::core::clone::Clone,
+            {
+                let mut opt = ::core::clone::Clone::clone(self).into_opt();
+                opt.retain(fields);
+                opt
+            }
+        }
+    };
+
+    let impl_name_opt = quote! {
+        impl #impl_generics #opt_name #ty_generics #where_clause {
+            pub const fn is_modified(&self) -> bool {
+                !matches!(self, Self::#unmodified)
+            }
+
+            pub const fn take(&mut self) -> Self {
+                ::core::mem::replace(self, Self::#unmodified)
+            }
+
+            /// The modified fields (of the variant).
+            pub const fn modified_fields(&self) -> #fields_name {
+                match self {
+                    #(#modified)*
+                    _ => #fields_name::empty(),
+                }
+            }
+
+            /// Takes the modifications of every field but `fields`.
+            pub fn retain(&mut self, fields: #fields_name) {
+                self.remove(fields.complement())
+            }
+
+            /// Takes the modifications of `fields`.
+            pub fn remove(&mut self, fields: #fields_name) {
+                match self {
+                    #(#removes)*
+                    _ => {}
+                }
+            }
+
+            pub fn try_into_full(self) -> Result<#name #ty_generics, ::wopt::MissingFields> {
+                #missing
+                match self {
+                    Self::#unmodified => Err(missing),
+                    #(#fulls)*
+                }
+            }
+
+            /// Whether the variant has every field set (i.e., whether `try_into_full` succeeds).
+            pub fn is_full(&self) -> bool {
+                match self {
+                    Self::#unmodified => false,
+                    #(#is_full)*
+                }
+            }
+
+            pub fn merge(&mut self, rhs: Self) {
+                if !self.is_modified() {
+                    *self = rhs;
+                    return;
+                }
+                match (self, rhs) {
+                    (_, Self::#unmodified) => {}
+                    #(#merges)*
+                    #fallback
+                }
+            }
+        }
+    };
+
+    #[cfg(feature = "bytemuck")]
+    let serde_og = if _no_serde {
+        quote! {}
+    } else {
+        // variants smaller than the largest one are padded
+        let padding = is_const.then(|| {
+            quote! {
+                let padding = *tail + Self::UNPADDED_SIZE - t;
+                ::wopt::__private::read(bytes, &mut t, padding)?;
+            }
+        });
+        let de = get_de(
+            quote! {
+                let [mut h, mut t] = [*tail; 2];
+                let new = match ::wopt::__private::read_pod::<u8>(bytes, &mut t)? {
+                    #(#de)*
+                    index => {
+                        return Err(::wopt::DecodeError::new(*tail, format!("unknown variant `{index}`")));
+                    }
+                };
+                #padding
+                *head = h;
+                *tail = t;
+                Ok(new)
+            },
+            id_og,
+        );
+        let ser = if is_const {
+            quote! {
+                pub fn serialize(&self) -> [u8; #id_len + Self::UNPADDED_SIZE] {
+                    let mut data = [0; #id_len + Self::UNPADDED_SIZE];
+                    let [mut h, mut t] = [0, #id_len + 1];
+                    data[..#id_len].copy_from_slice(&[#(#id_bytes),*]);
+                    match self {
+                        #(#ser)*
+                    }
+                    data
+                }
+
+                pub fn try_serialize(&self) -> Result<Vec<u8>, ::wopt::EncodeError> {
+                    Ok(self.serialize().into())
+                }
+            }
+        } else {
+            get_ser(quote! {
+                let mut data = Vec::with_capacity(#id_len + Self::UNPADDED_SIZE);
+                data.extend_from_slice(&[#(#id_bytes),*]);
+                match self {
+                    #(#ser)*
+                }
+                Ok(data)
+            })
+        };
+        quote! {
+            pub const ID: #id_ty = #id_lit;
+
+            #ser
+
+            #de
+        }
+    };
+
+    #[cfg(feature = "bytemuck")]
+    let de_opt = get_de(
+        quote! {
+            let [mut h, mut t] = [*tail; 2];
+            let new = match ::wopt::__private::read_pod::<u8>(bytes, &mut t)? {
+                u8::MAX => Self::#unmodified,
+                #(#de_opt)*
+                index => {
+                    return Err(::wopt::DecodeError::new(*tail, format!("unknown variant `{index}`")));
+                }
+            };
+            *head = h;
+            *tail = t;
+            Ok(new)
+        },
+        id_opt,
+    );
+
+    #[cfg(feature = "bytemuck")]
+    let ser_opt = get_ser(quote! {
+        let mut data = Vec::with_capacity(
+            #id_len_opt                     +   // identity bytes
+            1                               +   // variant index
+            ::core::mem::size_of::<#bits>() +   // bitmask data
+            Self::UNPADDED_SIZE                 // field(s) data
+        );
+        data.extend_from_slice(&[#(#id_bytes_opt),*]);
+        match self {
+            Self::#unmodified => data.push(u8::MAX),
+            #(#ser_opt)*
+        }
+        Ok(data)
+    });
+
+    #[cfg(feature = "bytemuck")]
+    let serde_opt = quote! {
+        pub const ID: #id_ty = #id_lit_opt;
+
+        #ser_opt
+        #de_opt
+    };
+
+    #[cfg(feature = "bytemuck")]
+    let [serde_where, serde_where_opt] = get_serde_where(generics, &pod_types, &[]);
+
+    // the largest variant (plus its index)
+    #[cfg(feature = "bytemuck")]
+    let size = quote! {
+        1 + {
+            let sizes = [#(#sizes),*];
+            let mut max = 0;
+            let mut i = 0;
+            while i < sizes.len() {
+                if sizes[i] > max {
+                    max = sizes[i];
+                }
+                i += 1;
+            }
+            max
+        }
+    };
+
+    #[cfg(feature = "bytemuck")]
+    let impl_name = {
+        // the identities of the optional types differ as long as those of the original ones do
+        let impl_wire = if _no_serde {
+            quote! {}
+        } else {
+            let marker = id_og.marker(name);
+            let impl_wire = get_wire_impl(input, name, &serde_where, id_og);
+            quote! {
+                #marker
+                #impl_wire
+            }
+        };
+        quote! {
+            #impl_name
+
+            impl #impl_generics #name #ty_generics #serde_where {
+                pub const UNPADDED_SIZE: usize = #size;
+
+                #serde_og
+            }
+
+            #impl_wire
+        }
+    };
+
+    #[cfg(feature = "bytemuck")]
+    let impl_name_opt = {
+        let impl_wire = get_wire_impl(input, &opt_name, &serde_where_opt, id_opt);
+        let impl_nested_wire = get_nested_wire_impl(input, &opt_name, &serde_where_opt);
+        let tracking = get_id_width_tracking(track_id_width);
+        quote! {
+            #impl_name_opt
+            #tracking
+
+            impl #impl_generics #opt_name #ty_generics #serde_where_opt {
+                pub const UNPADDED_SIZE: usize = #size;
+
+                #serde_opt
+            }
+
+            #impl_wire
+            #impl_nested_wire
+        }
+    };
+
+    let impl_traits = get_trait_impls(input, &opt_name);
+
+    let changes_name = Ident::new(&format!("{name}Changes"), Span::call_site().into());
+    let changes_doc = format!("Changes of a [`{name}`] patch (see `patch_tracked`).");
+    let switched = (infos.len() > 1).then(|| quote! { _ => changes.variant = true, });
+    let impl_track = get_track_impl(
+        input,
+        &opt_name,
+        &changes_name,
+        &get_diff_where(generics, &track_bounds),
+        quote! {
+            let mut changes = #changes_name::default();
+            match (self, undo) {
+                (_, #opt_name::#unmodified) => {}
+                #(#changes)*
+                #switched
+            }
+        },
+        on_change.as_ref(),
+    );
+    let impl_fields = get_fields_impl(&units, units.len(), name, &fields_name, &vis)?;
+    let impl_track = quote! {
+        #impl_track
+        #impl_fields
+
+        #[doc = #changes_doc]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        #vis struct #changes_name {
+            /// Whether the variant switched.
+            pub variant: bool,
+            /// The changed fields of the (unchanged) variant.
+            pub fields: #fields_name,
+        }
+
+        impl #changes_name {
+            /// Whether nothing changed.
+            pub const fn is_empty(&self) -> bool {
+                !self.variant && self.fields.is_empty()
+            }
+        }
+    };
+
+    let impl_diff = get_diff_impl(
+        input,
+        &opt_name,
+        &get_diff_where(generics, &diff_bounds),
+        quote! {
+            match (self, other) {
+                #(#diffs,)*
+                #(#diffs_switch)*
+            }
+        },
+    );
+
+    Ok(quote! {
+        #structure
+        #impl_name
+        #impl_name_opt
+        #impl_traits
+        #impl_diff
+        #impl_track
+    })
+}